@@ -44,6 +44,19 @@ pub fn get_bounding_box(vertices: &[Point4]) -> AxisAlignedBoundingBox {
     [bounds_min - EPSILON_VEC, bounds_max + EPSILON_VEC]
 }
 
+pub fn surface_area(bounds: &AxisAlignedBoundingBox) -> f64 {
+    let d = bounds[1] - bounds[0];
+    if d.x() < 0.0 || d.y() < 0.0 || d.z() < 0.0 {
+        return 0.0; // Empty bounds
+    }
+
+    2.0 * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+}
+
+pub fn centroid(bounds: &AxisAlignedBoundingBox) -> Point4 {
+    bounds[0].lerp(bounds[1], 0.5)
+}
+
 // From "An Efficient and Robust Ray–Box Intersection Algorithm"
 // by Amy Williams et al.
 #[inline(always)]
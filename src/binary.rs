@@ -0,0 +1,84 @@
+//! Little-endian reading and writing of primitive values, for binary file
+//! formats.
+
+use std::io::{self, Read, Write};
+
+/// Max length of a string read from a file.
+const MAX_STRING_LEN: usize = 1 << 16;
+
+pub trait WriteBinary: Write {
+    fn write_u32(&mut self, value: u32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, value: u64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_f32(&mut self, value: f32) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    fn write_f64(&mut self, value: f64) -> io::Result<()> {
+        self.write_all(&value.to_le_bytes())
+    }
+
+    /// Write a UTF-8 string, prefixed with its length.
+    fn write_str(&mut self, value: &str) -> io::Result<()> {
+        self.write_u32(value.len() as u32)?;
+        self.write_all(value.as_bytes())
+    }
+}
+
+impl<W: Write + ?Sized> WriteBinary for W {}
+
+pub trait ReadBinary: Read {
+    fn read_u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        self.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn read_u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        self.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    fn read_f32(&mut self) -> io::Result<f32> {
+        let mut bytes = [0; 4];
+        self.read_exact(&mut bytes)?;
+        Ok(f32::from_le_bytes(bytes))
+    }
+
+    fn read_f64(&mut self) -> io::Result<f64> {
+        let mut bytes = [0; 8];
+        self.read_exact(&mut bytes)?;
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    /// Read a string written by `write_str`.
+    fn read_str(&mut self) -> io::Result<String> {
+        let len = self.read_index(MAX_STRING_LEN)?;
+        let mut bytes = vec![0; len];
+        self.read_exact(&mut bytes)?;
+
+        String::from_utf8(bytes).map_err(|_| invalid_data("Invalid UTF-8 string"))
+    }
+
+    /// Read a `u32` length or index, failing if it's larger than `max`.
+    fn read_index(&mut self, max: usize) -> io::Result<usize> {
+        let value = self.read_u32()? as usize;
+        if value > max {
+            return Err(invalid_data("Index out of range"));
+        }
+
+        Ok(value)
+    }
+}
+
+impl<R: Read + ?Sized> ReadBinary for R {}
+
+pub fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
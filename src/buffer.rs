@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use image::{io::Reader as ImageReader, Pixel};
+use image::{io::Reader as ImageReader, DynamicImage, Pixel};
 
 use crate::vec4::{Color, Vec4};
 
@@ -28,7 +28,17 @@ impl Buffer {
     }
 
     pub fn from_image(file_path: &str) -> Result<Self, Box<dyn Error>> {
-        let image = ImageReader::open(file_path)?.decode()?.into_rgb32f();
+        Ok(Self::from_decoded(ImageReader::open(file_path)?.decode()?))
+    }
+
+    /// Decode an image file already loaded in memory, such as an image
+    /// embedded in a model file.
+    pub fn from_image_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::from_decoded(image::load_from_memory(bytes)?))
+    }
+
+    fn from_decoded(image: DynamicImage) -> Self {
+        let image = image.into_rgb32f();
 
         let width = image.width() as usize;
         let height = image.height() as usize;
@@ -40,11 +50,11 @@ impl Buffer {
             data.push(Vec4::vec(rgb[0] as f64, rgb[1] as f64, rgb[2] as f64));
         }
 
-        Ok(Buffer {
+        Buffer {
             width,
             height,
             data,
-        })
+        }
     }
 
     pub fn size(&self) -> (usize, usize) {
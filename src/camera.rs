@@ -1,3 +1,4 @@
+use std::f64::consts::PI;
 use std::sync::Arc;
 use std::thread;
 use std::time::Instant;
@@ -7,18 +8,66 @@ use rand_distr::Standard;
 use rand_pcg::Pcg64Mcg;
 
 use crate::buffer::Buffer;
-use crate::config::Config;
+use crate::config::{Config, LensConfig};
 use crate::interval::Interval;
 use crate::material::ScatterResult;
+use crate::medium::Fog;
 use crate::object::Hit;
 use crate::pdf::{HittablePDF, MixPDF, PDF};
 use crate::ray::Ray;
 use crate::vec4::{Color, Point4, Vec4};
 
+use animation::CameraAnimation;
+
+pub mod animation;
+pub mod aperture;
+
+/// How directions around the camera are mapped to the image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Thin lens perspective, with depth of field.
+    Perspective,
+    /// Parallel rays. The view covers the same area as the perspective view
+    /// on the focus plane.
+    Orthographic,
+    /// Fisheye lens, with a field of view set by the focal length.
+    Fisheye(FisheyeMapping),
+    /// 360° panorama, with longitude along X and latitude along Y, centered on
+    /// the view direction. Should be rendered with a 2:1 aspect ratio.
+    Equirectangular,
+}
+
+/// How a fisheye lens maps the angle from the view direction to the
+/// distance from the image center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FisheyeMapping {
+    /// Distance proportional to the angle, `r = f * theta`.
+    Equidistant,
+    /// Equal areas on the image cover equal solid angles,
+    /// `r = 2f * sin(theta / 2)`.
+    Equisolid,
+}
+
+impl Projection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "perspective" => Some(Projection::Perspective),
+            "orthographic" => Some(Projection::Orthographic),
+            "fisheye" | "fisheye_equidistant" => {
+                Some(Projection::Fisheye(FisheyeMapping::Equidistant))
+            }
+            "fisheye_equisolid" => Some(Projection::Fisheye(FisheyeMapping::Equisolid)),
+            "equirectangular" => Some(Projection::Equirectangular),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Camera {
     pub background_fn: fn(ray: &Ray) -> Color,
     background_color: Option<Color>,
+    fog: Option<Fog>,
 
     image_width: usize,
     aspect_ratio: f64,
@@ -28,11 +77,20 @@ pub struct Camera {
     position: Point4,
     look_at: Point4,
     v_up: Vec4,
+    roll: f64,
+    projection: Projection,
+    lens: LensConfig,
+    shutter: (f64, f64),
+    animation: Option<CameraAnimation>,
+    frame: usize,
 
     image_height: usize,
     pixel_delta: (Vec4, Vec4),
     first_pixel: Point4,
     basis: [Point4; 3],
+    focus_dist: f64,
+    viewport_size: (f64, f64),
+    focus_normal: Vec4,
     aperture_radius: Option<f64>,
 
     sqrt_spt: usize,
@@ -51,23 +109,51 @@ impl Camera {
         let samples_per_pixel = samples_per_thread * thread_count;
         let inv_sqrt_spt = 1.0 / sqrt_spt as f64;
 
+        let (position, look_at, v_up) = match config.scene.camera_transform {
+            Some(transform) => {
+                // Cameras look along their local -Z axis, with +Y up
+                let position = transform * Vec4::point(0.0, 0.0, 0.0);
+                let back = (transform * Vec4::vec(0.0, 0.0, 1.0)).to_unit();
+                let up = transform * Vec4::vec(0.0, 1.0, 0.0);
+                (position, position - back, up)
+            }
+            None => (
+                config.scene.camera_pos.unwrap(),
+                config.scene.camera_target.unwrap(),
+                config.scene.camera_up.unwrap(),
+            ),
+        };
+
         let mut camera = Camera {
             background_fn: |_| Vec4([0.0, 0.0, 0.0, 0.0]),
             background_color: config.scene.background,
+            fog: config.scene.fog.map(|fog| Fog::new(&fog)),
 
             image_width: config.scene.output_width.unwrap(),
             aspect_ratio: config.scene.aspect_ratio.unwrap(),
             focal_length: config.scene.focal_length.unwrap(),
             f_number: config.scene.f_number,
             focus_distance: config.scene.focus_distance,
-            position: config.scene.camera_pos.unwrap(),
-            look_at: config.scene.camera_target.unwrap(),
-            v_up: Vec4::vec(0.0, 1.0, 0.0),
+            position,
+            look_at,
+            v_up,
+            roll: config.scene.camera_roll.unwrap(),
+            projection: config.scene.projection.unwrap(),
+            lens: config.scene.lens.clone().unwrap_or_default(),
+            shutter: (
+                config.scene.shutter_open.unwrap(),
+                config.scene.shutter_close.unwrap(),
+            ),
+            animation: None,
+            frame: 0,
 
             image_height: 0,
             basis: [Vec4::vec(0.0, 0.0, 0.0); 3],
             pixel_delta: (Vec4::vec(0.0, 0.0, 0.0), Vec4::vec(0.0, 0.0, 0.0)),
             first_pixel: Vec4::point(0.0, 0.0, 0.0),
+            focus_dist: 0.0,
+            viewport_size: (0.0, 0.0),
+            focus_normal: Vec4::vec(0.0, 0.0, 1.0),
             aperture_radius: None,
 
             sqrt_spt,
@@ -96,11 +182,17 @@ impl Camera {
         let real_aspect_ratio = self.image_width as f64 / self.image_height as f64;
         let viewport_height = focus_dist * h;
         let viewport_width = viewport_height * real_aspect_ratio;
+        self.focus_dist = focus_dist;
+        self.viewport_size = (viewport_width, viewport_height);
 
         // Calculate the unit basis for the camera coordinate frame
         let w = direction.to_unit();
-        let u = self.v_up.cross(&w);
+        let u = image_right(self.v_up, w);
         let v = w.cross(&u);
+
+        // Rolling the camera turns the image axes around the view direction
+        let (sin_roll, cos_roll) = self.roll.sin_cos();
+        let (u, v) = (u * cos_roll + v * sin_roll, v * cos_roll - u * sin_roll);
         self.basis = [u, v, w];
 
         // Calculate viewport vectors
@@ -114,8 +206,18 @@ impl Camera {
 
         // Make the image plane match the focus plane, makes the math a lot easier
         // Not how a real camera works, but we're not constrained by the laws of physics!
+        // Shifting the lens moves the image on the sensor, without turning the
+        // camera
+        let mm_to_viewport = viewport_height / 24.0;
+        let (shift_x, shift_y) = self.lens.shift;
+        let shift = (u * shift_x + v * shift_y) * mm_to_viewport;
+
         let viewport_upper_left =
-            self.position - w * focus_dist - viewport_u / 2.0 - viewport_v / 2.0;
+            self.position - w * focus_dist + shift - viewport_u / 2.0 - viewport_v / 2.0;
+
+        // Tilting the lens tilts the focus plane around the center of the image
+        let (tilt_x, tilt_y) = self.lens.focus_tilt;
+        self.focus_normal = (w + v * tilt_x.tan() + u * tilt_y.tan()).to_unit();
 
         // Top-left pixel, shifted half a pixel from the top left corner of the viewport
         self.first_pixel = viewport_upper_left + (self.pixel_delta.0 + self.pixel_delta.1) * 0.5;
@@ -178,85 +280,126 @@ impl Camera {
         self.init();
     }
 
+    pub fn set_up(&mut self, up: Vec4) {
+        self.v_up = up;
+        self.init();
+    }
+
+    /// Counterclockwise rotation around the view direction, in radians
+    pub fn set_roll(&mut self, roll: f64) {
+        self.roll = roll;
+        self.init();
+    }
+
     pub fn move_and_look_at(&mut self, pos: Point4, target: Point4) {
         self.position = pos;
         self.look_at = target;
         self.init();
     }
 
-    // Rendering
+    /// Animate the camera with keyframes. Takes effect on the next call to
+    /// `set_frame`.
+    pub fn set_animation(&mut self, animation: CameraAnimation) {
+        self.animation = Some(animation);
+    }
 
-    pub fn render(self, world: Arc<dyn Hit>, lights: Arc<dyn Hit>, buf: &mut Buffer) {
-        let mut threads = Vec::new();
+    /// Move to a frame of the animation. Scene time is counted in frames, so
+    /// this also moves the shutter interval: frame `n` is exposed from time
+    /// `n + shutter_open` to `n + shutter_close`.
+    pub fn set_frame(&mut self, frame: usize) {
+        self.frame = frame;
+
+        if let Some(animation) = &self.animation {
+            let key = animation.at(frame as f64);
+            self.position = key.position.unwrap_or(self.position);
+            self.look_at = key.target.unwrap_or(self.look_at);
+            self.roll = key.roll.unwrap_or(self.roll);
+            self.focal_length = key.focal_length.unwrap_or(self.focal_length);
+            self.focus_distance = key.focus_distance.or(self.focus_distance);
+            self.f_number = key.f_number.or(self.f_number);
+            self.init();
+        }
+    }
+
+    // Rendering
 
+    /// Render the current frame. The camera can be reused to render other
+    /// frames of the same scene.
+    pub fn render(&self, world: Arc<dyn Hit>, lights: Arc<dyn Hit>, buf: &mut Buffer) {
         let sqrt_spt = self.sqrt_spt;
         let max_depth = self.max_depth;
         let samples_per_pixel = self.samples_per_pixel;
-        let self_ref = Arc::new(self);
-
-        for tid in 0..self_ref.thread_count {
-            let mut thread_buf = self_ref.create_buffer();
-            let thread_world = Arc::clone(&world);
-            let thread_lights = Arc::clone(&lights);
-            let thread_self_ref = Arc::clone(&self_ref);
-
-            let mut lights_pdf = HittablePDF::new(thread_lights, Vec4::point(0.0, 0.0, 0.0));
-
-            let thread = thread::spawn(move || {
-                let time = Instant::now();
-
-                let mut thread_rng =
-                    Pcg64Mcg::from_rng(rand::thread_rng()).expect("Failed to init RNG");
-                let image_height = thread_self_ref.image_height;
-                let image_width = thread_self_ref.image_width;
-
-                for y in 0..image_height {
-                    for x in 0..image_width {
-                        let mut color = Vec4::vec(0.0, 0.0, 0.0);
-
-                        for sy in 0..sqrt_spt {
-                            for sx in 0..sqrt_spt {
-                                let ray = thread_self_ref.get_ray(x, y, sx, sy, &mut thread_rng);
-                                color += thread_self_ref.ray_color(
-                                    &ray,
-                                    &thread_world,
-                                    &mut lights_pdf,
-                                    max_depth,
-                                    &mut thread_rng,
-                                );
+
+        thread::scope(|scope| {
+            let mut threads = Vec::new();
+
+            for tid in 0..self.thread_count {
+                let mut thread_buf = self.create_buffer();
+                let thread_world = Arc::clone(&world);
+                let thread_lights = Arc::clone(&lights);
+
+                let mut lights_pdf = HittablePDF::new(thread_lights, Vec4::point(0.0, 0.0, 0.0));
+
+                let thread = scope.spawn(move || {
+                    let time = Instant::now();
+
+                    let mut thread_rng =
+                        Pcg64Mcg::from_rng(rand::thread_rng()).expect("Failed to init RNG");
+
+                    for y in 0..self.image_height {
+                        for x in 0..self.image_width {
+                            let mut color = Vec4::vec(0.0, 0.0, 0.0);
+
+                            for sy in 0..sqrt_spt {
+                                for sx in 0..sqrt_spt {
+                                    let Some((ray, weight)) =
+                                        self.get_ray(x, y, sx, sy, &mut thread_rng)
+                                    else {
+                                        continue; // Outside the image, stays black
+                                    };
+                                    color += self.ray_color(
+                                        &ray,
+                                        &thread_world,
+                                        &mut lights_pdf,
+                                        max_depth,
+                                        &mut thread_rng,
+                                    ) * weight;
+                                }
                             }
-                        }
-                        color /= samples_per_pixel as f64;
+                            color /= samples_per_pixel as f64;
 
-                        thread_buf.set_pixel(x, y, color);
+                            thread_buf.set_pixel(x, y, color);
+                        }
                     }
-                }
 
-                let elapsed = time.elapsed();
-                println!("Thread {tid} finished in {:.2?}", elapsed);
-                thread_buf
-            });
+                    let elapsed = time.elapsed();
+                    println!("Thread {tid} finished in {:.2?}", elapsed);
+                    thread_buf
+                });
 
-            threads.push(thread);
-        }
+                threads.push(thread);
+            }
 
-        for thread in threads {
-            let thread_buf = thread.join().expect("Thread failed!");
+            for thread in threads {
+                let thread_buf = thread.join().expect("Thread failed!");
 
-            // Add thread buffer to main buffer
-            for y in 0..self_ref.image_height {
-                for x in 0..self_ref.image_width {
-                    let thread_color = thread_buf.get_pixel(x, y);
-                    let local_color = buf.get_pixel(x, y);
+                // Add thread buffer to main buffer
+                for y in 0..self.image_height {
+                    for x in 0..self.image_width {
+                        let thread_color = thread_buf.get_pixel(x, y);
+                        let local_color = buf.get_pixel(x, y);
 
-                    buf.set_pixel(x, y, local_color + thread_color);
+                        buf.set_pixel(x, y, local_color + thread_color);
+                    }
                 }
             }
-        }
+        });
     }
 
     // Rendering helpers
 
+    /// Returns the ray with the weight of its color channels, or `None` for
+    /// pixels outside the image circle of a fisheye lens.
     fn get_ray(
         &self,
         pixel_x: usize,
@@ -264,19 +407,125 @@ impl Camera {
         sample_x: usize,
         sample_y: usize,
         rng: &mut Pcg64Mcg,
-    ) -> Ray {
+    ) -> Option<(Ray, Color)> {
         let pixel_center = self.first_pixel
             + (self.pixel_delta.0 * pixel_x as f64)
             + (self.pixel_delta.1 * pixel_y as f64);
-        let pixel_sample = pixel_center + self.pixel_sample_square(sample_x, sample_y, rng);
+        let mut pixel_sample = pixel_center + self.pixel_sample_square(sample_x, sample_y, rng);
+
+        // With chromatic aberration, each ray only carries one channel, as each
+        // channel's image has a different size
+        let mut weight = Vec4::vec(1.0, 1.0, 1.0);
+        if self.lens.chromatic_aberration != 0.0 {
+            let channel = rng.gen_range(0..3);
+            let magnification = 1.0 + self.lens.chromatic_aberration * (1.0 - channel as f64);
+            let image_center = self.position - self.basis[2] * self.focus_dist;
+            pixel_sample = image_center + (pixel_sample - image_center) / magnification;
+
+            weight = Vec4::vec(0.0, 0.0, 0.0);
+            weight[channel] = 3.0;
+        }
 
-        let ray_origin = match self.aperture_radius {
-            Some(_) => self.defocus_disk_sample(rng),
-            None => self.position,
+        let (ray_origin, ray_direction) = match self.projection {
+            Projection::Perspective => {
+                let focus_point = self.focus_point(self.position, pixel_sample - self.position);
+                let ray_origin = match self.aperture_radius {
+                    Some(_) => self.aperture_sample(rng),
+                    None => self.position,
+                };
+                (ray_origin, focus_point - ray_origin)
+            }
+            Projection::Orthographic => {
+                // Rays leave a sensor as large as the focus plane, and go through
+                // the lens to converge on the focus plane
+                let sensor_point = pixel_sample + self.basis[2] * self.focus_dist;
+                let focus_point = self.focus_point(sensor_point, pixel_sample - sensor_point);
+                let ray_origin = match self.aperture_radius {
+                    Some(_) => sensor_point + (self.aperture_sample(rng) - self.position),
+                    None => sensor_point,
+                };
+                (ray_origin, focus_point - ray_origin)
+            }
+            projection => (
+                self.position,
+                self.wide_angle_direction(projection, pixel_sample)?,
+            ),
         };
-        let ray_direction = pixel_sample - ray_origin;
 
-        Ray::new(ray_origin, ray_direction)
+        let (open, close) = self.shutter;
+        let shutter_time = if close > open {
+            let r: f64 = rng.sample(Standard);
+            open + r * (close - open)
+        } else {
+            open
+        };
+        let time = self.frame as f64 + shutter_time;
+
+        Some((Ray::new(ray_origin, ray_direction).with_time(time), weight))
+    }
+
+    /// Where the ray from the center of the lens through a point of the image
+    /// meets the focus plane, which may be tilted.
+    fn focus_point(&self, origin: Point4, dir: Vec4) -> Point4 {
+        let plane_center = self.position - self.basis[2] * self.focus_dist;
+        let t = (plane_center - origin).dot(&self.focus_normal) / dir.dot(&self.focus_normal);
+
+        // Points whose rays miss the focus plane are focused at the image plane
+        if t.is_finite() && t > 0.0 {
+            origin + dir * t
+        } else {
+            origin + dir
+        }
+    }
+
+    /// Direction of the ray through a point of the focus plane, for the
+    /// projections that aren't limited to a half-space in front of the camera.
+    fn wide_angle_direction(&self, projection: Projection, pixel_sample: Point4) -> Option<Vec4> {
+        let [u, v, w] = self.basis;
+        let (viewport_width, viewport_height) = self.viewport_size;
+
+        // Offset from the center of the image, and position in [-0.5; 0.5]
+        // along each axis, with Y up
+        let offset = pixel_sample - (self.position - w * self.focus_dist);
+        let (x, y) = (
+            offset.dot(&u) / viewport_width,
+            offset.dot(&v) / viewport_height,
+        );
+
+        match projection {
+            Projection::Equirectangular => {
+                // Longitude from the view direction, and angle from the up vector
+                let phi = x * 2.0 * PI;
+                let theta = (0.5 - y) * PI;
+
+                Some((-w * phi.cos() + u * phi.sin()) * theta.sin() + v * theta.cos())
+            }
+            Projection::Fisheye(mapping) => {
+                // Distance from the center of a 36x24mm sensor, in mm
+                let r = offset.length() * 24.0 / viewport_height;
+                let theta = match mapping {
+                    FisheyeMapping::Equidistant => r / self.focal_length,
+                    FisheyeMapping::Equisolid => {
+                        let sin_half_theta = r / (2.0 * self.focal_length);
+                        if sin_half_theta > 1.0 {
+                            return None;
+                        }
+                        2.0 * sin_half_theta.asin()
+                    }
+                };
+                if theta > PI {
+                    return None;
+                }
+
+                let radial = if r > 0.0 {
+                    offset.to_unit()
+                } else {
+                    Vec4::vec(0.0, 0.0, 0.0)
+                };
+                Some(-w * theta.cos() + radial * theta.sin())
+            }
+            _ => Some(pixel_sample - self.position),
+        }
     }
 
     fn ray_color(
@@ -291,8 +540,18 @@ impl Camera {
             return Vec4::vec(0.0, 0.0, 0.0);
         }
 
-        if let Some(hit) = object.test(ray, Interval(0.001, f64::INFINITY), rng) {
-            let from_emission = hit.material().emit(&hit);
+        let mut hit = object.test(ray, Interval(0.001, f64::INFINITY), rng);
+
+        // Scattering in fog before reaching the closest surface replaces the hit
+        if let Some(fog) = &self.fog {
+            let t_max = hit.as_ref().map_or(f64::INFINITY, |hit| hit.t());
+            if let Some(fog_hit) = fog.test(ray, t_max, rng) {
+                hit = Some(fog_hit);
+            }
+        }
+
+        if let Some(hit) = hit {
+            let from_emission = hit.material().emit(ray, &hit);
 
             return match hit.material().scatter(ray, &hit, rng) {
                 ScatterResult::ScatteredWithPDF {
@@ -302,9 +561,15 @@ impl Camera {
                     lights_pdf.origin = hit.pos();
                     let mix_pdf = MixPDF::new(material_pdf.as_ref(), lights_pdf, self.light_bias);
 
-                    let scattered = Ray::new(hit.pos(), mix_pdf.generate(rng));
+                    let scattered = ray.spawn(hit.pos(), mix_pdf.generate(rng));
                     let pdf = mix_pdf.value(&scattered.dir(), rng);
 
+                    // Lights that can't be sampled (such as meshes) give
+                    // directions neither PDF can produce
+                    if pdf <= 0.0 {
+                        return from_emission;
+                    }
+
                     let scattering_pdf = hit.material().scattering_pdf(ray, &scattered, &hit);
 
                     let scatter_color =
@@ -340,11 +605,48 @@ impl Camera {
         self.pixel_delta.0 * x + self.pixel_delta.1 * y
     }
 
+    /// Random point on the lens aperture.
+    ///
     /// # Panics
     /// Panics if aperture_radius is `None`. Caller should make sure aperture radius is set.
-    fn defocus_disk_sample(&self, rng: &mut Pcg64Mcg) -> Vec4 {
-        let v = Vec4::random_in_unit_disk(rng);
+    fn aperture_sample(&self, rng: &mut Pcg64Mcg) -> Vec4 {
+        let (x, y) = self.lens.aperture.sample(rng);
+        let (sin, cos) = self.lens.aperture_rotation.sin_cos();
+        let (x, y) = (x * cos - y * sin, x * sin + y * cos);
+
         self.position
-            + (self.basis[0] * v[0] + self.basis[1] * v[1]) * self.aperture_radius.unwrap()
+            + (self.basis[0] * (x / self.lens.anamorphic_squeeze) + self.basis[1] * y)
+                * self.aperture_radius.unwrap()
+    }
+}
+
+/// Unit vector pointing right in the image of a camera looking along `-w`,
+/// with the top of the image turned towards `up`.
+pub(crate) fn image_right(up: Vec4, w: Vec4) -> Vec4 {
+    let up = up.to_unit();
+    let right = up.cross(&w);
+    if right.length() < 1e-6 {
+        return vertical_view_up(up, w).cross(&w).to_unit();
+    }
+
+    right.to_unit()
+}
+
+/// Up vector to use when looking straight along `up`, where it doesn't set a
+/// direction for the image. The top of the image points where the camera
+/// would have tipped from: -Z when looking down with +Y up, +Z when looking up.
+fn vertical_view_up(up: Vec4, w: Vec4) -> Vec4 {
+    let reference = if up.x().abs() < 0.9 {
+        Vec4::vec(1.0, 0.0, 0.0)
+    } else {
+        Vec4::vec(0.0, 0.0, 1.0)
+    };
+    let forward = up.cross(&reference).to_unit();
+
+    // The camera looks along -w, so w points up when looking down
+    if w.dot(&up) > 0.0 {
+        forward
+    } else {
+        -forward
     }
 }
@@ -0,0 +1,83 @@
+//! Keyframed camera parameters, for rendering frame sequences.
+
+use crate::vec4::Point4;
+
+/// Camera parameters at a given frame. Parameters left unset are
+/// interpolated from the keyframes around it that set them.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CameraKeyframe {
+    pub frame: f64,
+    pub position: Option<Point4>,
+    pub target: Option<Point4>,
+    /// Counterclockwise rotation around the view direction, in radians.
+    pub roll: Option<f64>,
+    pub focal_length: Option<f64>,
+    pub focus_distance: Option<f64>,
+    pub f_number: Option<f64>,
+}
+
+impl CameraKeyframe {
+    pub fn new(frame: f64) -> Self {
+        CameraKeyframe {
+            frame,
+            ..Default::default()
+        }
+    }
+}
+
+/// A camera animation. Each parameter is interpolated linearly between the
+/// keyframes that set it, and held before the first one and after the last.
+#[derive(Debug)]
+pub struct CameraAnimation {
+    keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraAnimation {
+    pub fn new(mut keyframes: Vec<CameraKeyframe>) -> Self {
+        keyframes.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+        CameraAnimation { keyframes }
+    }
+
+    /// Parameters at the given frame. Parameters that no keyframe sets are
+    /// left unset.
+    pub fn at(&self, frame: f64) -> CameraKeyframe {
+        CameraKeyframe {
+            frame,
+            position: self.track(frame, |key| key.position, |a, b, s| a.lerp(b, s)),
+            target: self.track(frame, |key| key.target, |a, b, s| a.lerp(b, s)),
+            roll: self.track(frame, |key| key.roll, lerp),
+            focal_length: self.track(frame, |key| key.focal_length, lerp),
+            focus_distance: self.track(frame, |key| key.focus_distance, lerp),
+            f_number: self.track(frame, |key| key.f_number, lerp),
+        }
+    }
+
+    fn track<T: Copy>(
+        &self,
+        frame: f64,
+        get: impl Fn(&CameraKeyframe) -> Option<T>,
+        lerp: impl Fn(T, T, f64) -> T,
+    ) -> Option<T> {
+        let mut prev = None;
+        for key in &self.keyframes {
+            let Some(value) = get(key) else {
+                continue;
+            };
+
+            if key.frame > frame {
+                let Some((prev_frame, prev_value)) = prev else {
+                    return Some(value);
+                };
+                let s = (frame - prev_frame) / (key.frame - prev_frame);
+                return Some(lerp(prev_value, value, s));
+            }
+            prev = Some((key.frame, value));
+        }
+
+        prev.map(|(_, value)| value)
+    }
+}
+
+fn lerp(a: f64, b: f64, s: f64) -> f64 {
+    a + (b - a) * s
+}
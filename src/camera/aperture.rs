@@ -0,0 +1,69 @@
+//! Aperture shapes, setting the shape of out of focus highlights (bokeh).
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_distr::Standard;
+use rand_pcg::Pcg64Mcg;
+
+use crate::pdf::Distribution2D;
+use crate::texture::Sampler;
+use crate::vec4::{Color, Vec4};
+
+/// Resolution of the grid aperture masks are sampled on.
+const MASK_RESOLUTION: usize = 128;
+
+/// Shape of the lens aperture, scaled to the unit disk.
+#[derive(Clone, Debug, Default)]
+pub enum Aperture {
+    /// A round aperture, as with a wide open lens.
+    #[default]
+    Disk,
+    /// A regular polygon with a corner on each blade, as with a stopped
+    /// down lens. Its corners are on the unit circle, starting from +X.
+    Polygon(usize),
+    /// An image over the [-1; 1]² square, letting light through in
+    /// proportion to its brightness.
+    Mask(Arc<Distribution2D>),
+}
+
+impl Aperture {
+    pub fn from_mask(mask: &dyn Sampler<Output = Color>) -> Self {
+        let distribution = Distribution2D::from_fn(MASK_RESOLUTION, MASK_RESOLUTION, |uv| {
+            mask.sample(uv, &Vec4::point(0.0, 0.0, 0.0)).luminance()
+        });
+
+        Aperture::Mask(Arc::new(distribution))
+    }
+
+    /// Sample a point on the aperture uniformly, as `(x, y)` coordinates with
+    /// Y up.
+    pub fn sample(&self, rng: &mut Pcg64Mcg) -> (f64, f64) {
+        match self {
+            Aperture::Disk => {
+                let v = Vec4::random_in_unit_disk(rng);
+                (v[0], v[1])
+            }
+            Aperture::Polygon(blades) => {
+                // The triangles between the center and each side have the same
+                // area, so pick one uniformly, then a point in it
+                let r: f64 = rng.sample(Standard);
+                let side = ((r * *blades as f64) as usize).min(blades - 1);
+                let step = 2.0 * PI / *blades as f64;
+                let (a0, a1) = (side as f64 * step, (side + 1) as f64 * step);
+
+                let (mut s, mut t): (f64, f64) = (rng.sample(Standard), rng.sample(Standard));
+                if s + t > 1.0 {
+                    (s, t) = (1.0 - s, 1.0 - t);
+                }
+
+                (s * a0.cos() + t * a1.cos(), s * a0.sin() + t * a1.sin())
+            }
+            Aperture::Mask(distribution) => {
+                let ((u, v), _) = distribution.sample(rng.sample(Standard), rng.sample(Standard));
+                (2.0 * u - 1.0, 1.0 - 2.0 * v)
+            }
+        }
+    }
+}
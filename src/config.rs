@@ -3,6 +3,10 @@ use std::env::Args;
 use regex::Regex;
 
 use crate::{utils::parse_vec, vec4::Vec4};
+use crate::camera::aperture::Aperture;
+use crate::camera::Projection;
+use crate::mat4::Mat4;
+use crate::utils::{deg_to_rad, parse_transform};
 use crate::vec4::Color;
 
 #[derive(Debug)]
@@ -14,7 +18,79 @@ pub struct SceneConfig {
     pub focus_distance: Option<f64>,
     pub camera_pos: Option<Vec4>,
     pub camera_target: Option<Vec4>,
+    /// Direction the top of the image points towards, before roll.
+    pub camera_up: Option<Vec4>,
+    /// Counterclockwise rotation of the camera around its view direction, in
+    /// radians.
+    pub camera_roll: Option<f64>,
+    /// Camera to world matrix, for cameras looking along their local -Z axis
+    /// with +Y up. Overrides the camera position, target and up vector.
+    pub camera_transform: Option<Mat4>,
     pub background: Option<Color>,
+    pub fog: Option<FogConfig>,
+    /// Time the shutter opens. Each camera ray gets a random time between
+    /// shutter open and close, blurring objects that move in between.
+    pub shutter_open: Option<f64>,
+    pub shutter_close: Option<f64>,
+    pub projection: Option<Projection>,
+    pub lens: Option<LensConfig>,
+}
+
+/// Scene-wide fog settings. See `Fog` for details.
+#[derive(Clone, Copy, Debug)]
+pub struct FogConfig {
+    pub density: f64,
+    pub albedo: Color,
+    /// Henyey-Greenstein asymmetry parameter, 0 for isotropic scattering.
+    pub g: f64,
+    /// Exponential falloff rate of density with height, 0 for uniform fog.
+    pub height_falloff: f64,
+    /// Height below which density stays constant.
+    pub base_height: f64,
+}
+
+impl FogConfig {
+    pub fn new(density: f64) -> Self {
+        FogConfig {
+            density,
+            albedo: Vec4([1.0, 1.0, 1.0, 0.0]),
+            g: 0.0,
+            height_falloff: 0.0,
+            base_height: 0.0,
+        }
+    }
+}
+
+/// Lens settings beyond focal length and aperture size.
+#[derive(Clone, Debug)]
+pub struct LensConfig {
+    pub aperture: Aperture,
+    /// Counterclockwise rotation of the aperture shape, in radians.
+    pub aperture_rotation: f64,
+    /// Rotation of the focus plane around the horizontal and vertical axes
+    /// of the image, in radians.
+    pub focus_tilt: (f64, f64),
+    /// Offset of the image on the sensor in mm, with Y up.
+    pub shift: (f64, f64),
+    /// How much narrower the aperture is horizontally than vertically, 1
+    /// for a spherical lens.
+    pub anamorphic_squeeze: f64,
+    /// Lateral chromatic aberration: the red and blue images are magnified
+    /// by `1 + chromatic_aberration` and `1 - chromatic_aberration`.
+    pub chromatic_aberration: f64,
+}
+
+impl Default for LensConfig {
+    fn default() -> Self {
+        LensConfig {
+            aperture: Aperture::Disk,
+            aperture_rotation: 0.0,
+            focus_tilt: (0.0, 0.0),
+            shift: (0.0, 0.0),
+            anamorphic_squeeze: 1.0,
+            chromatic_aberration: 0.0,
+        }
+    }
 }
 
 pub const DEFAULT_SCENE_CONFIG: SceneConfig = SceneConfig {
@@ -25,7 +101,15 @@ pub const DEFAULT_SCENE_CONFIG: SceneConfig = SceneConfig {
     focus_distance: None,
     camera_pos: Some(Vec4([0.0, 0.0, 1.0, 1.0])),
     camera_target: Some(Vec4([0.0, 0.0, 0.0, 1.0])),
+    camera_up: Some(Vec4([0.0, 1.0, 0.0, 0.0])),
+    camera_roll: Some(0.0),
+    camera_transform: None,
     background: Some(Vec4([0.0, 0.0, 0.0, 1.0])),
+    fog: None,
+    shutter_open: Some(0.0),
+    shutter_close: Some(0.0),
+    projection: Some(Projection::Perspective),
+    lens: None,
 };
 
 impl SceneConfig {
@@ -38,7 +122,25 @@ impl SceneConfig {
             focus_distance: overrides.focus_distance.or(base.focus_distance),
             camera_pos: overrides.camera_pos.or(base.camera_pos),
             camera_target: overrides.camera_target.or(base.camera_target),
+            camera_up: overrides.camera_up.or(base.camera_up),
+            camera_roll: overrides.camera_roll.or(base.camera_roll),
+            // Placing the camera by hand replaces a transform from the base
+            camera_transform: overrides.camera_transform.or(
+                if overrides.camera_pos.is_some()
+                    || overrides.camera_target.is_some()
+                    || overrides.camera_up.is_some()
+                {
+                    None
+                } else {
+                    base.camera_transform
+                },
+            ),
             background: overrides.background.or(base.background),
+            fog: overrides.fog.or(base.fog),
+            shutter_open: overrides.shutter_open.or(base.shutter_open),
+            shutter_close: overrides.shutter_close.or(base.shutter_close),
+            projection: overrides.projection.or(base.projection),
+            lens: overrides.lens.clone().or_else(|| base.lens.clone()),
         }
     }
 }
@@ -56,6 +158,8 @@ pub struct Config {
     pub scene: SceneConfig,
     pub camera: CameraConfig,
     pub scene_name: String,
+    /// First and last frame to render as an image sequence, if set.
+    pub frames: Option<(usize, usize)>,
 }
 
 impl Config {
@@ -69,7 +173,14 @@ impl Config {
         let mut focus_distance: Option<f64> = None;
         let mut camera_pos: Option<Vec4> = None;
         let mut camera_target: Option<Vec4> = None;
+        let mut camera_up: Option<Vec4> = None;
+        let mut camera_roll: Option<f64> = None;
+        let mut camera_transform: Option<Mat4> = None;
         let mut background: Option<Vec4> = None;
+        let mut fog: Option<FogConfig> = None;
+        let mut shutter_open: Option<f64> = None;
+        let mut shutter_close: Option<f64> = None;
+        let mut projection: Option<Projection> = None;
 
         let mut thread_count = 1;
         let mut samples_per_pixel = 250;
@@ -77,6 +188,7 @@ impl Config {
         let mut light_bias = 0.25;
 
         let mut scene_name = String::new();
+        let mut frames: Option<(usize, usize)> = None;
 
         for arg in args.skip(1) {
             if arg.starts_with("-") {
@@ -116,6 +228,21 @@ impl Config {
                             let [x, y, z] = parse_vec(value).unwrap();
                             camera_target = Some(Vec4::point(x, y, z));
                         }
+                        "u" | "-camera-up" => {
+                            let [x, y, z] = parse_vec(value).unwrap();
+                            assert!(
+                                x != 0.0 || y != 0.0 || z != 0.0,
+                                "Camera up vector must not be zero"
+                            );
+                            camera_up = Some(Vec4::vec(x, y, z));
+                        }
+                        "-camera-roll" => {
+                            let roll = value.parse::<f64>().expect("Camera roll must be a number");
+                            camera_roll = Some(deg_to_rad(roll));
+                        }
+                        "-camera-transform" => {
+                            camera_transform = Some(parse_transform(value).unwrap());
+                        }
                         "t" | "-threads" => {
                             thread_count = value
                                 .parse::<usize>()
@@ -130,6 +257,42 @@ impl Config {
                             let [r, g, b] = parse_vec(value).unwrap();
                             background = Some(Vec4::point(r, g, b));
                         }
+                        "-fog" => {
+                            let density =
+                                value.parse::<f64>().expect("Fog density must be a number");
+                            assert!(density >= 0.0, "Fog density can't be negative");
+                            fog = Some(FogConfig::new(density));
+                        }
+                        "-shutter-open" => {
+                            shutter_open = Some(
+                                value
+                                    .parse::<f64>()
+                                    .expect("Shutter open time must be a number"),
+                            );
+                        }
+                        "-shutter-close" => {
+                            shutter_close = Some(
+                                value
+                                    .parse::<f64>()
+                                    .expect("Shutter close time must be a number"),
+                            );
+                        }
+                        "-projection" => {
+                            projection =
+                                Some(Projection::from_name(value).expect("Unknown projection"));
+                        }
+                        "-frames" => {
+                            let (first, last) = value.split_once('-').unwrap_or((value, value));
+                            let first = first
+                                .parse::<usize>()
+                                .expect("Frame numbers must be positive integers");
+                            let last = last
+                                .parse::<usize>()
+                                .expect("Frame numbers must be positive integers");
+
+                            assert!(first <= last, "Frame range must not be empty");
+                            frames = Some((first, last));
+                        }
                         "-max-depth" => {
                             max_depth = value
                                 .parse::<usize>()
@@ -163,7 +326,15 @@ impl Config {
                 focus_distance,
                 camera_pos,
                 camera_target,
+                camera_up,
+                camera_roll,
+                camera_transform,
                 background,
+                fog,
+                shutter_open,
+                shutter_close,
+                projection,
+                lens: None,
             },
             camera: CameraConfig {
                 thread_count,
@@ -172,6 +343,7 @@ impl Config {
                 light_bias,
             },
             scene_name,
+            frames,
         }
     }
 }
@@ -1,4 +1,5 @@
 pub mod aabb;
+pub mod binary;
 pub mod buffer;
 pub mod camera;
 pub mod config;
@@ -7,6 +8,7 @@ pub mod interval;
 pub mod loaders;
 pub mod mat4;
 pub mod material;
+pub mod medium;
 pub mod noise;
 pub mod object;
 pub mod output;
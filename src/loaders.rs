@@ -1,3 +1,11 @@
+pub mod contents;
+pub mod gltf;
+pub mod json;
+pub mod mtl;
 pub mod obj;
+pub mod ply;
+pub mod polymesh;
+pub mod punctual;
+pub mod rtmesh;
 pub mod scene;
 pub mod assimp;
@@ -1,203 +1,510 @@
+//! Scene import through assimp, for formats without a native loader (FBX,
+//! Collada, 3DS, ...).
+//!
+//! Loads the node hierarchy with its meshes, the first camera and the
+//! scene's point, spot, directional and area lights.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
 use std::error::Error;
+use std::f64::consts::PI;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use std::sync::Arc;
 
-use rand_distr::num_traits::ToPrimitive;
-use russimp::material::{Material as AssimpMaterial, PropertyTypeInfo, TextureType};
+use russimp::camera::Camera as AssimpCamera;
+use russimp::light::{Light as AssimpLight, LightSourceType};
+use russimp::material::{DataContent, Material as AssimpMaterial, PropertyTypeInfo, TextureType};
 use russimp::mesh::Mesh as AssimpMesh;
 use russimp::node::Node;
 use russimp::scene::{PostProcess, Scene};
+use russimp::Vector3D;
 
-use crate::camera::Camera;
-use crate::config::{Config, DEFAULT_SCENE_CONFIG, SceneConfig};
+use crate::buffer::Buffer;
+use crate::camera::{image_right, Camera};
+use crate::config::{Config, SceneConfig, DEFAULT_SCENE_CONFIG};
 use crate::mat4::Mat4;
-use crate::material::{Emissive, Glossy};
 use crate::material::Material;
-use crate::object::{Hit, ObjectList, Sphere, Transform};
+use crate::material::{Dielectric, Emissive, Glossy, Metal};
 use crate::object::mesh::{Triangle, TriangleMesh};
+use crate::object::{Hit, ObjectList, Plane, Sun, Transform};
 use crate::scene::SceneData;
-use crate::texture::{Channel, ConstantTexture, ImageTexture, TexturePointer};
+use crate::texture::{Channel, ConstantTexture, ImageTexture, Scale, TexturePointer};
 use crate::utils::ParseError;
 use crate::vec4::{Color, Point4, Vec4};
 
+use super::contents;
+use super::punctual::{point_light, spot_light};
+
 pub struct AssimpLoader {
     scene: Scene,
-    base_path: String,
+    base_path: PathBuf,
+}
+
+type SceneContents = contents::SceneContents<Arc<dyn Hit>>;
+
+/// World transforms of nodes by name. Cameras and lights are placed by the
+/// node with the same name.
+type NodeTransforms = HashMap<String, Mat4>;
+
+fn to_point(v: &Vector3D) -> Point4 {
+    Vec4::point(v.x as f64, v.y as f64, v.z as f64)
+}
+
+fn to_vec(v: &Vector3D) -> Vec4 {
+    Vec4::vec(v.x as f64, v.y as f64, v.z as f64)
 }
 
 impl AssimpLoader {
     pub fn new(file_path: &str) -> Result<Self, Box<dyn Error>> {
-        let scene = Scene::from_file(file_path, vec![
-            PostProcess::Triangulate
-        ])?;
-        let base_path = file_path.rsplit_once("/").unwrap().0.to_owned();
+        let scene = Scene::from_file(
+            file_path,
+            vec![PostProcess::Triangulate, PostProcess::GenerateSmoothNormals],
+        )?;
+        let base_path = Path::new(file_path)
+            .parent()
+            .map_or(PathBuf::new(), Path::to_path_buf);
         Ok(AssimpLoader { scene, base_path })
     }
 
     pub fn load(&self, config: Config) -> Result<SceneData, Box<dyn Error>> {
-        if let Some(root_node) = &self.scene.root {
-            let mut scene_config = DEFAULT_SCENE_CONFIG;
-
-            if let Some(camera) = self.scene.cameras.get(0) {
-                let c_pos = camera.position;
-                let c_look = camera.look_at;
-                let hfov = camera.horizontal_fov as f64;
-
-                scene_config.camera_pos = Some(Vec4::point(c_pos.x as f64, c_pos.y as f64, c_pos.z as f64));
-                scene_config.camera_target = Some(Vec4::point(c_look.x as f64, c_look.y as f64, c_look.z as f64));
-                scene_config.aspect_ratio = Some(camera.aspect as f64);
-                scene_config.focal_length = Some(18.0 / f64::tan(hfov / 2.0));
-            }
+        let root_node = self
+            .scene
+            .root
+            .as_ref()
+            .ok_or(ParseError::boxed("Scene has no root node"))?;
 
-            let scene_config = SceneConfig::merge(&scene_config, &config.scene);
-            let config = Config {
-                scene: scene_config,
-                ..config
-            };
+        let materials = self
+            .scene
+            .materials
+            .iter()
+            .enumerate()
+            .map(|(idx, material)| {
+                self.load_material(material).map_err(|err| {
+                    ParseError::boxed(&format!("Failed to load material {idx}: {err}"))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
+        let mut contents = SceneContents::default();
+        let mut node_transforms = NodeTransforms::new();
+        self.load_node(
+            root_node,
+            &Mat4::identity(),
+            &materials,
+            &mut contents,
+            &mut node_transforms,
+        )?;
 
-            let camera = Camera::new(&config);
+        for light in &self.scene.lights {
+            self.load_light(light, &node_transforms, &mut contents);
+        }
 
-            let mut lights: Vec<Arc<dyn Hit>> = Vec::new();
-            let world = self.load_node(root_node, Vec4::point(0.0, 0.0, 0.0), &mut lights);
+        if contents.objects.is_empty() {
+            return Err(ParseError::boxed("Scene has no meshes"));
+        }
 
-            let lights: Arc<dyn Hit> = Arc::new(ObjectList::from(lights));
-            Ok((camera, world, lights))
-        } else {
-            Err(Box::new(ParseError::new("Assimp load fail")))
+        let mut scene_config = DEFAULT_SCENE_CONFIG;
+        if let Some(camera) = self.scene.cameras.first() {
+            let world = contents.camera.map_or(Mat4::identity(), |(_, world)| world);
+            self.apply_camera(camera, &world, &config, &mut scene_config);
         }
+
+        let scene_config = SceneConfig::merge(&scene_config, &config.scene);
+        let config = Config {
+            scene: scene_config,
+            ..config
+        };
+        let camera = Camera::new(&config);
+        let (world, lights) = contents.into_world_and_lights();
+
+        Ok((camera, world, lights))
     }
 
-    fn load_node(&self, node: &Rc<Node>, pos: Point4, lights: &mut Vec<Arc<dyn Hit>>) -> Arc<dyn Hit> {
-        let children = node.children.borrow();
-        let mut objects = Vec::with_capacity(node.meshes.len() + children.len());
+    fn load_node(
+        &self,
+        node: &Rc<Node>,
+        parent_transform: &Mat4,
+        materials: &[Arc<dyn Material>],
+        contents: &mut SceneContents,
+        node_transforms: &mut NodeTransforms,
+    ) -> Result<(), Box<dyn Error>> {
+        let world = *parent_transform * Mat4::from_assimp(&node.transformation);
+        node_transforms.insert(node.name.clone(), world);
 
-        let t_mat = Mat4::from_assimp(&node.transformation);
-        let mut translation = t_mat.column(3);
-        translation[3] = 0.0;
+        // The first camera is placed by the node with the same name
+        if let Some(camera) = self.scene.cameras.first() {
+            if camera.name == node.name {
+                contents.camera.get_or_insert((0, world));
+            }
+        }
+
+        if !node.meshes.is_empty() {
+            let mut meshes = Vec::with_capacity(node.meshes.len());
+            for &mesh_idx in &node.meshes {
+                meshes.push(self.mesh(mesh_idx as usize, materials, contents)?);
+            }
 
-        for mesh_idx in &node.meshes {
-            let mesh = self.load_mesh(&self.scene.meshes[mesh_idx.to_usize().unwrap()], pos + translation, lights);
-            objects.push(mesh);
+            let object: Arc<dyn Hit> = match meshes.len() {
+                1 => meshes.remove(0),
+                _ => Arc::new(ObjectList::from(meshes)),
+            };
+
+            if world == Mat4::identity() {
+                contents.objects.push(object);
+            } else if let Some(transform) = Transform::from_matrix(object, &world) {
+                contents.objects.push(Arc::new(transform));
+            } else {
+                println!(
+                    "Warning: node '{}' has a singular transform, its meshes are ignored\n",
+                    node.name
+                );
+            }
         }
 
-        for child in children.iter() {
-            objects.push(self.load_node(child, pos + translation, lights));
+        for child in node.children.borrow().iter() {
+            self.load_node(child, &world, materials, contents, node_transforms)?;
         }
 
-        let list = ObjectList::from(objects);
-        let transform = Transform::from_matrix(Arc::new(list), &t_mat).unwrap();
-        Arc::new(transform)
+        Ok(())
     }
 
-    fn load_mesh(&self, mesh: &AssimpMesh, pos: Point4, lights: &mut Vec<Arc<dyn Hit>>) -> Arc<dyn Hit> {
-        let vertices: Vec<Vec4> = mesh.vertices.iter().map(|v| {
-            Vec4::point(v.x as f64, v.y as f64, v.z as f64)
-        }).collect();
-        let normals = mesh.normals.iter().map(|n| {
-            Vec4::vec(n.x as f64, n.y as f64, n.z as f64)
-        }).collect();
-        let uvs = if let Some(Some(mesh_uvs)) = &mesh.texture_coords.get(0) {
-            mesh_uvs.iter().map(|uv| {
-                Vec4::vec(uv.x as f64, uv.y as f64, uv.z as f64)
-            }).collect()
-        } else {
-            vec![]
-        };
+    /// Get a mesh by index, loading it the first time it's used.
+    fn mesh(
+        &self,
+        mesh_idx: usize,
+        materials: &[Arc<dyn Material>],
+        contents: &mut SceneContents,
+    ) -> Result<Arc<dyn Hit>, Box<dyn Error>> {
+        match contents.meshes.entry(mesh_idx) {
+            Entry::Occupied(entry) => Ok(Arc::clone(entry.get())),
+            Entry::Vacant(entry) => {
+                let mesh = self
+                    .scene
+                    .meshes
+                    .get(mesh_idx)
+                    .ok_or(ParseError::boxed(&format!("Invalid mesh index {mesh_idx}")))?;
+                let mesh = Self::load_mesh(mesh, materials).map_err(|err| {
+                    ParseError::boxed(&format!("Failed to load mesh '{}': {err}", mesh.name))
+                })?;
 
-        let tris = mesh.faces.iter().map(|face| {
-            let indices: [usize; 3] = [
-                face.0[0] as usize,
-                face.0[1] as usize,
-                face.0[2] as usize,
-            ];
-            Triangle {
-                vert_indices: indices,
-                normal_indices: indices,
-                uv_indices: if uvs.len() > 0 { Some(indices) } else { None },
+                Ok(Arc::clone(entry.insert(Arc::new(mesh))))
             }
-        }).collect();
+        }
+    }
 
-        let (material, is_emissive) = self.load_material(&self.scene.materials[mesh.material_index as usize]);
+    fn load_mesh(
+        mesh: &AssimpMesh,
+        materials: &[Arc<dyn Material>],
+    ) -> Result<TriangleMesh, Box<dyn Error>> {
+        let vertices: Vec<Vec4> = mesh.vertices.iter().map(to_point).collect();
+        let normals: Vec<Vec4> = mesh.normals.iter().map(to_vec).collect();
+        let uvs: Vec<Vec4> = match mesh.texture_coords.first() {
+            Some(Some(mesh_uvs)) => mesh_uvs.iter().map(to_vec).collect(),
+            _ => vec![],
+        };
+        let has_normals = normals.len() == vertices.len();
+        let has_uvs = uvs.len() == vertices.len();
 
-        if is_emissive {
-            // Create an invisible sphere object to sample lighting
-            // This is a bit of a hack, but I don't support importance sampling from arbitrary meshes
-            let radius = vertices.iter().fold(f64::INFINITY, |r, v| f64::min(v.length(), r));
-            let sampler = Sphere::new(pos, radius, Arc::clone(&material));
-            lights.push(Arc::new(sampler))
-        }
-        Arc::new(TriangleMesh::new(vertices, uvs, normals, tris, material))
+        let material = materials
+            .get(mesh.material_index as usize)
+            .ok_or(ParseError::boxed(&format!(
+                "Invalid material index {}",
+                mesh.material_index
+            )))?;
+
+        // Points and lines are left over after triangulation, skip them
+        let tris = mesh
+            .faces
+            .iter()
+            .filter(|face| face.0.len() == 3)
+            .map(|face| {
+                let indices = [face.0[0], face.0[1], face.0[2]].map(|idx| idx as usize);
+                if let Some(idx) = indices.iter().find(|&&idx| idx >= vertices.len()) {
+                    return Err(ParseError::boxed(&format!(
+                        "Out of range vertex index {idx}"
+                    )));
+                }
+
+                Ok(Triangle {
+                    vert_indices: indices,
+                    normal_indices: indices,
+                    uv_indices: has_uvs.then_some(indices),
+                    material_index: 0,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut mesh = TriangleMesh::new(
+            vertices,
+            if has_uvs { uvs } else { vec![] },
+            if has_normals { normals } else { vec![] },
+            tris,
+            vec![Arc::clone(material)],
+        );
+        mesh.flat_shading = !has_normals;
+
+        Ok(mesh)
     }
 
-    fn load_material(&self, mat: &AssimpMaterial) -> (Arc<dyn Material>, bool) {
-        // Emissive material
-        if let Some(mut emission) = get_vec3_property(mat, "$clr.emissive") {
+    /// Map a material onto the closest material type:
+    /// - `Emissive` if it has an emissive color
+    /// - `Dielectric` if it's mostly transmissive or transparent
+    /// - `Metal` if it's mostly metallic
+    /// - `Glossy` otherwise
+    fn load_material(&self, mat: &AssimpMaterial) -> Result<Arc<dyn Material>, Box<dyn Error>> {
+        if let Some(emission) = get_vec3_property(mat, "$clr.emissive")? {
             if emission.length_squared() > 0.0 {
-                let intensity = get_float_property(mat, "$mat.emissiveIntensity").unwrap_or(1.0);
-                emission *= intensity;
+                let intensity = get_float_property(mat, "$mat.emissiveIntensity")?.unwrap_or(1.0);
+                let emission_map: TexturePointer<Color> =
+                    match self.texture(mat, TextureType::Emissive) {
+                        Some(texture) => Arc::new(Scale::new(texture, emission * intensity)),
+                        None => Arc::new(ConstantTexture::new(emission * intensity)),
+                    };
 
-                return (Arc::new(Emissive::new(Arc::new(ConstantTexture::new(emission)))), true);
+                return Ok(Arc::new(Emissive::new(emission_map)));
             }
         }
 
-        // Use glossy material for anything else
-        // TODO: metals
-        let ior = 1.5;
-        let roughness = get_float_property(mat, "$mat.roughnessFactor").unwrap_or(0.0);
-        let albedo = get_vec3_property(mat, "$clr.base").unwrap_or(Vec4::vec(0.5, 0.5, 0.5));
-
-        let mut albedo: TexturePointer<Color> = Arc::new(ConstantTexture::new(albedo));
-        let mut roughness: TexturePointer<f64> = Arc::new(ConstantTexture::new(roughness));
-
-        for (tex_type, texture) in &mat.textures {
-            let texture = texture.borrow();
-            let mut filename = String::from(&self.base_path);
-            filename.push_str("/");
-            filename.push_str(&texture.filename);
-            filename.push_str(".");
-            filename.push_str(&texture.ach_format_hint);
-
-            match tex_type {
-                TextureType::BaseColor => {
-                    let tex = ImageTexture::from_file(&filename).unwrap();
-                    albedo = Arc::new(tex);
+        // Refractive indices of 1 usually mean the file doesn't set one
+        let ior = get_float_property(mat, "$mat.refracti")?
+            .filter(|&ior| ior > 1.0)
+            .unwrap_or(1.5);
+        let transmission = match get_float_property(mat, "$mat.transmission.factor")? {
+            Some(transmission) => transmission,
+            None => 1.0 - get_float_property(mat, "$mat.opacity")?.unwrap_or(1.0),
+        };
+        if transmission >= 0.5 {
+            return Ok(Arc::new(Dielectric::new(ior)));
+        }
+
+        let base_color = match get_vec3_property(mat, "$clr.base")? {
+            Some(color) => color,
+            None => get_vec3_property(mat, "$clr.diffuse")?.unwrap_or(Vec4::vec(0.5, 0.5, 0.5)),
+        };
+        let albedo: TexturePointer<Color> = match self
+            .texture(mat, TextureType::BaseColor)
+            .or_else(|| self.texture(mat, TextureType::Diffuse))
+        {
+            Some(texture) => texture,
+            None => Arc::new(ConstantTexture::new(base_color)),
+        };
+
+        let roughness = get_float_property(mat, "$mat.roughnessFactor")?.unwrap_or(0.0);
+        let roughness: TexturePointer<f64> = match self.texture(mat, TextureType::Roughness) {
+            Some(texture) => Arc::new(Channel::new(texture, 0)),
+            None => Arc::new(ConstantTexture::new(roughness)),
+        };
+
+        let metallic = get_float_property(mat, "$mat.metallicFactor")?.unwrap_or(0.0);
+        if metallic >= 0.5 {
+            return Ok(Arc::new(Metal::new(albedo, roughness)));
+        }
+
+        let mut glossy = Glossy::new(albedo, roughness, ior);
+        if let Some(normal_map) = self.texture(mat, TextureType::Normals) {
+            glossy.normal_map = Some(normal_map);
+        }
+
+        Ok(Arc::new(glossy))
+    }
+
+    /// Load a material's texture of the given type, either embedded in the
+    /// file or from an image file next to it. Failing to load a texture
+    /// isn't an error, the material is used without it.
+    fn texture(&self, mat: &AssimpMaterial, tex_type: TextureType) -> Option<Arc<ImageTexture>> {
+        let texture = if let Some(embedded) = mat.textures.get(&tex_type) {
+            match &embedded.borrow().data {
+                DataContent::Bytes(bytes) => {
+                    Buffer::from_image_bytes(bytes).map(ImageTexture::from_buffer)
                 }
-                TextureType::Roughness => {
-                    let tex = ImageTexture::from_file(&filename).unwrap();
-                    roughness = Arc::new(Channel::new(Arc::new(tex), 0));
+                DataContent::Texel(_) => Err(ParseError::boxed(
+                    "Uncompressed embedded textures aren't supported",
+                )),
+            }
+        } else {
+            let file = mat.properties.iter().find_map(|p| match &p.data {
+                PropertyTypeInfo::String(file)
+                    if p.key == "$tex.file" && p.semantic == tex_type && p.index == 0 =>
+                {
+                    Some(file)
                 }
-                _ => (),
+                _ => None,
+            })?;
+
+            // Paths from files made on Windows can use backslashes
+            let path = self.base_path.join(file.replace('\\', "/"));
+            ImageTexture::from_file(&path.to_string_lossy())
+        };
+
+        match texture {
+            Ok(texture) => Some(Arc::new(texture)),
+            Err(err) => {
+                println!("Warning: failed to load {tex_type:?} texture");
+                println!("\t{err}\n");
+                None
             }
         }
+    }
+
+    /// Place the scene camera in world space, by the transform of its node.
+    fn apply_camera(
+        &self,
+        camera: &AssimpCamera,
+        world: &Mat4,
+        config: &Config,
+        scene_config: &mut SceneConfig,
+    ) {
+        // Position, look-at and up vectors are relative to the node. Cameras
+        // look along their local -Z axis, so build a frame from them. Zero
+        // vectors fall back to assimp's defaults
+        let or_default =
+            |v: Vec4, default: Vec4| if v.length_squared() > 0.0 { v } else { default };
+        let back = -or_default(to_vec(&camera.look_at), Vec4::vec(0.0, 0.0, 1.0)).to_unit();
+        let up = or_default(to_vec(&camera.up), Vec4::vec(0.0, 1.0, 0.0));
+        let right = image_right(up, back);
+        let local = Mat4::from_columns(right, back.cross(&right), back, to_point(&camera.position));
+        let transform = *world * local;
+        let position = transform * Vec4::point(0.0, 0.0, 0.0);
+
+        // The position and target are kept for when flags place the camera by
+        // hand
+        scene_config.camera_pos = Some(position);
+        scene_config.camera_target = Some(position - transform * Vec4::vec(0.0, 0.0, 1.0));
+        scene_config.camera_transform = Some(transform);
+
+        if camera.aspect > 0.0 {
+            scene_config.aspect_ratio = Some(camera.aspect as f64);
+        }
 
-        (
-            Arc::new(Glossy::new(albedo, roughness, ior)),
-            false,
-        )
+        // Assimp gives half the horizontal FOV. Keep it for the render's final
+        // aspect ratio, on a 36x24mm frame scaled to that aspect ratio
+        let half_fov = camera.horizontal_fov as f64;
+        if half_fov <= 0.0 || half_fov >= PI / 2.0 {
+            println!(
+                "Warning: camera '{}' has an invalid field of view\n",
+                camera.name
+            );
+            return;
+        }
+        let aspect_ratio = config
+            .scene
+            .aspect_ratio
+            .or(scene_config.aspect_ratio)
+            .unwrap_or(1.5);
+        scene_config.focal_length = Some(12.0 * aspect_ratio / f64::tan(half_fov));
     }
-}
 
-fn get_vec3_property(mat: &AssimpMaterial, prop_name: &str) -> Option<Vec4> {
-    if let Some(property) = mat.properties.iter().find(|p| p.key == prop_name) {
-        let value = match &property.data {
-            PropertyTypeInfo::FloatArray(arr) => Vec4::vec(arr[0] as f64, arr[1] as f64, arr[2] as f64),
-            _ => panic!(),
+    /// Add a scene light, placed in world space by the node with the same name.
+    fn load_light(
+        &self,
+        light: &AssimpLight,
+        node_transforms: &NodeTransforms,
+        contents: &mut SceneContents,
+    ) {
+        let world = node_transforms
+            .get(&light.name)
+            .copied()
+            .unwrap_or(Mat4::identity());
+
+        let color = light.color_diffuse;
+        let color = Vec4::vec(color.r as f64, color.g as f64, color.b as f64);
+        let position = world * to_point(&light.pos);
+        let direction = (world * to_vec(&light.direction)).to_unit();
+
+        let object: Arc<dyn Hit> = match light.light_source_type {
+            LightSourceType::Directional => {
+                let sun = Sun::new(Arc::new(ConstantTexture::new(color)), -direction);
+                contents.distant_lights.push(Arc::new(sun));
+                return;
+            }
+            LightSourceType::Point => Arc::new(point_light(position, color)),
+            LightSourceType::Spot => {
+                // Cone angles default to 2π for no cone, clamp them to a full sphere
+                let inner_angle = f64::min(light.angle_inner_cone as f64, PI);
+                let outer_angle = f64::min(light.angle_outer_cone as f64, PI);
+                Arc::new(spot_light(
+                    position,
+                    direction,
+                    color,
+                    inner_angle,
+                    outer_angle,
+                ))
+            }
+            LightSourceType::Area => {
+                let width = light.size.x as f64;
+                let height = light.size.y as f64;
+                if width <= 0.0 || height <= 0.0 {
+                    println!(
+                        "Warning: area light '{}' has no size, ignored\n",
+                        light.name
+                    );
+                    return;
+                }
+
+                // Build the light's frame from its direction and up vector, so
+                // the emitting side of the plane faces along its direction
+                let up = world * to_vec(&light.up);
+                let u = Vec4::cross(&up, &direction);
+                if u.length_squared() < 1e-12 {
+                    println!(
+                        "Warning: area light '{}' has an up vector along its direction, ignored\n",
+                        light.name
+                    );
+                    return;
+                }
+                let u = u.to_unit();
+                let v = Vec4::cross(&direction, &u);
+
+                let material = Emissive::new(Arc::new(ConstantTexture::new(color)));
+                Arc::new(Plane::new(
+                    position,
+                    (u * (width / 2.0), v * (height / 2.0)),
+                    Arc::new(material),
+                ))
+            }
+            _ => {
+                println!(
+                    "Warning: light '{}' has an unsupported type, ignored\n",
+                    light.name
+                );
+                return;
+            }
         };
-        Some(value)
-    } else {
-        None
+
+        contents.objects.push(Arc::clone(&object));
+        contents.lights.push(object);
     }
 }
 
-fn get_float_property(mat: &AssimpMaterial, prop_name: &str) -> Option<f64> {
-    if let Some(property) = mat.properties.iter().find(|p| p.key == prop_name) {
-        let value = match &property.data {
-            PropertyTypeInfo::FloatArray(arr) => arr[0] as f64,
-            _ => panic!(),
-        };
-        Some(value)
-    } else {
-        None
+fn get_float_array<'a>(
+    mat: &'a AssimpMaterial,
+    prop_name: &str,
+    len: usize,
+) -> Result<Option<&'a [f32]>, Box<dyn Error>> {
+    let Some(property) = mat.properties.iter().find(|p| p.key == prop_name) else {
+        return Ok(None);
+    };
+
+    match &property.data {
+        PropertyTypeInfo::FloatArray(arr) if arr.len() >= len => Ok(Some(arr)),
+        _ => Err(ParseError::boxed(&format!(
+            "Material property {prop_name} should have {len} float value(s)"
+        ))),
     }
 }
+
+fn get_vec3_property(
+    mat: &AssimpMaterial,
+    prop_name: &str,
+) -> Result<Option<Vec4>, Box<dyn Error>> {
+    Ok(get_float_array(mat, prop_name, 3)?
+        .map(|arr| Vec4::vec(arr[0] as f64, arr[1] as f64, arr[2] as f64)))
+}
+
+fn get_float_property(
+    mat: &AssimpMaterial,
+    prop_name: &str,
+) -> Result<Option<f64>, Box<dyn Error>> {
+    Ok(get_float_array(mat, prop_name, 1)?.map(|arr| arr[0] as f64))
+}
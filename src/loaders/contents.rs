@@ -0,0 +1,64 @@
+//! Objects collected by the loaders for imported scenes (glTF, assimp).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::aabb::{self, AxisAlignedBoundingBox};
+use crate::mat4::Mat4;
+use crate::object::{BoundingVolumeHierarchy, Hit, ObjectList};
+
+/// Objects collected while walking the node hierarchy. Meshes are stored as
+/// `M`, so loaders can also remember meshes with nothing to render.
+pub struct SceneContents<M> {
+    pub objects: Vec<Arc<dyn Hit>>,
+    pub lights: Vec<Arc<dyn Hit>>,
+    /// Directional lights, kept out of the BVH as they have infinite bounds.
+    pub distant_lights: Vec<Arc<dyn Hit>>,
+    /// First camera found, by index, with its world transform.
+    pub camera: Option<(usize, Mat4)>,
+    /// Meshes by index, loaded once and shared by every node using them.
+    pub meshes: HashMap<usize, M>,
+}
+
+impl<M> Default for SceneContents<M> {
+    fn default() -> Self {
+        SceneContents {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            distant_lights: Vec::new(),
+            camera: None,
+            meshes: HashMap::new(),
+        }
+    }
+}
+
+impl<M> SceneContents<M> {
+    /// Bounds of the objects, without directional lights.
+    pub fn bounds(&self) -> AxisAlignedBoundingBox {
+        let bounds: Vec<_> = self
+            .objects
+            .iter()
+            .map(|obj| obj.get_bounding_box())
+            .collect();
+        aabb::combine_bounds(&bounds)
+    }
+
+    /// Build the world and lights objects of the scene. Objects go in a BVH,
+    /// and directional lights are both added to the world and sampled as
+    /// lights.
+    pub fn into_world_and_lights(mut self) -> (Arc<dyn Hit>, Arc<dyn Hit>) {
+        let bvh: Arc<dyn Hit> = Arc::new(BoundingVolumeHierarchy::from(self.objects));
+        let world: Arc<dyn Hit> = if self.distant_lights.is_empty() {
+            bvh
+        } else {
+            let mut objects = vec![bvh];
+            objects.extend(self.distant_lights.iter().cloned());
+            Arc::new(ObjectList::from(objects))
+        };
+
+        self.lights.extend(self.distant_lights);
+        let lights: Arc<dyn Hit> = Arc::new(ObjectList::from(self.lights));
+
+        (world, lights)
+    }
+}
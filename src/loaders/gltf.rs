@@ -0,0 +1,702 @@
+//! Native glTF 2.0 loader, for `.gltf` files (with external or embedded
+//! buffers) and binary `.glb` files.
+//!
+//! Loads the default scene's node hierarchy, with meshes using the
+//! metallic-roughness material model, perspective cameras and punctual lights
+//! (`KHR_lights_punctual`). Animations, skins and morph targets are ignored.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::binary::ReadBinary;
+use crate::camera::Camera;
+use crate::config::{Config, SceneConfig, DEFAULT_SCENE_CONFIG};
+use crate::mat4::Mat4;
+use crate::object::mesh::{Triangle, TriangleMesh};
+use crate::object::{Hit, Sun, Transform};
+use crate::scene::SceneData;
+use crate::texture::ConstantTexture;
+use crate::utils::ParseError;
+use crate::vec4::{Color, Vec4};
+
+use super::contents;
+use super::json::{self, JsonValue};
+use super::punctual::{point_light, spot_light};
+
+use self::material::LoadedMaterial;
+
+mod accessor;
+mod material;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_CHUNK_JSON: u32 = 0x4E4F534A;
+const GLB_CHUNK_BIN: u32 = 0x004E4942;
+
+/// Max depth of the node hierarchy, so node cycles in malformed files are
+/// caught instead of recursing forever.
+const MAX_NODE_DEPTH: usize = 256;
+
+const SUPPORTED_EXTENSIONS: [&str; 5] = [
+    "KHR_lights_punctual",
+    "KHR_materials_emissive_strength",
+    "KHR_materials_ior",
+    "KHR_materials_transmission",
+    "KHR_materials_unlit",
+];
+
+pub struct GltfLoader {
+    document: JsonValue,
+    buffers: Vec<Vec<u8>>,
+    base_path: PathBuf,
+}
+
+/// Meshes without triangles are stored as `None`.
+type SceneContents = contents::SceneContents<Option<Arc<dyn Hit>>>;
+
+/// Whether a path has a glTF file extension (`.gltf` or `.glb`).
+pub fn is_gltf_path(path: &str) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+
+    matches!(extension.as_deref(), Some("gltf" | "glb"))
+}
+
+/// Get a number property, or a default value if it's missing.
+fn get_f64(json: &JsonValue, key: &str, default: f64) -> f64 {
+    json.get(key).and_then(JsonValue::as_f64).unwrap_or(default)
+}
+
+/// Get a color property from an RGB or RGBA array, or a default color if it's
+/// missing.
+fn get_color(json: &JsonValue, key: &str, default: Color) -> Color {
+    match json.get(key).and_then(JsonValue::as_f64_array) {
+        Some(rgb) if rgb.len() >= 3 => Vec4::vec(rgb[0], rgb[1], rgb[2]),
+        _ => default,
+    }
+}
+
+/// JSON and binary chunks of a .glb file.
+type GlbChunks<'a> = (&'a [u8], Option<&'a [u8]>);
+
+/// Split a .glb file into its JSON and binary chunks.
+fn read_glb(data: &[u8]) -> Result<GlbChunks<'_>, Box<dyn Error>> {
+    let mut r = &data[GLB_MAGIC.len()..];
+    let version = r.read_u32()?;
+    if version != 2 {
+        return Err(ParseError::boxed(&format!(
+            "Unsupported .glb version {version}"
+        )));
+    }
+    let length = r.read_u32()? as usize;
+    if length < 12 || length > data.len() {
+        return Err(ParseError::boxed("Truncated .glb file"));
+    }
+
+    let mut r = &data[12..length];
+    let mut json = None;
+    let mut bin = None;
+    while !r.is_empty() {
+        let chunk_length = r.read_u32()? as usize;
+        let chunk_type = r.read_u32()?;
+        if chunk_length > r.len() {
+            return Err(ParseError::boxed("Truncated .glb chunk"));
+        }
+
+        let (chunk, rest) = r.split_at(chunk_length);
+        r = rest;
+        match chunk_type {
+            GLB_CHUNK_JSON if json.is_none() => json = Some(chunk),
+            GLB_CHUNK_BIN if bin.is_none() => bin = Some(chunk),
+            _ => (), // Unknown chunks must be ignored
+        }
+    }
+
+    match json {
+        Some(json) => Ok((json, bin)),
+        None => Err(ParseError::boxed("Missing JSON chunk in .glb file")),
+    }
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut data = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+
+    for byte in text.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => return None,
+        };
+
+        bits = (bits << 6 | value as u32) & 0xFFFF;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            data.push((bits >> bit_count) as u8);
+        }
+    }
+
+    Some(data)
+}
+
+/// Decode percent-encoded characters in a relative URI.
+fn decode_uri(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Local transform of a node, from either its matrix or its translation,
+/// rotation and scale.
+fn node_transform(node: &JsonValue) -> Result<Mat4, Box<dyn Error>> {
+    if let Some(matrix) = node.get("matrix") {
+        return match matrix.as_f64_array() {
+            // Stored in column-major order
+            Some(values) if values.len() == 16 => {
+                Ok(Mat4::from_values(values.try_into().unwrap()).transposed())
+            }
+            _ => Err(ParseError::boxed("Invalid node matrix")),
+        };
+    }
+
+    let vector = |key: &str, default: &[f64]| -> Result<Vec<f64>, Box<dyn Error>> {
+        match node.get(key).map(JsonValue::as_f64_array) {
+            None => Ok(default.to_vec()),
+            Some(Some(values)) if values.len() == default.len() => Ok(values),
+            Some(_) => Err(ParseError::boxed(&format!("Invalid node {key}"))),
+        }
+    };
+
+    let t = vector("translation", &[0.0, 0.0, 0.0])?;
+    let r = vector("rotation", &[0.0, 0.0, 0.0, 1.0])?;
+    let s = vector("scale", &[1.0, 1.0, 1.0])?;
+
+    // Rotation from a unit quaternion
+    let (x, y, z, w) = (r[0], r[1], r[2], r[3]);
+    let rotation = Mat4::from_rows(
+        Vec4([
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+            0.0,
+        ]),
+        Vec4([
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+            0.0,
+        ]),
+        Vec4([
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+        ]),
+        Vec4([0.0, 0.0, 0.0, 1.0]),
+    );
+
+    Ok(Mat4::translation(t[0], t[1], t[2]) * rotation * Mat4::scale(s[0], s[1], s[2]))
+}
+
+impl GltfLoader {
+    pub fn new(file_path: &str) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read(file_path)?;
+        let base_path = Path::new(file_path)
+            .parent()
+            .unwrap_or(Path::new(""))
+            .to_owned();
+
+        let (json_data, mut bin) = if data.starts_with(GLB_MAGIC) {
+            read_glb(&data)?
+        } else {
+            (data.as_slice(), None)
+        };
+        let document = json::parse(std::str::from_utf8(json_data)?)?;
+
+        let version = document
+            .get("asset")
+            .and_then(|asset| asset.get("version"))
+            .and_then(JsonValue::as_str)
+            .unwrap_or("");
+        if !version.starts_with("2.") {
+            return Err(ParseError::boxed(&format!(
+                "Unsupported glTF version {version}"
+            )));
+        }
+
+        let mut loader = GltfLoader {
+            document,
+            buffers: Vec::new(),
+            base_path,
+        };
+
+        for required in loader.array("extensionsRequired") {
+            let name = required.as_str().unwrap_or("");
+            if !SUPPORTED_EXTENSIONS.contains(&name) {
+                println!(
+                    "Warning: unsupported required extension {name}, rendering may be wrong\n"
+                );
+            }
+        }
+
+        let mut buffers = Vec::new();
+        for buffer in loader.array("buffers") {
+            let data = match buffer.get("uri").and_then(JsonValue::as_str) {
+                Some(uri) => loader.load_uri(uri)?,
+                // The binary chunk of a .glb file can only be used by one buffer
+                None => bin
+                    .take()
+                    .ok_or(ParseError::boxed("Buffer has no data"))?
+                    .to_vec(),
+            };
+
+            let byte_length = buffer.get("byteLength").and_then(JsonValue::as_usize);
+            if byte_length.is_none_or(|length| data.len() < length) {
+                return Err(ParseError::boxed("Buffer is smaller than its byteLength"));
+            }
+
+            buffers.push(data);
+        }
+        loader.buffers = buffers;
+
+        Ok(loader)
+    }
+
+    pub fn load(&self, config: Config) -> Result<SceneData, Box<dyn Error>> {
+        let materials = self.load_materials();
+        let mut contents = SceneContents::default();
+
+        for node in self.scene_roots()? {
+            self.load_node(node, &Mat4::identity(), 0, &materials, &mut contents)?;
+        }
+
+        if contents.objects.is_empty() {
+            return Err(ParseError::boxed("Scene has no meshes"));
+        }
+
+        let mut scene_config = DEFAULT_SCENE_CONFIG;
+        match contents.camera {
+            Some((camera_idx, world)) => {
+                self.apply_camera(camera_idx, &world, &mut scene_config)?
+            }
+            None => {
+                // Look at the whole scene from the front
+                let [min, max] = contents.bounds();
+                let center = (min + max) * 0.5;
+                let radius = (max - min).length() * 0.5;
+                scene_config.camera_pos = Some(center + Vec4::vec(0.0, 0.0, 2.5 * radius));
+                scene_config.camera_target = Some(center);
+            }
+        }
+
+        let scene_config = SceneConfig::merge(&scene_config, &config.scene);
+        let config = Config {
+            scene: scene_config,
+            ..config
+        };
+        let camera = Camera::new(&config);
+        let (world, lights) = contents.into_world_and_lights();
+
+        Ok((camera, world, lights))
+    }
+
+    /// Get a top-level array of the document, empty if it's missing.
+    fn array(&self, key: &str) -> &[JsonValue] {
+        self.document
+            .get(key)
+            .and_then(JsonValue::as_array)
+            .unwrap_or(&[])
+    }
+
+    /// Get an item of a top-level array by index.
+    fn item(&self, key: &str, idx: usize) -> Result<&JsonValue, Box<dyn Error>> {
+        self.array(key)
+            .get(idx)
+            .ok_or_else(|| ParseError::boxed(&format!("Invalid {key} index {idx}")))
+    }
+
+    /// Get an index property of an object, such as the mesh used by a node.
+    fn index(json: &JsonValue, key: &str) -> Result<Option<usize>, Box<dyn Error>> {
+        match json.get(key) {
+            None => Ok(None),
+            Some(value) => match value.as_usize() {
+                Some(idx) => Ok(Some(idx)),
+                None => Err(ParseError::boxed(&format!("Invalid {key} index"))),
+            },
+        }
+    }
+
+    /// Load the data referred to by a URI, either embedded as base64 or in a
+    /// file relative to the glTF file.
+    fn load_uri(&self, uri: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        if let Some(data_uri) = uri.strip_prefix("data:") {
+            let Some((header, payload)) = data_uri.split_once(',') else {
+                return Err(ParseError::boxed("Invalid data URI"));
+            };
+            if !header.ends_with(";base64") {
+                return Err(ParseError::boxed("Only base64 data URIs are supported"));
+            }
+
+            decode_base64(payload).ok_or(ParseError::boxed("Invalid base64 data"))
+        } else {
+            Ok(fs::read(self.base_path.join(decode_uri(uri)))?)
+        }
+    }
+
+    /// Root nodes of the default scene, or of the first scene if there's no
+    /// default.
+    fn scene_roots(&self) -> Result<Vec<usize>, Box<dyn Error>> {
+        let scene_idx = Self::index(&self.document, "scene")?.unwrap_or(0);
+        let scene = self.item("scenes", scene_idx)?;
+
+        scene
+            .get("nodes")
+            .and_then(JsonValue::as_array)
+            .unwrap_or(&[])
+            .iter()
+            .map(|node| {
+                node.as_usize()
+                    .ok_or(ParseError::boxed("Invalid scene node index"))
+            })
+            .collect()
+    }
+
+    fn load_node(
+        &self,
+        node_idx: usize,
+        parent: &Mat4,
+        depth: usize,
+        materials: &[LoadedMaterial],
+        contents: &mut SceneContents,
+    ) -> Result<(), Box<dyn Error>> {
+        if depth >= MAX_NODE_DEPTH {
+            return Err(ParseError::boxed("Node hierarchy is too deep"));
+        }
+
+        let node = self.item("nodes", node_idx)?;
+        let world = *parent * node_transform(node)?;
+
+        if let Some(mesh_idx) = Self::index(node, "mesh")? {
+            let mesh = match contents.meshes.entry(mesh_idx) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.load_mesh(mesh_idx, materials)?),
+            };
+
+            if let Some(mesh) = mesh.clone() {
+                if world == Mat4::identity() {
+                    contents.objects.push(mesh);
+                } else if let Some(transform) = Transform::from_matrix(mesh, &world) {
+                    contents.objects.push(Arc::new(transform));
+                } else {
+                    println!("Warning: node {node_idx} has a degenerate transform, skipped\n");
+                }
+            }
+        }
+
+        if let Some(camera_idx) = Self::index(node, "camera")? {
+            contents.camera.get_or_insert((camera_idx, world));
+        }
+
+        let light = node
+            .get("extensions")
+            .and_then(|ext| ext.get("KHR_lights_punctual"))
+            .map(|ext| Self::index(ext, "light"));
+        if let Some(light_idx) = light.transpose()?.flatten() {
+            self.load_light(light_idx, &world, contents)?;
+        }
+
+        let children = node
+            .get("children")
+            .and_then(JsonValue::as_array)
+            .unwrap_or(&[]);
+        for child in children {
+            let child_idx = child
+                .as_usize()
+                .ok_or(ParseError::boxed("Invalid node child index"))?;
+            self.load_node(child_idx, &world, depth + 1, materials, contents)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load all triangle primitives of a mesh into a single `TriangleMesh`.
+    /// Returns `None` if the mesh has no triangles.
+    fn load_mesh(
+        &self,
+        mesh_idx: usize,
+        materials: &[LoadedMaterial],
+    ) -> Result<Option<Arc<dyn Hit>>, Box<dyn Error>> {
+        let mesh = self.item("meshes", mesh_idx)?;
+
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut uvs = Vec::new();
+        let mut colors = Vec::new();
+        let mut triangles = Vec::new();
+
+        // Materials used by the mesh, mapped to their index in the mesh's material table
+        let mut mesh_materials = Vec::new();
+        let mut material_indices = HashMap::new();
+        let mut double_sided = false;
+
+        let primitives = mesh
+            .get("primitives")
+            .and_then(JsonValue::as_array)
+            .unwrap_or(&[]);
+        for primitive in primitives {
+            let mode = Self::index(primitive, "mode")?.unwrap_or(4);
+            if !matches!(mode, 4..=6) {
+                println!("Warning: mesh {mesh_idx} has points or lines, skipped\n");
+                continue;
+            }
+
+            let attributes = primitive
+                .get("attributes")
+                .ok_or(ParseError::boxed("Primitive has no attributes"))?;
+            let Some(position_idx) = Self::index(attributes, "POSITION")? else {
+                continue;
+            };
+
+            let vertex_offset = vertices.len();
+            let positions = self.accessor(position_idx)?.read_vecs(Vec4::point)?;
+            let vertex_count = positions.len();
+            vertices.extend(positions);
+
+            let normal_offset = normals.len();
+            let has_normals = match Self::index(attributes, "NORMAL")? {
+                Some(idx) => {
+                    let primitive_normals = self.accessor(idx)?.read_vecs(Vec4::vec)?;
+                    if primitive_normals.len() != vertex_count {
+                        return Err(ParseError::boxed("Normal count doesn't match vertex count"));
+                    }
+                    normals.extend(primitive_normals.into_iter().map(Vec4::to_unit));
+                    true
+                }
+                None => false,
+            };
+
+            let uv_offset = uvs.len();
+            let has_uvs = match Self::index(attributes, "TEXCOORD_0")? {
+                Some(idx) => {
+                    let primitive_uvs = self.accessor(idx)?.read_vecs(Vec4::vec)?;
+                    if primitive_uvs.len() != vertex_count {
+                        return Err(ParseError::boxed("UV count doesn't match vertex count"));
+                    }
+                    uvs.extend(primitive_uvs);
+                    true
+                }
+                None => false,
+            };
+
+            // Primitives without colors are white, when others have some
+            if let Some(idx) = Self::index(attributes, "COLOR_0")? {
+                let primitive_colors = self.accessor(idx)?.read_colors()?;
+                if primitive_colors.len() != vertex_count {
+                    return Err(ParseError::boxed("Color count doesn't match vertex count"));
+                }
+                colors.resize(vertex_offset, Vec4::vec(1.0, 1.0, 1.0));
+                colors.extend(primitive_colors);
+            }
+
+            let indices = match Self::index(primitive, "indices")? {
+                Some(idx) => self.accessor(idx)?.read_indices(vertex_count)?,
+                None => (0..vertex_count).collect(),
+            };
+            let corners: Vec<[usize; 3]> = match mode {
+                // Strips alternate winding, every other triangle is flipped to keep it consistent
+                5 => (2..indices.len())
+                    .map(|i| match i % 2 {
+                        0 => [indices[i - 2], indices[i - 1], indices[i]],
+                        _ => [indices[i - 1], indices[i - 2], indices[i]],
+                    })
+                    .collect(),
+                6 => (2..indices.len())
+                    .map(|i| [indices[0], indices[i - 1], indices[i]])
+                    .collect(),
+                _ => indices
+                    .chunks_exact(3)
+                    .map(|c| [c[0], c[1], c[2]])
+                    .collect(),
+            };
+
+            let material_idx = Self::index(primitive, "material")?;
+            let material = match material_idx {
+                Some(idx) => materials
+                    .get(idx)
+                    .ok_or(ParseError::boxed(&format!("Invalid material index {idx}")))?,
+                // The default material is stored last
+                None => materials.last().unwrap(),
+            };
+            double_sided |= material.double_sided;
+            let material_index = *material_indices.entry(material_idx).or_insert_with(|| {
+                mesh_materials.push(Arc::clone(&material.material));
+                mesh_materials.len() - 1
+            });
+
+            for corner in corners {
+                let vert_indices = corner.map(|i| i + vertex_offset);
+
+                let normal_indices = if has_normals {
+                    corner.map(|i| i + normal_offset)
+                } else {
+                    // No normals means flat shading
+                    let [a, b, c] = vert_indices.map(|i| vertices[i]);
+                    let normal = (b - a).cross(&(c - a));
+                    normals.push(if normal.length_squared() > 0.0 {
+                        normal.to_unit()
+                    } else {
+                        Vec4::vec(0.0, 0.0, 1.0)
+                    });
+                    [normals.len() - 1; 3]
+                };
+
+                triangles.push(Triangle {
+                    vert_indices,
+                    normal_indices,
+                    uv_indices: has_uvs.then(|| corner.map(|i| i + uv_offset)),
+                    material_index,
+                });
+            }
+        }
+
+        if triangles.is_empty() {
+            return Ok(None);
+        }
+
+        if !colors.is_empty() {
+            colors.resize(vertices.len(), Vec4::vec(1.0, 1.0, 1.0));
+        }
+
+        let mut mesh = TriangleMesh::new(vertices, uvs, normals, triangles, mesh_materials);
+        mesh.set_vertex_colors(colors);
+        mesh.hit_back_faces = double_sided;
+
+        Ok(Some(Arc::new(mesh)))
+    }
+
+    fn apply_camera(
+        &self,
+        camera_idx: usize,
+        world: &Mat4,
+        scene_config: &mut SceneConfig,
+    ) -> Result<(), Box<dyn Error>> {
+        let camera = self.item("cameras", camera_idx)?;
+        let Some(perspective) = camera.get("perspective") else {
+            println!("Warning: only perspective cameras are supported, camera ignored\n");
+            return Ok(());
+        };
+
+        // Cameras look along their local -Z axis. The position and target are
+        // kept for when flags place the camera by hand
+        scene_config.camera_pos = Some(*world * Vec4::point(0.0, 0.0, 0.0));
+        scene_config.camera_target = Some(*world * Vec4::point(0.0, 0.0, -1.0));
+        scene_config.camera_transform = Some(*world);
+
+        if let Some(aspect_ratio) = perspective.get("aspectRatio").and_then(JsonValue::as_f64) {
+            scene_config.aspect_ratio = Some(aspect_ratio);
+        }
+        let y_fov = perspective
+            .get("yfov")
+            .and_then(JsonValue::as_f64)
+            .ok_or(ParseError::boxed("Camera has no field of view"))?;
+        // Focal length for the same vertical FOV on a 36x24mm frame
+        scene_config.focal_length = Some(12.0 / f64::tan(y_fov / 2.0));
+
+        Ok(())
+    }
+
+    fn load_light(
+        &self,
+        light_idx: usize,
+        world: &Mat4,
+        contents: &mut SceneContents,
+    ) -> Result<(), Box<dyn Error>> {
+        let light = self
+            .document
+            .get("extensions")
+            .and_then(|ext| ext.get("KHR_lights_punctual"))
+            .and_then(|ext| ext.get("lights"))
+            .and_then(JsonValue::as_array)
+            .and_then(|lights| lights.get(light_idx))
+            .ok_or(ParseError::boxed(&format!(
+                "Invalid light index {light_idx}"
+            )))?;
+
+        let color = get_color(light, "color", Vec4::vec(1.0, 1.0, 1.0));
+        let intensity = get_f64(light, "intensity", 1.0);
+
+        // Lights point along their local -Z axis
+        let direction = (*world * Vec4::vec(0.0, 0.0, -1.0)).to_unit();
+        let center = *world * Vec4::point(0.0, 0.0, 0.0);
+
+        let light_type = light.get("type").and_then(JsonValue::as_str);
+        let object: Arc<dyn Hit> = match light_type {
+            Some("directional") => {
+                let sun = Sun::new(
+                    Arc::new(ConstantTexture::new(color * intensity)),
+                    -direction,
+                );
+                contents.distant_lights.push(Arc::new(sun));
+                return Ok(());
+            }
+            Some("point") => Arc::new(point_light(center, color * intensity)),
+            Some("spot") => {
+                let spot = light.get("spot");
+                let cone_angle = |key: &str, default: f64| {
+                    spot.map_or(default, |spot| get_f64(spot, key, default))
+                };
+
+                Arc::new(spot_light(
+                    center,
+                    direction,
+                    color * intensity,
+                    cone_angle("innerConeAngle", 0.0),
+                    cone_angle("outerConeAngle", PI / 4.0),
+                ))
+            }
+            _ => {
+                return Err(ParseError::boxed(&format!(
+                    "Invalid type for light {light_idx}"
+                )))
+            }
+        };
+
+        contents.objects.push(Arc::clone(&object));
+        contents.lights.push(object);
+
+        Ok(())
+    }
+}
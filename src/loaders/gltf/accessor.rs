@@ -0,0 +1,214 @@
+use std::error::Error;
+
+use crate::utils::ParseError;
+use crate::vec4::Vec4;
+
+use super::GltfLoader;
+
+const BYTE: u32 = 5120;
+const UNSIGNED_BYTE: u32 = 5121;
+const SHORT: u32 = 5122;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+/// Max number of elements of an accessor without a buffer view. Its elements
+/// are all zero, so a bogus count would only allocate memory.
+const MAX_ZERO_FILLED: usize = 1 << 24;
+
+/// A typed view into a buffer, such as the positions or indices of a mesh.
+pub(super) struct Accessor<'a> {
+    /// Data starting at the first element, or `None` if all values are zero.
+    data: Option<&'a [u8]>,
+    stride: usize,
+    component_type: u32,
+    components: usize,
+    normalized: bool,
+    count: usize,
+}
+
+impl Accessor<'_> {
+    /// Read a component of an element, as a float. Normalized integers are
+    /// mapped to [0; 1] or [-1; 1].
+    fn get(&self, element: usize, component: usize) -> f64 {
+        let Some(data) = self.data else {
+            return 0.0;
+        };
+
+        let size = component_size(self.component_type);
+        let offset = element * self.stride + component * size;
+        let bytes = &data[offset..(offset + size)];
+
+        match (self.component_type, self.normalized) {
+            (BYTE, false) => bytes[0] as i8 as f64,
+            (BYTE, true) => f64::max(bytes[0] as i8 as f64 / 127.0, -1.0),
+            (UNSIGNED_BYTE, false) => bytes[0] as f64,
+            (UNSIGNED_BYTE, true) => bytes[0] as f64 / 255.0,
+            (SHORT, false) => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            (SHORT, true) => f64::max(
+                i16::from_le_bytes([bytes[0], bytes[1]]) as f64 / 32767.0,
+                -1.0,
+            ),
+            (UNSIGNED_SHORT, false) => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            (UNSIGNED_SHORT, true) => u16::from_le_bytes([bytes[0], bytes[1]]) as f64 / 65535.0,
+            (UNSIGNED_INT, _) => {
+                u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+        }
+    }
+
+    /// Read all elements as vectors, using `make` to build a point or a
+    /// vector from the first three components. Missing components are zero.
+    pub fn read_vecs(&self, make: fn(f64, f64, f64) -> Vec4) -> Result<Vec<Vec4>, Box<dyn Error>> {
+        if !(2..=4).contains(&self.components) {
+            return Err(ParseError::boxed("Expected a vector accessor"));
+        }
+
+        Ok((0..self.count)
+            .map(|i| {
+                let z = if self.components > 2 {
+                    self.get(i, 2)
+                } else {
+                    0.0
+                };
+                make(self.get(i, 0), self.get(i, 1), z)
+            })
+            .collect())
+    }
+
+    /// Read all elements as RGB colors, ignoring alpha. Colors are stored as
+    /// floats or normalized integers.
+    pub fn read_colors(&self) -> Result<Vec<Vec4>, Box<dyn Error>> {
+        if !(3..=4).contains(&self.components) || !(self.component_type == FLOAT || self.normalized)
+        {
+            return Err(ParseError::boxed("Expected a color accessor"));
+        }
+
+        self.read_vecs(Vec4::vec)
+    }
+
+    /// Read all elements as indices into a list of `max` elements.
+    pub fn read_indices(&self, max: usize) -> Result<Vec<usize>, Box<dyn Error>> {
+        if self.components != 1
+            || self.normalized
+            || !matches!(
+                self.component_type,
+                UNSIGNED_BYTE | UNSIGNED_SHORT | UNSIGNED_INT
+            )
+        {
+            return Err(ParseError::boxed("Expected an integer index accessor"));
+        }
+
+        (0..self.count)
+            .map(|i| {
+                let idx = self.get(i, 0) as usize;
+                if idx < max {
+                    Ok(idx)
+                } else {
+                    Err(ParseError::boxed(&format!(
+                        "Out of range vertex index {idx}"
+                    )))
+                }
+            })
+            .collect()
+    }
+}
+
+fn component_size(component_type: u32) -> usize {
+    match component_type {
+        BYTE | UNSIGNED_BYTE => 1,
+        SHORT | UNSIGNED_SHORT => 2,
+        _ => 4,
+    }
+}
+
+impl GltfLoader {
+    /// Get an accessor by index, checking it's within the bounds of its buffer.
+    pub(super) fn accessor(&self, idx: usize) -> Result<Accessor<'_>, Box<dyn Error>> {
+        let accessor = self.item("accessors", idx)?;
+        if accessor.get("sparse").is_some() {
+            return Err(ParseError::boxed("Sparse accessors aren't supported"));
+        }
+
+        let component_type = Self::index(accessor, "componentType")?.unwrap_or(0) as u32;
+        if !matches!(
+            component_type,
+            BYTE | UNSIGNED_BYTE | SHORT | UNSIGNED_SHORT | UNSIGNED_INT | FLOAT
+        ) {
+            return Err(ParseError::boxed(&format!(
+                "Invalid component type {component_type}"
+            )));
+        }
+
+        let components = match accessor.get("type").and_then(|t| t.as_str()) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") | Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            _ => return Err(ParseError::boxed("Invalid accessor type")),
+        };
+        let normalized = accessor
+            .get("normalized")
+            .and_then(|n| n.as_bool())
+            .unwrap_or(false);
+        let count = Self::index(accessor, "count")?.unwrap_or(0);
+
+        let element_size = components * component_size(component_type);
+        let Some(view_idx) = Self::index(accessor, "bufferView")? else {
+            if count > MAX_ZERO_FILLED {
+                return Err(ParseError::boxed("Accessor out of range"));
+            }
+            return Ok(Accessor {
+                data: None,
+                stride: element_size,
+                component_type,
+                components,
+                normalized,
+                count,
+            });
+        };
+
+        let view = self.item("bufferViews", view_idx)?;
+        let view_data = self.buffer_view(view_idx)?;
+
+        let stride = Self::index(view, "byteStride")?.unwrap_or(element_size);
+        let offset = Self::index(accessor, "byteOffset")?.unwrap_or(0);
+        let end = match count {
+            0 => Some(offset),
+            _ => stride
+                .checked_mul(count - 1)
+                .and_then(|size| size.checked_add(offset))
+                .and_then(|end| end.checked_add(element_size)),
+        };
+        match end {
+            Some(end) if stride >= element_size && end <= view_data.len() => (),
+            _ => return Err(ParseError::boxed("Accessor out of range")),
+        }
+
+        Ok(Accessor {
+            data: Some(&view_data[offset..]),
+            stride,
+            component_type,
+            components,
+            normalized,
+            count,
+        })
+    }
+
+    /// Get the raw data of a buffer view, such as an embedded image.
+    pub(super) fn buffer_view(&self, idx: usize) -> Result<&[u8], Box<dyn Error>> {
+        let view = self.item("bufferViews", idx)?;
+        let buffer_idx = Self::index(view, "buffer")?.unwrap_or(0);
+        let offset = Self::index(view, "byteOffset")?.unwrap_or(0);
+        let length = Self::index(view, "byteLength")?.unwrap_or(0);
+
+        self.buffers
+            .get(buffer_idx)
+            .zip(offset.checked_add(length))
+            .and_then(|(buffer, end)| buffer.get(offset..end))
+            .ok_or(ParseError::boxed("Buffer view out of range"))
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use super::{get_color, get_f64, GltfLoader};
+use crate::buffer::Buffer;
+use crate::loaders::json::JsonValue;
+use crate::material::{Dielectric, Emissive, Glossy, Material, Metal};
+use crate::texture::image::TextureRepeat;
+use crate::texture::{
+    Channel, ConstantTexture, ImageTexture, Sampler, Scale, TexturePointer, VertexColorTint,
+};
+use crate::utils::ParseError;
+use crate::vec4::{Color, Vec4};
+
+const CLAMP_TO_EDGE: usize = 33071;
+
+/// Resolution of the grid a metallic map is sampled on, to decide whether a
+/// material is a metal.
+const METALLIC_SAMPLES: usize = 16;
+
+pub(super) struct LoadedMaterial {
+    pub material: Arc<dyn Material>,
+    pub double_sided: bool,
+}
+
+/// Loaded textures by index, `None` if a texture failed to load.
+type TextureCache = HashMap<usize, Option<Arc<ImageTexture>>>;
+
+fn extension<'a>(material: &'a JsonValue, name: &str) -> Option<&'a JsonValue> {
+    material.get("extensions").and_then(|ext| ext.get(name))
+}
+
+/// Apply a constant factor to an optional texture.
+fn scaled<T>(texture: Option<TexturePointer<T>>, factor: T, identity: T) -> TexturePointer<T>
+where
+    T: Send + Sync + Copy + PartialEq + std::ops::Mul<T, Output = T> + std::fmt::Debug + 'static,
+{
+    match texture {
+        Some(texture) if factor == identity => texture,
+        Some(texture) => Arc::new(Scale::new(texture, factor)),
+        None => Arc::new(ConstantTexture::new(factor)),
+    }
+}
+
+impl GltfLoader {
+    /// Load all materials of the file, mapped onto the closest material type.
+    /// glTF's default material is added last, for primitives with no
+    /// material.
+    pub(super) fn load_materials(&self) -> Vec<LoadedMaterial> {
+        let mut textures = TextureCache::new();
+        let default_material = JsonValue::Object(Vec::new());
+
+        self.array("materials")
+            .iter()
+            .chain([&default_material])
+            .map(|material| self.load_material(material, &mut textures))
+            .collect()
+    }
+
+    /// Map a material onto the closest material type:
+    /// - `Emissive` if it has an emissive color, or is unlit
+    /// - `Dielectric` if it's mostly transmissive
+    /// - `Metal` if it's mostly metallic
+    /// - `Glossy` otherwise
+    fn load_material(&self, material: &JsonValue, textures: &mut TextureCache) -> LoadedMaterial {
+        let double_sided = material
+            .get("doubleSided")
+            .and_then(JsonValue::as_bool)
+            .unwrap_or(false);
+        let done = |material: Arc<dyn Material>| LoadedMaterial {
+            material,
+            double_sided,
+        };
+
+        let empty = JsonValue::Object(Vec::new());
+        let pbr = material.get("pbrMetallicRoughness").unwrap_or(&empty);
+
+        let base_color = get_color(pbr, "baseColorFactor", Vec4::vec(1.0, 1.0, 1.0));
+        let base_texture = self.texture(pbr.get("baseColorTexture"), textures);
+        // Vertex colors (`COLOR_0`) also multiply the base color
+        let albedo: TexturePointer<Color> = Arc::new(VertexColorTint::new(scaled(
+            base_texture.map(|tex| tex as TexturePointer<Color>),
+            base_color,
+            Vec4::vec(1.0, 1.0, 1.0),
+        )));
+
+        let emissive_strength = extension(material, "KHR_materials_emissive_strength")
+            .map_or(1.0, |ext| get_f64(ext, "emissiveStrength", 1.0));
+        let emission =
+            get_color(material, "emissiveFactor", Vec4::vec(0.0, 0.0, 0.0)) * emissive_strength;
+        if emission.length_squared() > 0.0 {
+            let emission_texture = self.texture(material.get("emissiveTexture"), textures);
+            let emission_map = scaled(
+                emission_texture.map(|tex| tex as TexturePointer<Color>),
+                emission,
+                Vec4::vec(1.0, 1.0, 1.0),
+            );
+
+            let mut emissive = Emissive::new(emission_map);
+            emissive.two_sided = double_sided;
+            return done(Arc::new(emissive));
+        }
+
+        // Unlit materials show their base color regardless of lighting
+        if extension(material, "KHR_materials_unlit").is_some() {
+            let mut emissive = Emissive::new(albedo);
+            emissive.two_sided = double_sided;
+            return done(Arc::new(emissive));
+        }
+
+        let ior =
+            extension(material, "KHR_materials_ior").map_or(1.5, |ext| get_f64(ext, "ior", 1.5));
+        let transmission = extension(material, "KHR_materials_transmission")
+            .map_or(0.0, |ext| get_f64(ext, "transmissionFactor", 0.0));
+        if transmission >= 0.5 {
+            return done(Arc::new(Dielectric::new(ior)));
+        }
+
+        // Roughness is in the green channel and metalness in the blue channel
+        let metallic_roughness = self.texture(pbr.get("metallicRoughnessTexture"), textures);
+        let roughness = scaled(
+            metallic_roughness
+                .clone()
+                .map(|tex| Arc::new(Channel::new(tex, 1)) as TexturePointer<f64>),
+            get_f64(pbr, "roughnessFactor", 1.0),
+            1.0,
+        );
+
+        // Materials can't be partly metallic, so use the average metalness of the map
+        let mut metallic = get_f64(pbr, "metallicFactor", 1.0);
+        if let Some(map) = &metallic_roughness {
+            let origin = Vec4::point(0.0, 0.0, 0.0);
+            let sample = |i: usize| (i as f64 + 0.5) / METALLIC_SAMPLES as f64;
+            let total: f64 = (0..METALLIC_SAMPLES * METALLIC_SAMPLES)
+                .map(|i| {
+                    let uv = (sample(i % METALLIC_SAMPLES), sample(i / METALLIC_SAMPLES));
+                    map.sample(uv, &origin)[2]
+                })
+                .sum();
+            metallic *= total / (METALLIC_SAMPLES * METALLIC_SAMPLES) as f64;
+        }
+
+        if metallic >= 0.5 {
+            return done(Arc::new(Metal::new(albedo, roughness)));
+        }
+
+        let mut glossy = Glossy::new(albedo, roughness, ior);
+        if let Some(normal_map) = self.texture(material.get("normalTexture"), textures) {
+            glossy.normal_map = Some(normal_map);
+        }
+
+        done(Arc::new(glossy))
+    }
+
+    /// Load the texture referred to by a texture info object, if any. Failing
+    /// to load a texture isn't an error, the material is used without it.
+    fn texture(
+        &self,
+        info: Option<&JsonValue>,
+        textures: &mut TextureCache,
+    ) -> Option<Arc<ImageTexture>> {
+        let texture_idx = info?.get("index")?.as_usize()?;
+        if info?
+            .get("texCoord")
+            .and_then(JsonValue::as_usize)
+            .unwrap_or(0)
+            != 0
+        {
+            println!(
+                "Warning: texture {texture_idx} uses a second UV set, which isn't supported\n"
+            );
+        }
+
+        textures
+            .entry(texture_idx)
+            .or_insert_with(|| match self.load_texture(texture_idx) {
+                Ok(texture) => Some(Arc::new(texture)),
+                Err(err) => {
+                    println!("Warning: failed to load texture {texture_idx}");
+                    println!("\t{err}\n");
+                    None
+                }
+            })
+            .clone()
+    }
+
+    fn load_texture(&self, texture_idx: usize) -> Result<ImageTexture, Box<dyn Error>> {
+        let texture = self.item("textures", texture_idx)?;
+        let image_idx =
+            Self::index(texture, "source")?.ok_or(ParseError::boxed("Texture has no image"))?;
+        let image = self.item("images", image_idx)?;
+
+        let buffer = match (
+            image.get("uri").and_then(JsonValue::as_str),
+            Self::index(image, "bufferView")?,
+        ) {
+            (Some(uri), _) => Buffer::from_image_bytes(&self.load_uri(uri)?)?,
+            (None, Some(view_idx)) => Buffer::from_image_bytes(self.buffer_view(view_idx)?)?,
+            (None, None) => return Err(ParseError::boxed("Image has no data")),
+        };
+        let mut image_texture = ImageTexture::from_buffer(buffer);
+
+        if let Some(sampler_idx) = Self::index(texture, "sampler")? {
+            let sampler = self.item("samplers", sampler_idx)?;
+            // Wrapping can't be set per axis, clamp only if both axes are clamped
+            let wrap = |key: &str| sampler.get(key).and_then(JsonValue::as_usize);
+            if wrap("wrapS") == Some(CLAMP_TO_EDGE) && wrap("wrapT") == Some(CLAMP_TO_EDGE) {
+                image_texture.repeat = TextureRepeat::Clamp;
+            }
+        }
+
+        Ok(image_texture)
+    }
+}
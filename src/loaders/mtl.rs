@@ -0,0 +1,217 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    material::{Dielectric, Emissive, Glossy, Material, Metal},
+    texture::{Channel, ConstantTexture, ImageTexture, TexturePointer},
+    vec4::{Color, Vec4},
+};
+
+use super::obj::ObjError;
+
+/// A material from a Wavefront MTL file.
+///
+/// Supports the classic Phong parameters along with the common PBR
+/// extension (`Pr`, `Pm`, `Ke`, `map_Pr`, `norm`). Bump maps (`bump`,
+/// `map_Bump`) are scalar heights rather than normals, so they're ignored.
+#[derive(Debug)]
+pub struct MtlMaterial {
+    diffuse: Color,
+    specular: Color,
+    emission: Color,
+    specular_exponent: Option<f64>,
+    ior: f64,
+    opacity: f64,
+    illum: u32,
+    roughness: Option<f64>,
+    metallic: f64,
+
+    diffuse_map: Option<PathBuf>,
+    emission_map: Option<PathBuf>,
+    roughness_map: Option<PathBuf>,
+    normal_map: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        MtlMaterial {
+            diffuse: Vec4::vec(0.8, 0.8, 0.8),
+            specular: Vec4::vec(0.0, 0.0, 0.0),
+            emission: Vec4::vec(0.0, 0.0, 0.0),
+            specular_exponent: None,
+            ior: 1.5,
+            opacity: 1.0,
+            illum: 2,
+            roughness: None,
+            metallic: 0.0,
+            diffuse_map: None,
+            emission_map: None,
+            roughness_map: None,
+            normal_map: None,
+        }
+    }
+}
+
+fn load_texture(path: &Path) -> Option<Arc<ImageTexture>> {
+    match ImageTexture::from_file(&path.to_string_lossy()) {
+        Ok(tex) => Some(Arc::new(tex)),
+        Err(err) => {
+            println!("Warning: failed to load texture {}", path.display());
+            println!("\t{err}\n");
+            None
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Map the material onto the closest material type:
+    /// - `Emissive` if it has an emission color or map
+    /// - `Dielectric` if it's transparent (`d` < 1, `Tr` > 0 or a refractive `illum`)
+    /// - `Metal` if it's metallic (`Pm` >= 0.5) or a mirror (`illum 3`)
+    /// - `Glossy` otherwise
+    pub fn to_material(&self) -> Arc<dyn Material> {
+        if let Some(map) = self.emission_map.as_deref().and_then(load_texture) {
+            return Arc::new(Emissive::new(map));
+        }
+        if self.emission.length_squared() > 0.0 {
+            return Arc::new(Emissive::new(Arc::new(ConstantTexture::new(self.emission))));
+        }
+
+        if self.opacity < 1.0 || matches!(self.illum, 4 | 6 | 7 | 9) {
+            return Arc::new(Dielectric::new(self.ior));
+        }
+
+        // Roughness from the Phong exponent, matching the width of its highlight
+        let roughness = self
+            .roughness
+            .unwrap_or_else(|| match self.specular_exponent {
+                Some(ns) => f64::sqrt(2.0 / (ns.max(0.0) + 2.0)),
+                None => 1.0,
+            });
+        let roughness: TexturePointer<f64> =
+            match self.roughness_map.as_deref().and_then(load_texture) {
+                Some(map) => Arc::new(Channel::new(map, 0)),
+                None => Arc::new(ConstantTexture::new(roughness.clamp(0.0, 1.0))),
+            };
+
+        let is_mirror = self.illum == 3 && self.specular.length_squared() > 0.0;
+        let base_color = if is_mirror {
+            self.specular
+        } else {
+            self.diffuse
+        };
+        let albedo: TexturePointer<Color> = match self.diffuse_map.as_deref().and_then(load_texture)
+        {
+            Some(map) if !is_mirror => map,
+            _ => Arc::new(ConstantTexture::new(base_color)),
+        };
+
+        if self.metallic >= 0.5 || is_mirror {
+            return Arc::new(Metal::new(albedo, roughness));
+        }
+
+        let mut glossy = Glossy::new(albedo, roughness, self.ior);
+        if let Some(map) = self.normal_map.as_deref().and_then(load_texture) {
+            glossy.normal_map = Some(map);
+        }
+
+        Arc::new(glossy)
+    }
+}
+
+/// Load all materials from an MTL file, by name. Texture paths are resolved
+/// relative to the file.
+pub fn load_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>, Box<dyn std::error::Error>> {
+    let reader = BufReader::new(File::open(path)?);
+    let base_path = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line_number = line_idx + 1;
+        let line = line?;
+        let line = line.split('#').next().unwrap_or("");
+
+        let mut params = line.split_whitespace();
+        let Some(statement) = params.next() else {
+            continue;
+        };
+        let params: Vec<_> = params.collect();
+
+        if statement == "newmtl" {
+            if let Some((name, material)) = current.take() {
+                materials.insert(name, material);
+            }
+            current = Some((params.join(" "), MtlMaterial::default()));
+            continue;
+        }
+
+        let Some((_, material)) = &mut current else {
+            return Err(Box::new(ObjError::new(
+                line_number,
+                "Material statement before newmtl",
+            )));
+        };
+
+        let float = |i: usize| -> Result<f64, ObjError> {
+            let value = params
+                .get(i)
+                .ok_or(ObjError::new(line_number, "Missing value"))?;
+            value
+                .parse::<f64>()
+                .map_err(|_| ObjError::new(line_number, &format!("Invalid number {value}")))
+        };
+        let color = || -> Result<Color, ObjError> {
+            let r = float(0)?;
+            // A single value is used for all channels
+            if params.len() < 3 {
+                Ok(Vec4::vec(r, r, r))
+            } else {
+                Ok(Vec4::vec(r, float(1)?, float(2)?))
+            }
+        };
+        // Map statements can have options before the file name, so use the last parameter
+        let map_path = || -> Result<PathBuf, ObjError> {
+            match params.last() {
+                Some(file) => Ok(base_path.join(file)),
+                None => Err(ObjError::new(line_number, "Missing texture file")),
+            }
+        };
+
+        match statement {
+            "Kd" => material.diffuse = color()?,
+            "Ks" => material.specular = color()?,
+            "Ke" => material.emission = color()?,
+            "Ns" => material.specular_exponent = Some(float(0)?),
+            "Ni" => material.ior = float(0)?,
+            "d" => material.opacity = float(0)?,
+            "Tr" => material.opacity = 1.0 - float(0)?,
+            "illum" => material.illum = float(0)? as u32,
+            "Pr" => material.roughness = Some(float(0)?),
+            "Pm" => material.metallic = float(0)?,
+            "map_Kd" => material.diffuse_map = Some(map_path()?),
+            "map_Ke" => material.emission_map = Some(map_path()?),
+            "map_Pr" => material.roughness_map = Some(map_path()?),
+            "norm" => material.normal_map = Some(map_path()?),
+            // Bump maps hold heights, which can't be used as a normal map
+            "map_Bump" | "bump" => {
+                let path = map_path()?;
+                println!("Warning: bump maps aren't supported, use norm for normal maps");
+                println!("\tIgnoring {} on line {line_number}\n", path.display());
+            }
+            _ => (),
+        }
+    }
+
+    if let Some((name, material)) = current {
+        materials.insert(name, material);
+    }
+
+    Ok(materials)
+}
@@ -1,107 +1,472 @@
 use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Display,
     fs::File,
-    io::{self, BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read, Write},
+    path::Path,
     sync::Arc,
 };
 
-use crate::vec4::{Point4, Vec4};
+use crate::binary::{ReadBinary, WriteBinary};
+use crate::vec4::{Color, Point4, Vec4};
 use crate::{
     material::Material,
-    object::mesh::{Triangle, TriangleMesh},
+    object::mesh::{
+        subdivide::{ControlMesh, Face},
+        vertex_normals, Triangle, TriangleMesh,
+    },
 };
 
-pub fn load_mesh_from_file(file: &File, material: Arc<dyn Material>) -> io::Result<TriangleMesh> {
-    let reader = BufReader::new(file);
+use super::mtl::{self, MtlMaterial};
 
-    let mut vertex_positions: Vec<Point4> = Vec::new();
-    let mut vertex_uvs: Vec<Point4> = Vec::new();
-    let mut vertex_normals: Vec<Vec4> = Vec::new();
+/// An error in an OBJ or MTL file.
+#[derive(Debug)]
+pub struct ObjError {
+    line: usize,
+    message: String,
+}
 
-    let mut triangles: Vec<Triangle> = Vec::new();
+impl ObjError {
+    pub fn new(line: usize, message: &str) -> Self {
+        ObjError {
+            line,
+            message: message.to_owned(),
+        }
+    }
+}
 
-    // Parse OBJ file
-    for line in reader.lines().flatten() {
-        let mut params = line.split(' ');
-        let cmd = params.next();
+impl Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Parse error on line {}: {}", self.line, self.message)
+    }
+}
 
-        match cmd {
-            Some("v") => {
-                let params: Vec<_> = params.map(|p| p.parse::<f64>().unwrap()).collect();
-                assert!(params.len() >= 3);
+impl Error for ObjError {}
 
-                vertex_positions.push(Vec4::point(params[0], params[1], params[2]));
+/// Geometry parsed from an OBJ file. Polygons are triangulated, and normals
+/// are generated for faces that don't have any.
+#[derive(Debug)]
+pub struct ObjModel {
+    vertices: Vec<Point4>,
+    /// Colors by vertex index, from the `v x y z r g b` extension. Empty if no
+    /// vertex has a color, otherwise vertices without one are white.
+    colors: Vec<Color>,
+    uvs: Vec<Vec4>,
+    normals: Vec<Vec4>,
+    /// Triangles, with material indices in `material_slots`.
+    triangles: Vec<Triangle>,
+    /// Number of corners of each face, which are split into consecutive
+    /// triangles.
+    face_sizes: Vec<usize>,
+    material_slots: MaterialSlots,
+}
+
+/// The materials an OBJ file refers to, by name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialSlots {
+    /// Material libraries set with `mtllib`, relative to the OBJ file.
+    pub libraries: Vec<String>,
+    /// Materials set with `usemtl`, in order of first use. `None` is used
+    /// for faces before any `usemtl` statement.
+    pub names: Vec<Option<String>>,
+}
+
+impl Default for MaterialSlots {
+    fn default() -> Self {
+        MaterialSlots {
+            libraries: Vec::new(),
+            names: vec![None],
+        }
+    }
+}
+
+impl MaterialSlots {
+    /// Load the materials from the material libraries, in slot order.
+    /// `default_material` is used for faces with no material, or one that
+    /// can't be found. Libraries are relative to `base_path`.
+    pub fn load(
+        &self,
+        base_path: &Path,
+        default_material: &Arc<dyn Material>,
+    ) -> Vec<Arc<dyn Material>> {
+        let mut libraries: HashMap<String, MtlMaterial> = HashMap::new();
+        for lib in &self.libraries {
+            let lib_path = base_path.join(lib);
+            match mtl::load_mtl(&lib_path) {
+                Ok(materials) => libraries.extend(materials),
+                Err(err) => {
+                    println!(
+                        "Warning: failed to load material library {}",
+                        lib_path.display()
+                    );
+                    println!("\t{err}\n");
+                }
             }
-            Some("vt") => {
-                let params: Vec<_> = params.map(|p| p.parse::<f64>().unwrap()).collect();
-                assert!(params.len() >= 2);
-
-                vertex_uvs.push(Vec4::vec(
-                    params[0],
-                    params[1],
-                    *params.get(2).unwrap_or(&0.0),
-                ));
+        }
+
+        self.names
+            .iter()
+            .map(|name| match name {
+                Some(name) => match libraries.get(name) {
+                    Some(mtl) => mtl.to_material(),
+                    None => {
+                        println!("Warning: material {name} not found, using default");
+                        Arc::clone(default_material)
+                    }
+                },
+                None => Arc::clone(default_material),
+            })
+            .collect()
+    }
+
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_u32(self.libraries.len() as u32)?;
+        for lib in &self.libraries {
+            w.write_str(lib)?;
+        }
+
+        w.write_u32(self.names.len() as u32)?;
+        for name in &self.names {
+            match name {
+                Some(name) => {
+                    w.write_u32(1)?;
+                    w.write_str(name)?;
+                }
+                None => w.write_u32(0)?,
             }
-            Some("vn") => {
-                let params: Vec<_> = params.map(|p| p.parse::<f64>().unwrap()).collect();
-                assert!(params.len() >= 3);
+        }
+
+        Ok(())
+    }
 
-                vertex_normals.push(Vec4::vec(params[0], params[1], params[2]).to_unit());
+    pub fn read_from(r: &mut impl Read) -> io::Result<Self> {
+        let lib_count = r.read_index(MAX_SLOTS)?;
+        let libraries = (0..lib_count)
+            .map(|_| r.read_str())
+            .collect::<io::Result<_>>()?;
+
+        let name_count = r.read_index(MAX_SLOTS)?;
+        let names = (0..name_count)
+            .map(|_| match r.read_u32()? {
+                0 => Ok(None),
+                _ => r.read_str().map(Some),
+            })
+            .collect::<io::Result<_>>()?;
+
+        Ok(MaterialSlots { libraries, names })
+    }
+}
+
+/// Max number of material libraries or materials read from a binary file.
+const MAX_SLOTS: usize = 1 << 16;
+
+/// Materials to apply to a mesh loaded from a file.
+pub enum MeshMaterials {
+    /// Use a single material for every face.
+    Single(Arc<dyn Material>),
+    /// Use the materials from the file's material libraries, with a default
+    /// for faces with no material.
+    FromFile(Arc<dyn Material>),
+}
+
+impl MeshMaterials {
+    /// Get the material table for a mesh file with the given slots.
+    pub fn resolve(&self, slots: &MaterialSlots, mesh_path: &Path) -> Vec<Arc<dyn Material>> {
+        match self {
+            Self::Single(material) => vec![Arc::clone(material); slots.names.len().max(1)],
+            Self::FromFile(default_material) => {
+                let base_path = mesh_path.parent().unwrap_or(Path::new(""));
+                slots.load(base_path, default_material)
             }
-            Some("f") => {
-                let params = params.map(|p| {
-                    let parts = p.split('/').map(|n| match n {
-                        "" => None,
-                        _ => Some(n.parse::<i32>().unwrap()),
-                    });
-                    parts
-                });
-
-                let mut i_vert: [usize; 3] = [0; 3];
-                let mut i_norm: [usize; 3] = [0; 3];
-                let mut i_uv: [Option<usize>; 3] = [None; 3];
-
-                for (i, mut param) in params.enumerate() {
-                    i_vert[i] = match param.next().unwrap().unwrap() {
-                        idx if idx > 0 => idx - 1,
-                        idx if idx < 0 => vertex_positions.len() as i32 + idx,
-                        _ => 0,
-                    } as usize;
-                    i_uv[i] = match param.next().unwrap() {
-                        Some(idx) if idx > 0 => Some(idx as usize - 1),
-                        Some(idx) if idx < 0 => Some((vertex_uvs.len() as i32 + idx) as usize),
-                        Some(_) => Some(0),
-                        None => None,
-                    };
-                    i_norm[i] = match param.next().unwrap().unwrap() {
-                        idx if idx > 0 => idx - 1,
-                        idx if idx < 0 => vertex_normals.len() as i32 + idx,
-                        _ => 0,
-                    } as usize;
+        }
+    }
+}
+
+/// Parse the numbers following an OBJ statement.
+fn parse_floats<'a>(
+    params: impl Iterator<Item = &'a str>,
+    line: usize,
+) -> Result<Vec<f64>, ObjError> {
+    params
+        .map(|p| {
+            p.parse::<f64>()
+                .map_err(|_| ObjError::new(line, &format!("Invalid number {p}")))
+        })
+        .collect()
+}
+
+/// Resolve a 1-based (or negative, relative) OBJ index into a list of `count`
+/// elements.
+fn resolve_index(token: &str, count: usize, line: usize, kind: &str) -> Result<usize, ObjError> {
+    let idx = token
+        .parse::<i64>()
+        .map_err(|_| ObjError::new(line, &format!("Invalid {kind} index {token}")))?;
+
+    let resolved = match idx {
+        idx if idx > 0 => idx - 1,
+        idx if idx < 0 => count as i64 + idx,
+        _ => -1,
+    };
+
+    if resolved < 0 || resolved >= count as i64 {
+        Err(ObjError::new(
+            line,
+            &format!("Out of range {kind} index {idx}"),
+        ))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+impl ObjModel {
+    pub fn parse(reader: impl BufRead) -> Result<Self, ObjError> {
+        let mut model = ObjModel {
+            vertices: Vec::new(),
+            colors: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            triangles: Vec::new(),
+            face_sizes: Vec::new(),
+            material_slots: MaterialSlots::default(),
+        };
+
+        let mut current_material = 0;
+        // Color of vertices without one, in models with vertex colors
+        let white = Vec4::vec(1.0, 1.0, 1.0);
+        // Triangles with no normals in the file, to generate them for
+        let mut missing_normals = Vec::new();
+
+        let mut statement = String::new();
+        let mut statement_line = 0;
+        for (line_idx, line) in reader.lines().enumerate() {
+            let line_number = line_idx + 1;
+            let line = line.map_err(|err| ObjError::new(line_number, &err.to_string()))?;
+
+            if statement.is_empty() {
+                statement_line = line_number;
+            }
+
+            // Lines ending with a backslash continue on the next line
+            let line = line.split('#').next().unwrap_or("");
+            if let Some(start) = line.trim_end().strip_suffix('\\') {
+                statement.push_str(start);
+                statement.push(' ');
+                continue;
+            }
+            statement.push_str(line);
+
+            let line = statement_line;
+            let mut params = statement.split_whitespace();
+            match params.next() {
+                Some("v") => {
+                    let values = parse_floats(params, line)?;
+                    if values.len() < 3 {
+                        return Err(ObjError::new(line, "Vertex needs 3 coordinates"));
+                    }
+
+                    model
+                        .vertices
+                        .push(Vec4::point(values[0], values[1], values[2]));
+
+                    // Colors may follow the position, as written by scanners and some exporters
+                    if let [_, _, _, r, g, b, ..] = values[..] {
+                        model.colors.resize(model.vertices.len() - 1, white);
+                        model.colors.push(Vec4::vec(r, g, b));
+                    }
                 }
+                Some("vt") => {
+                    let values = parse_floats(params, line)?;
+                    if values.is_empty() {
+                        return Err(ObjError::new(line, "UV needs at least 1 coordinate"));
+                    }
 
-                let has_uvs = i_uv[0].is_some() && i_uv[1].is_some() && i_uv[2].is_some();
+                    model.uvs.push(Vec4::vec(
+                        values[0],
+                        *values.get(1).unwrap_or(&0.0),
+                        *values.get(2).unwrap_or(&0.0),
+                    ));
+                }
+                Some("vn") => {
+                    let values = parse_floats(params, line)?;
+                    if values.len() < 3 {
+                        return Err(ObjError::new(line, "Normal needs 3 coordinates"));
+                    }
 
-                triangles.push(Triangle {
-                    vert_indices: i_vert,
-                    normal_indices: i_norm,
-                    uv_indices: if has_uvs {
-                        Some(i_uv.map(|x| x.unwrap()))
+                    let normal = Vec4::vec(values[0], values[1], values[2]);
+                    model.normals.push(if normal.length_squared() > 0.0 {
+                        normal.to_unit()
                     } else {
-                        None
-                    },
-                })
+                        Vec4::vec(0.0, 1.0, 0.0)
+                    });
+                }
+                Some("f") => {
+                    let mut corners = Vec::new();
+                    for corner in params {
+                        let mut parts = corner.split('/');
+                        let vert = parts.next().unwrap_or("");
+                        let uv = parts.next().filter(|p| !p.is_empty());
+                        let normal = parts.next().filter(|p| !p.is_empty());
+
+                        corners.push((
+                            resolve_index(vert, model.vertices.len(), line, "vertex")?,
+                            uv.map(|uv| resolve_index(uv, model.uvs.len(), line, "uv"))
+                                .transpose()?,
+                            normal
+                                .map(|n| resolve_index(n, model.normals.len(), line, "normal"))
+                                .transpose()?,
+                        ));
+                    }
+
+                    if corners.len() < 3 {
+                        return Err(ObjError::new(line, "Face needs at least 3 vertices"));
+                    }
+
+                    // Triangulate polygons as a fan around the first corner
+                    model.face_sizes.push(corners.len());
+                    for i in 1..(corners.len() - 1) {
+                        let tri = [corners[0], corners[i], corners[i + 1]];
+
+                        let uv_indices = match tri.map(|c| c.1) {
+                            [Some(a), Some(b), Some(c)] => Some([a, b, c]),
+                            _ => None,
+                        };
+                        let normal_indices = match tri.map(|c| c.2) {
+                            [Some(a), Some(b), Some(c)] => [a, b, c],
+                            _ => {
+                                missing_normals.push(model.triangles.len());
+                                [0; 3]
+                            }
+                        };
+
+                        model.triangles.push(Triangle {
+                            vert_indices: tri.map(|c| c.0),
+                            normal_indices,
+                            uv_indices,
+                            material_index: current_material,
+                        });
+                    }
+                }
+                Some("usemtl") => {
+                    let name = params.collect::<Vec<_>>().join(" ");
+                    let name = Some(name);
+
+                    let names = &mut model.material_slots.names;
+                    current_material = match names.iter().position(|n| *n == name) {
+                        Some(idx) => idx,
+                        None => {
+                            names.push(name);
+                            names.len() - 1
+                        }
+                    };
+                }
+                Some("mtllib") => {
+                    let libraries = &mut model.material_slots.libraries;
+                    libraries.extend(params.map(|p| p.to_owned()));
+                }
+                // Objects, groups, smoothing groups, lines, curves, etc. aren't used
+                Some(_) | None => (),
             }
-            Some(_) => continue,
-            None => continue,
+
+            statement.clear();
+        }
+
+        if !model.colors.is_empty() {
+            model.colors.resize(model.vertices.len(), white);
+        }
+        model.generate_normals(&missing_normals);
+
+        Ok(model)
+    }
+
+    /// Generate smooth vertex normals for a set of triangles, weighted by
+    /// triangle area.
+    fn generate_normals(&mut self, triangles: &[usize]) {
+        if triangles.is_empty() {
+            return;
+        }
+
+        let normals = vertex_normals(
+            &self.vertices,
+            triangles.iter().map(|&idx| &self.triangles[idx]),
+        );
+
+        let offset = self.normals.len();
+        self.normals.extend(normals);
+
+        for &idx in triangles {
+            let triangle = &mut self.triangles[idx];
+            triangle.normal_indices = triangle.vert_indices.map(|v| v + offset);
+        }
+    }
+
+    /// Rebuild the model's polygons from their triangles, for subdivision.
+    /// Normals aren't kept, since subdivision changes the surface.
+    pub fn into_control_mesh(self) -> ControlMesh {
+        let mut faces = Vec::with_capacity(self.face_sizes.len());
+        let mut triangles = self.triangles.iter();
+
+        for &size in &self.face_sizes {
+            // Each triangle of a fan adds its last corner to the polygon
+            let fan: Vec<&Triangle> = triangles.by_ref().take(size - 2).collect();
+            let [first, second, _] = fan[0].vert_indices;
+            let mut vert_indices = vec![first, second];
+            vert_indices.extend(fan.iter().map(|tri| tri.vert_indices[2]));
+
+            let fan_uvs: Option<Vec<[usize; 3]>> = fan.iter().map(|tri| tri.uv_indices).collect();
+            let uv_indices = fan_uvs.map(|fan_uvs| {
+                let mut uv_indices = vec![fan_uvs[0][0], fan_uvs[0][1]];
+                uv_indices.extend(fan_uvs.iter().map(|uv| uv[2]));
+                uv_indices
+            });
+
+            faces.push(Face {
+                vert_indices,
+                uv_indices,
+                material_index: fan[0].material_index,
+            });
         }
+
+        ControlMesh {
+            vertices: self.vertices,
+            uvs: self.uvs,
+            colors: self.colors,
+            faces,
+        }
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn material_slots(&self) -> &MaterialSlots {
+        &self.material_slots
     }
 
-    println!("Loaded {} tris", triangles.len());
-    Ok(TriangleMesh::new(
-        vertex_positions,
-        vertex_uvs,
-        vertex_normals,
-        triangles,
-        material,
-    ))
+    /// Build a mesh from the model. `obj_path` is used to find material
+    /// libraries.
+    pub fn into_mesh(self, materials: &MeshMaterials, obj_path: &Path) -> TriangleMesh {
+        let materials = materials.resolve(&self.material_slots, obj_path);
+
+        let mut mesh = TriangleMesh::new(
+            self.vertices,
+            self.uvs,
+            self.normals,
+            self.triangles,
+            materials,
+        );
+        mesh.set_vertex_colors(self.colors);
+
+        mesh
+    }
+}
+
+pub fn load_mesh_from_file(
+    file: &File,
+    material: Arc<dyn Material>,
+) -> Result<TriangleMesh, ObjError> {
+    let model = ObjModel::parse(BufReader::new(file))?;
+
+    println!("Loaded {} tris", model.triangle_count());
+    Ok(model.into_mesh(&MeshMaterials::Single(material), Path::new("")))
 }
@@ -0,0 +1,541 @@
+//! Stanford PLY mesh loader, for ASCII and binary (little and big-endian)
+//! files.
+//!
+//! Reads vertex positions with optional normals, UVs and colors, and faces
+//! as lists of vertex indices. Other elements and properties are skipped.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+};
+
+use crate::object::mesh::{vertex_normals, Triangle, TriangleMesh};
+use crate::utils::ParseError;
+use crate::vec4::{Color, Point4, Vec4};
+
+use super::obj::{MaterialSlots, MeshMaterials};
+
+/// Max number of lines in a header, so a file that isn't a PLY file isn't
+/// read to the end looking for `end_header`.
+const MAX_HEADER_LINES: usize = 1 << 12;
+
+/// Max number of items reserved up front for an element, so a bogus count in
+/// the header can't allocate a huge amount of memory.
+const MAX_RESERVED: usize = 1 << 20;
+
+/// Max number of vertices of a single face.
+const MAX_FACE_VERTICES: usize = 1 << 10;
+
+/// Whether a path has the `.ply` extension.
+pub fn is_ply_path(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ply"))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "char" | "int8" => Some(Self::I8),
+            "uchar" | "uint8" => Some(Self::U8),
+            "short" | "int16" => Some(Self::I16),
+            "ushort" | "uint16" => Some(Self::U16),
+            "int" | "int32" => Some(Self::I32),
+            "uint" | "uint32" => Some(Self::U32),
+            "float" | "float32" => Some(Self::F32),
+            "double" | "float64" => Some(Self::F64),
+            _ => None,
+        }
+    }
+
+    /// Scale that maps values of the type to [0; 1], for colors.
+    fn color_scale(self) -> f64 {
+        match self {
+            Self::I8 => 1.0 / i8::MAX as f64,
+            Self::U8 => 1.0 / u8::MAX as f64,
+            Self::I16 => 1.0 / i16::MAX as f64,
+            Self::U16 => 1.0 / u16::MAX as f64,
+            Self::I32 => 1.0 / i32::MAX as f64,
+            Self::U32 => 1.0 / u32::MAX as f64,
+            Self::F32 | Self::F64 => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum PropertyType {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyType,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    /// Index of the first scalar property with one of the given names.
+    fn scalar(&self, names: &[&str]) -> Option<(usize, ScalarType)> {
+        names.iter().find_map(|name| {
+            self.properties
+                .iter()
+                .enumerate()
+                .find_map(|(idx, property)| match property.kind {
+                    PropertyType::Scalar(ty) if property.name == *name => Some((idx, ty)),
+                    _ => None,
+                })
+        })
+    }
+}
+
+/// Parse the header, leaving the reader at the start of the data.
+fn read_header(r: &mut impl BufRead) -> Result<(Format, Vec<Element>), Box<dyn Error>> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+
+    let mut line = Vec::new();
+    for line_idx in 0..MAX_HEADER_LINES {
+        line.clear();
+        if r.read_until(b'\n', &mut line)? == 0 {
+            return Err(ParseError::boxed("Unexpected end of header"));
+        }
+        let text = String::from_utf8_lossy(&line);
+        let mut words = text.split_ascii_whitespace();
+        let keyword = words.next().unwrap_or("");
+
+        if line_idx == 0 {
+            if keyword != "ply" {
+                return Err(ParseError::boxed("Not a PLY file"));
+            }
+            continue;
+        }
+
+        match keyword {
+            "format" => {
+                format = match (words.next(), words.next()) {
+                    (Some("ascii"), Some("1.0")) => Some(Format::Ascii),
+                    (Some("binary_little_endian"), Some("1.0")) => Some(Format::BinaryLittleEndian),
+                    (Some("binary_big_endian"), Some("1.0")) => Some(Format::BinaryBigEndian),
+                    _ => {
+                        return Err(ParseError::boxed(&format!(
+                            "Unsupported format '{}'",
+                            text.trim()
+                        )))
+                    }
+                }
+            }
+            "element" => {
+                let (Some(name), Some(count)) = (words.next(), words.next()) else {
+                    return Err(ParseError::boxed("Invalid element declaration"));
+                };
+                let count = count
+                    .parse()
+                    .map_err(|_| ParseError::boxed(&format!("Invalid count for element {name}")))?;
+
+                elements.push(Element {
+                    name: name.to_owned(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            "property" => {
+                let element = elements
+                    .last_mut()
+                    .ok_or(ParseError::boxed("Property declared before any element"))?;
+                let invalid = || ParseError::boxed(&format!("Invalid property '{}'", text.trim()));
+
+                let property = match words.next() {
+                    Some("list") => {
+                        let count = words.next().and_then(ScalarType::parse);
+                        let item = words.next().and_then(ScalarType::parse);
+                        match (count, item, words.next()) {
+                            (Some(count), Some(item), Some(name)) => Property {
+                                name: name.to_owned(),
+                                kind: PropertyType::List { count, item },
+                            },
+                            _ => return Err(invalid()),
+                        }
+                    }
+                    ty => match (ty.and_then(ScalarType::parse), words.next()) {
+                        (Some(ty), Some(name)) => Property {
+                            name: name.to_owned(),
+                            kind: PropertyType::Scalar(ty),
+                        },
+                        _ => return Err(invalid()),
+                    },
+                };
+                element.properties.push(property);
+            }
+            "end_header" => {
+                let format = format.ok_or(ParseError::boxed("Missing format"))?;
+                return Ok((format, elements));
+            }
+            "comment" | "obj_info" | "" => (),
+            _ => {
+                return Err(ParseError::boxed(&format!(
+                    "Unknown header line '{}'",
+                    text.trim()
+                )))
+            }
+        }
+    }
+
+    Err(ParseError::boxed("Header is too long"))
+}
+
+/// Source of the values of elements, in the file's format.
+trait ValueReader {
+    fn read_value(&mut self, ty: ScalarType) -> Result<f64, Box<dyn Error>>;
+
+    /// Read a property, appending its values to `values`.
+    fn read_property(
+        &mut self,
+        kind: PropertyType,
+        values: &mut Vec<f64>,
+    ) -> Result<(), Box<dyn Error>> {
+        match kind {
+            PropertyType::Scalar(ty) => values.push(self.read_value(ty)?),
+            PropertyType::List { count, item } => {
+                let count = self.read_value(count)?;
+                if !(0.0..=MAX_FACE_VERTICES as f64).contains(&count) {
+                    return Err(ParseError::boxed(&format!("Invalid list length {count}")));
+                }
+                for _ in 0..(count as usize) {
+                    values.push(self.read_value(item)?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct AsciiReader<'a> {
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl ValueReader for AsciiReader<'_> {
+    fn read_value(&mut self, ty: ScalarType) -> Result<f64, Box<dyn Error>> {
+        let token = self
+            .tokens
+            .next()
+            .ok_or(ParseError::boxed("Unexpected end of file"))?;
+        let value: f64 = token
+            .parse()
+            .map_err(|_| ParseError::boxed(&format!("Invalid number {token}")))?;
+
+        match ty {
+            ScalarType::F32 | ScalarType::F64 => Ok(value),
+            _ if value.fract() == 0.0 => Ok(value),
+            _ => Err(ParseError::boxed(&format!(
+                "Expected an integer, got {token}"
+            ))),
+        }
+    }
+}
+
+struct BinaryReader<R: Read> {
+    reader: R,
+    big_endian: bool,
+}
+
+impl<R: Read> BinaryReader<R> {
+    fn read_bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.reader.read_exact(&mut bytes)?;
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+}
+
+impl<R: Read> ValueReader for BinaryReader<R> {
+    fn read_value(&mut self, ty: ScalarType) -> Result<f64, Box<dyn Error>> {
+        // Bytes are swapped to little-endian when reading
+        let value = match ty {
+            ScalarType::I8 => i8::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::U8 => u8::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::I16 => i16::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::U16 => u16::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::I32 => i32::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::U32 => u32::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::F32 => f32::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::F64 => f64::from_le_bytes(self.read_bytes()?),
+        };
+
+        Ok(value)
+    }
+}
+
+/// Geometry parsed from a PLY file. Polygons are triangulated, and smooth
+/// normals are generated if the file has none.
+#[derive(Debug)]
+pub struct PlyModel {
+    vertices: Vec<Point4>,
+    uvs: Vec<Vec4>,
+    normals: Vec<Vec4>,
+    /// Vertex colors, as stored in the file and scaled to [0; 1].
+    colors: Vec<Color>,
+    triangles: Vec<Triangle>,
+}
+
+impl PlyModel {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        Self::parse(BufReader::new(File::open(path)?))
+    }
+
+    pub fn parse(mut reader: impl BufRead) -> Result<Self, Box<dyn Error>> {
+        let (format, elements) = read_header(&mut reader)?;
+
+        let mut model = PlyModel {
+            vertices: Vec::new(),
+            uvs: Vec::new(),
+            normals: Vec::new(),
+            colors: Vec::new(),
+            triangles: Vec::new(),
+        };
+
+        match format {
+            Format::Ascii => {
+                let mut text = String::new();
+                reader.read_to_string(&mut text)?;
+                let mut values = AsciiReader {
+                    tokens: text.split_ascii_whitespace(),
+                };
+                model.read_elements(&elements, &mut values)?;
+            }
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => {
+                let mut values = BinaryReader {
+                    reader,
+                    big_endian: format == Format::BinaryBigEndian,
+                };
+                model.read_elements(&elements, &mut values).map_err(|err| {
+                    match err.downcast_ref::<io::Error>() {
+                        Some(io_err) if io_err.kind() == io::ErrorKind::UnexpectedEof => {
+                            ParseError::boxed("Unexpected end of file")
+                        }
+                        _ => err,
+                    }
+                })?;
+            }
+        }
+
+        if model.vertices.is_empty() {
+            return Err(ParseError::boxed("File has no vertices"));
+        }
+        if model.normals.is_empty() {
+            model.generate_normals();
+        }
+
+        Ok(model)
+    }
+
+    fn read_elements(
+        &mut self,
+        elements: &[Element],
+        values: &mut impl ValueReader,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut row = Vec::new();
+
+        for element in elements {
+            match element.name.as_str() {
+                "vertex" => self.read_vertices(element, values)?,
+                "face" => self.read_faces(element, values)?,
+                // Skip other elements
+                _ => {
+                    for _ in 0..element.count {
+                        row.clear();
+                        for property in &element.properties {
+                            values.read_property(property.kind, &mut row)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_vertices(
+        &mut self,
+        element: &Element,
+        values: &mut impl ValueReader,
+    ) -> Result<(), Box<dyn Error>> {
+        let position = [["x"], ["y"], ["z"]].map(|names| element.scalar(&names));
+        let [Some((x, _)), Some((y, _)), Some((z, _))] = position else {
+            return Err(ParseError::boxed("Vertices have no position"));
+        };
+
+        let normal = [["nx"], ["ny"], ["nz"]].map(|names| element.scalar(&names));
+        let uv = [
+            element.scalar(&["u", "s", "texture_u", "texture_s"]),
+            element.scalar(&["v", "t", "texture_v", "texture_t"]),
+        ];
+        let color = [
+            element.scalar(&["red", "diffuse_red"]),
+            element.scalar(&["green", "diffuse_green"]),
+            element.scalar(&["blue", "diffuse_blue"]),
+        ];
+
+        // Properties are at the same index in `row` as in the element, as
+        // lists are only allowed in faces
+        if element
+            .properties
+            .iter()
+            .any(|property| matches!(property.kind, PropertyType::List { .. }))
+        {
+            return Err(ParseError::boxed("Vertices can't have list properties"));
+        }
+
+        let capacity = element.count.min(MAX_RESERVED);
+        self.vertices.reserve(capacity);
+        let mut row = Vec::with_capacity(element.properties.len());
+
+        for _ in 0..element.count {
+            row.clear();
+            for property in &element.properties {
+                values.read_property(property.kind, &mut row)?;
+            }
+
+            self.vertices.push(Vec4::point(row[x], row[y], row[z]));
+
+            if let [Some((nx, _)), Some((ny, _)), Some((nz, _))] = normal {
+                let n = Vec4::vec(row[nx], row[ny], row[nz]);
+                self.normals.push(if n.length_squared() > 0.0 {
+                    n.to_unit()
+                } else {
+                    Vec4::vec(0.0, 1.0, 0.0)
+                });
+            }
+            if let [Some((u, _)), Some((v, _))] = uv {
+                self.uvs.push(Vec4::vec(row[u], row[v], 0.0));
+            }
+            if let [Some((r, r_ty)), Some((g, g_ty)), Some((b, b_ty))] = color {
+                self.colors.push(Vec4::vec(
+                    row[r] * r_ty.color_scale(),
+                    row[g] * g_ty.color_scale(),
+                    row[b] * b_ty.color_scale(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_faces(
+        &mut self,
+        element: &Element,
+        values: &mut impl ValueReader,
+    ) -> Result<(), Box<dyn Error>> {
+        let indices_property = element
+            .properties
+            .iter()
+            .position(|property| {
+                matches!(property.kind, PropertyType::List { .. })
+                    && matches!(property.name.as_str(), "vertex_indices" | "vertex_index")
+            })
+            .ok_or(ParseError::boxed("Faces have no vertex indices"))?;
+
+        // Faces are usually triangles
+        self.triangles.reserve(element.count.min(MAX_RESERVED));
+        let vertex_count = self.vertices.len();
+        let has_uvs = !self.uvs.is_empty();
+        let mut row = Vec::new();
+        let mut face = Vec::new();
+
+        for _ in 0..element.count {
+            for (idx, property) in element.properties.iter().enumerate() {
+                let target = if idx == indices_property {
+                    face.clear();
+                    &mut face
+                } else {
+                    row.clear();
+                    &mut row
+                };
+                values.read_property(property.kind, target)?;
+            }
+
+            let indices = face
+                .iter()
+                .map(|&idx| {
+                    if idx >= 0.0 && (idx as usize) < vertex_count {
+                        Ok(idx as usize)
+                    } else {
+                        Err(ParseError::boxed(&format!(
+                            "Out of range vertex index {idx}"
+                        )))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // Split polygons into a fan of triangles, assuming they're convex
+            for i in 2..indices.len() {
+                let vert_indices = [indices[0], indices[i - 1], indices[i]];
+                self.triangles.push(Triangle {
+                    vert_indices,
+                    normal_indices: vert_indices,
+                    uv_indices: has_uvs.then_some(vert_indices),
+                    material_index: 0,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate smooth normals, averaging the normals of the faces around
+    /// each vertex weighted by their area.
+    fn generate_normals(&mut self) {
+        self.normals = vertex_normals(&self.vertices, &self.triangles);
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Build a mesh from the model. PLY files have no materials, so every
+    /// face uses the mesh's material or the default material.
+    pub fn into_mesh(self, materials: &MeshMaterials, ply_path: &Path) -> TriangleMesh {
+        let materials = materials.resolve(&MaterialSlots::default(), ply_path);
+
+        let mut mesh = TriangleMesh::new(
+            self.vertices,
+            self.uvs,
+            self.normals,
+            self.triangles,
+            materials,
+        );
+        mesh.set_vertex_colors(self.colors);
+
+        mesh
+    }
+}
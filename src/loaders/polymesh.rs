@@ -0,0 +1,104 @@
+//! Meshes declared inline in scene files, from lists of vertices and
+//! polygons.
+
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::object::mesh::{Triangle, TriangleMesh};
+use crate::utils::ParseError;
+use crate::vec4::{Point4, Vec4};
+
+/// A mesh made of polygons over a list of vertices. Normals and UVs are
+/// optional, and given per vertex.
+#[derive(Debug)]
+pub struct PolygonMesh {
+    pub vertices: Vec<Point4>,
+    /// Without normals, faces are flat shaded.
+    pub normals: Option<Vec<Vec4>>,
+    /// UVs as `(u, v, 0)` vectors.
+    pub uvs: Option<Vec<Vec4>>,
+    /// Polygons as lists of vertex indices, in counterclockwise order when
+    /// seen from the front.
+    pub faces: Vec<Vec<usize>>,
+}
+
+impl PolygonMesh {
+    /// A mesh made of a single polygon.
+    pub fn polygon(vertices: Vec<Point4>) -> Self {
+        PolygonMesh {
+            faces: vec![(0..vertices.len()).collect()],
+            vertices,
+            normals: None,
+            uvs: None,
+        }
+    }
+
+    /// Build a mesh where every face uses the same material. Polygons are
+    /// split into a fan of triangles, so they should be convex.
+    pub fn into_mesh(self, material: Arc<dyn Material>) -> Result<TriangleMesh, Box<dyn Error>> {
+        let vertex_count = self.vertices.len();
+        let count_matches = |items: &Option<Vec<Vec4>>| {
+            items
+                .as_ref()
+                .is_none_or(|items| items.len() == vertex_count)
+        };
+        if !count_matches(&self.normals) {
+            return Err(ParseError::boxed("Expected one normal per vertex"));
+        }
+        if !count_matches(&self.uvs) {
+            return Err(ParseError::boxed("Expected one UV per vertex"));
+        }
+
+        let has_uvs = self.uvs.is_some();
+        let mut normals = match self.normals {
+            Some(normals) => normals.into_iter().map(|n| n.to_unit()).collect(),
+            None => Vec::new(),
+        };
+        let flat_shading = normals.is_empty();
+        let mut triangles = Vec::new();
+
+        for face in &self.faces {
+            if face.len() < 3 {
+                return Err(ParseError::boxed("Faces must have at least 3 vertices"));
+            }
+            if let Some(idx) = face.iter().find(|&&idx| idx >= vertex_count) {
+                return Err(ParseError::boxed(&format!(
+                    "Out of range vertex index {idx}"
+                )));
+            }
+
+            for i in 2..face.len() {
+                let vert_indices = [face[0], face[i - 1], face[i]];
+
+                // Flat shaded faces get a normal per triangle
+                let normal_indices = if flat_shading {
+                    let [v0, v1, v2] = vert_indices.map(|idx| self.vertices[idx]);
+                    let normal = Vec4::cross(&(v1 - v0), &(v2 - v0));
+                    if normal.length_squared() == 0.0 {
+                        return Err(ParseError::boxed("Faces must not be degenerate"));
+                    }
+                    normals.push(normal.to_unit());
+                    [normals.len() - 1; 3]
+                } else {
+                    vert_indices
+                };
+
+                triangles.push(Triangle {
+                    vert_indices,
+                    normal_indices,
+                    uv_indices: has_uvs.then_some(vert_indices),
+                    material_index: 0,
+                });
+            }
+        }
+
+        Ok(TriangleMesh::new(
+            self.vertices,
+            self.uvs.unwrap_or_default(),
+            normals,
+            triangles,
+            vec![material],
+        ))
+    }
+}
@@ -0,0 +1,71 @@
+//! Point and spot lights for imported scenes. Formats like glTF describe
+//! lights with no size, so they're rendered as small emissive spheres with
+//! the same intensity.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::material::Emissive;
+use crate::object::Sphere;
+use crate::texture::{ConstantTexture, Sampler};
+use crate::vec4::{Color, Point4, Vec4};
+
+/// Radius of the spheres standing in for point and spot lights.
+pub const LIGHT_RADIUS: f64 = 0.02;
+
+/// Emission of a spot light's sphere. Points on the sphere only emit if they
+/// face into the light's cone, so light leaves the sphere in roughly the same
+/// directions as the spot light, with a smooth falloff between the inner and
+/// outer cone angles.
+#[derive(Debug)]
+struct SpotEmission {
+    emission: Color,
+    center: Point4,
+    direction: Vec4,
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl Sampler for SpotEmission {
+    type Output = Color;
+
+    fn sample(&self, _: (f64, f64), p: &Point4) -> Self::Output {
+        let cos_theta = (*p - self.center).to_unit().dot(&self.direction);
+        let t = (cos_theta - self.cos_outer) / f64::max(self.cos_inner - self.cos_outer, 1e-6);
+        let t = t.clamp(0.0, 1.0);
+
+        self.emission * (t * t * (3.0 - 2.0 * t))
+    }
+}
+
+/// Emission of a light sphere so it has the given intensity, in W/sr.
+fn sphere_emission(intensity: Color) -> Color {
+    intensity / (PI * LIGHT_RADIUS * LIGHT_RADIUS)
+}
+
+/// A light emitting the same intensity in all directions.
+pub fn point_light(center: Point4, intensity: Color) -> Sphere {
+    let material = Emissive::new(Arc::new(ConstantTexture::new(sphere_emission(intensity))));
+    Sphere::new(center, LIGHT_RADIUS, Arc::new(material))
+}
+
+/// A light emitting in a cone around `direction`. The cone angles are
+/// measured from the cone's axis.
+pub fn spot_light(
+    center: Point4,
+    direction: Vec4,
+    intensity: Color,
+    inner_angle: f64,
+    outer_angle: f64,
+) -> Sphere {
+    let emission_map = SpotEmission {
+        emission: sphere_emission(intensity),
+        center,
+        direction: direction.to_unit(),
+        cos_inner: f64::cos(inner_angle),
+        cos_outer: f64::cos(outer_angle),
+    };
+    let material = Emissive::new(Arc::new(emission_map));
+
+    Sphere::new(center, LIGHT_RADIUS, Arc::new(material))
+}
@@ -0,0 +1,188 @@
+//! Binary mesh format (`.rtmesh`), storing a triangle mesh along with its BVH
+//! so it can be loaded without parsing or building anything.
+//!
+//! Files start with a header identifying the format version and the source
+//! file the mesh was converted from, which is used to tell if a cached mesh
+//! is up to date. Materials aren't stored, only the names of the materials
+//! in the source file, which are loaded from its material libraries.
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::{
+    binary::{self, ReadBinary, WriteBinary},
+    object::mesh::TriangleMesh,
+};
+
+use super::obj::{MaterialSlots, MeshMaterials, ObjModel};
+use super::ply::{self, PlyModel};
+
+pub const EXTENSION: &str = "rtmesh";
+
+const MAGIC: &[u8; 8] = b"RTMESH\0\0";
+const FORMAT_VERSION: u32 = 3;
+
+/// Size and modification time of the file a mesh was converted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SourceStamp {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+}
+
+impl SourceStamp {
+    fn of(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Ok(SourceStamp {
+            size: metadata.len(),
+            modified_secs: modified.as_secs(),
+            modified_nanos: modified.subsec_nanos(),
+        })
+    }
+
+    fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_u64(self.size)?;
+        w.write_u64(self.modified_secs)?;
+        w.write_u32(self.modified_nanos)
+    }
+
+    fn read_from(r: &mut impl Read) -> io::Result<Self> {
+        Ok(SourceStamp {
+            size: r.read_u64()?,
+            modified_secs: r.read_u64()?,
+            modified_nanos: r.read_u32()?,
+        })
+    }
+}
+
+pub fn is_rtmesh_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == EXTENSION)
+}
+
+/// Path of the cached binary mesh for a source file, next to it.
+pub fn cache_path(source: &Path) -> PathBuf {
+    source.with_extension(EXTENSION)
+}
+
+/// Read the header of a binary mesh, returning the stamp of its source file,
+/// or `None` if it was written by another version.
+fn read_header(r: &mut impl Read) -> io::Result<Option<SourceStamp>> {
+    let mut magic = [0; 8];
+    r.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(binary::invalid_data("Not a binary mesh file"));
+    }
+
+    if r.read_u32()? != FORMAT_VERSION {
+        return Ok(None);
+    }
+
+    SourceStamp::read_from(r).map(Some)
+}
+
+/// Load a binary mesh file. Materials from material libraries are resolved
+/// relative to the file.
+pub fn load_rtmesh(path: &Path, materials: &MeshMaterials) -> io::Result<TriangleMesh> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if read_header(&mut reader)?.is_none() {
+        return Err(binary::invalid_data("Unsupported binary mesh version"));
+    }
+
+    read_mesh(&mut reader, materials, path)
+}
+
+fn read_mesh(
+    r: &mut impl Read,
+    materials: &MeshMaterials,
+    path: &Path,
+) -> io::Result<TriangleMesh> {
+    let slots = MaterialSlots::read_from(r)?;
+    TriangleMesh::read_from(r, materials.resolve(&slots, path))
+}
+
+fn write_rtmesh(
+    path: &Path,
+    mesh: &TriangleMesh,
+    slots: &MaterialSlots,
+    stamp: SourceStamp,
+) -> io::Result<()> {
+    // Write to a temporary file first, so a partially written mesh is never loaded
+    let tmp_path = path.with_extension(format!("{EXTENSION}.tmp"));
+
+    let mut writer = BufWriter::new(File::create(&tmp_path)?);
+    writer.write_all(MAGIC)?;
+    writer.write_u32(FORMAT_VERSION)?;
+    stamp.write_to(&mut writer)?;
+    slots.write_to(&mut writer)?;
+    mesh.write_to(&mut writer)?;
+    writer.flush()?;
+    drop(writer);
+
+    fs::rename(&tmp_path, path)
+}
+
+/// Load an OBJ or PLY mesh through its binary cache.
+///
+/// If a cached mesh exists next to `source` and was converted from the
+/// current version of that file, it's loaded directly. Otherwise the mesh is
+/// loaded from the source, and the cache is (re)written.
+pub fn load_cached(
+    source: &Path,
+    materials: &MeshMaterials,
+) -> Result<TriangleMesh, Box<dyn Error>> {
+    let stamp = SourceStamp::of(source)?;
+    let cache = cache_path(source);
+
+    let cached = File::open(&cache).and_then(|file| {
+        let mut reader = BufReader::new(file);
+        match read_header(&mut reader)? {
+            Some(cached_stamp) if cached_stamp == stamp => {
+                read_mesh(&mut reader, materials, source).map(Some)
+            }
+            _ => Ok(None), // Out of date
+        }
+    });
+
+    match cached {
+        Ok(Some(mesh)) => {
+            println!("Loaded cached mesh {}", cache.display());
+            return Ok(mesh);
+        }
+        Ok(None) => (),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+        Err(err) => {
+            println!(
+                "Warning: invalid mesh cache {}, rebuilding",
+                cache.display()
+            );
+            println!("\t{err}\n");
+        }
+    }
+
+    let (mesh, slots) = if ply::is_ply_path(source) {
+        let model = PlyModel::load(source)?;
+        println!("Loaded {} tris", model.triangle_count());
+        (model.into_mesh(materials, source), MaterialSlots::default())
+    } else {
+        let model = ObjModel::parse(BufReader::new(File::open(source)?))?;
+        println!("Loaded {} tris", model.triangle_count());
+        let slots = model.material_slots().clone();
+        (model.into_mesh(materials, source), slots)
+    };
+    if let Err(err) = write_rtmesh(&cache, &mesh, &slots, stamp) {
+        println!("Warning: failed to write mesh cache {}", cache.display());
+        println!("\t{err}\n");
+    }
+
+    Ok(mesh)
+}
@@ -1,37 +1,49 @@
 use std::{
     collections::HashMap,
     error::Error,
+    f64::consts::PI,
     fs::File,
     io::{BufRead, BufReader},
+    path::Path,
     sync::Arc,
 };
 
+use rand::{Rng, SeedableRng};
 use rand_pcg::Pcg64Mcg;
 use regex::Regex;
 
 use crate::{
-    camera::Camera,
+    camera::animation::{CameraAnimation, CameraKeyframe},
+    camera::{aperture::Aperture, Camera, Projection},
     material::{
-        normal_debug::NormalDebug, Dielectric, Emissive, Glossy, Isotropic, LambertianDiffuse,
-        Material, Metal,
+        normal_debug::NormalDebug, Dielectric, Emissive, Glossy, HenyeyGreenstein, Isotropic,
+        LambertianDiffuse, Material, Metal,
     },
     noise::{Noise3D, PerlinNoise3D},
-    object::{obj_box, BoundingVolumeHierarchyNode, Plane, Sky, Sphere, Sun, Transform, Volume},
+    object::{
+        mesh::TriangleMesh, obj_box, BoundingVolumeHierarchy, Cone, Cylinder, Disk,
+        HeterogeneousVolume, Instance, Keyframe, Motion, Plane, Sky, Sphere, Sun, Tessellate,
+        Torus, Transform, Volume,
+    },
     texture::{
         Channel, CheckerboardSolidTexture, CheckerboardTexture, ConstantTexture, ImageTexture,
         Interpolate, NoiseSolidTexture, Sampler, TexturePointer, UvDebugTexture,
+        VertexColorTexture, VoxelGridTexture,
     },
-    utils::{deg_to_rad, ParseError},
+    utils::{deg_to_rad, parse_transform, ParseError},
     vec4::Color,
 };
 use crate::{
-    config::{Config, SceneConfig, DEFAULT_SCENE_CONFIG},
+    config::{Config, FogConfig, LensConfig, SceneConfig, DEFAULT_SCENE_CONFIG},
     object::Hit,
 };
 use crate::{object::ObjectList, utils::parse_vec};
 use crate::{scene::SceneData, vec4::Vec4};
 
-use super::obj::load_mesh_from_file;
+use super::obj::{MeshMaterials, ObjModel};
+use super::ply::{self, PlyModel};
+use super::polymesh::PolygonMesh;
+use super::rtmesh;
 
 enum Entity {
     Object(Arc<dyn Hit>),
@@ -48,14 +60,83 @@ enum Texture {
 
 type ParseResult = Result<Entity, Box<dyn Error>>;
 
+/// A mesh file along with the options it was loaded with.
+#[derive(PartialEq, Eq, Hash)]
+struct MeshKey {
+    path: String,
+    backface: bool,
+    octree: bool,
+    /// Materials come from the file, instead of being set in the scene.
+    file_materials: bool,
+    /// Number of subdivision levels.
+    subdivide: u32,
+}
+
+struct LoadedMesh {
+    object: Arc<dyn Hit>,
+    /// Material set in the scene, if any.
+    material: Option<Arc<dyn Material>>,
+}
+
+/// Displacement set on an object by its `displace`, `displace_scale` and
+/// `displace_level` parameters.
+struct Displacement {
+    heights: Option<TexturePointer<f64>>,
+    scale: f64,
+    level: Option<u32>,
+}
+
+impl Displacement {
+    fn new() -> Self {
+        Displacement {
+            heights: None,
+            scale: 1.0,
+            level: None,
+        }
+    }
+
+    /// Tessellate and displace a primitive, with `2^level` quads along each
+    /// of its UV directions. Returns `None` without a height texture.
+    fn displace_primitive(
+        &self,
+        primitive: &impl Tessellate,
+        material: Arc<dyn Material>,
+    ) -> Option<TriangleMesh> {
+        let heights = self.heights.as_ref()?;
+        let level = self.level.unwrap_or(DEFAULT_PRIMITIVE_DISPLACE_LEVEL);
+
+        // Meshes have no light sampling PDF
+        if material.emission_map().is_some() {
+            println!("Warning: displaced emitters can't be sampled as lights\n");
+        }
+        let mesh = primitive.tessellate(1 << level, material);
+
+        Some(mesh.displace(heights.as_ref(), self.scale, 0))
+    }
+
+    /// The primitive as an object, displaced if there's a height texture.
+    fn primitive_object(
+        &self,
+        primitive: impl Tessellate + Hit + 'static,
+        material: Arc<dyn Material>,
+    ) -> Arc<dyn Hit> {
+        match self.displace_primitive(&primitive, material) {
+            Some(mesh) => Arc::new(mesh),
+            None => Arc::new(primitive),
+        }
+    }
+}
+
 pub struct SceneLoader<'a> {
     objects: HashMap<String, Arc<dyn Hit>>,
     materials: HashMap<String, Arc<dyn Material>>,
     color_textures: HashMap<String, TexturePointer<Vec4>>,
     float_textures: HashMap<String, TexturePointer<f64>>,
     noise: HashMap<String, Arc<dyn Noise3D<Output = f64>>>,
+    meshes: HashMap<MeshKey, LoadedMesh>,
 
     scene_config: SceneConfig,
+    camera_keyframes: Vec<CameraKeyframe>,
     asset_path: String,
 
     rng: &'a mut Pcg64Mcg,
@@ -69,8 +150,10 @@ impl<'a> SceneLoader<'a> {
             color_textures: HashMap::new(),
             float_textures: HashMap::new(),
             noise: HashMap::new(),
+            meshes: HashMap::new(),
 
             scene_config: DEFAULT_SCENE_CONFIG,
+            camera_keyframes: Vec::new(),
             asset_path: asset_path.to_owned(),
 
             rng,
@@ -95,6 +178,13 @@ impl<'a> SceneLoader<'a> {
                                 println!("\t{err}\n");
                             }
                         }
+                        "keyframe" => {
+                            let res = self.parse_keyframe_directive(content);
+                            if let Err(err) = res {
+                                println!("Warning: invalid @keyframe directive");
+                                println!("\t{err}\n");
+                            }
+                        }
                         _ => (),
                     }
                 }
@@ -147,7 +237,10 @@ impl<'a> SceneLoader<'a> {
                 ..config
             };
 
-            let camera = Camera::new(&config);
+            let mut camera = Camera::new(&config);
+            if !self.camera_keyframes.is_empty() {
+                camera.set_animation(CameraAnimation::new(self.camera_keyframes));
+            }
 
             Ok((camera, world, lights))
         } else {
@@ -202,6 +295,98 @@ impl<'a> SceneLoader<'a> {
                     let vec = Vec4::point(x, y, z);
                     self.scene_config.camera_target = Some(vec);
                 }
+                "camera_up" => {
+                    let [x, y, z] = parse_vec(value)?;
+                    if x == 0.0 && y == 0.0 && z == 0.0 {
+                        return Err(Box::new(ParseError::new("Camera up vector can't be zero")));
+                    }
+                    self.scene_config.camera_up = Some(Vec4::vec(x, y, z));
+                }
+                "camera_roll" => {
+                    let deg = value.parse::<f64>()?;
+                    self.scene_config.camera_roll = Some(deg_to_rad(deg));
+                }
+                "camera_transform" => {
+                    self.scene_config.camera_transform = Some(parse_transform(value)?);
+                }
+                "fog_density" => {
+                    let density = value.parse::<f64>()?;
+                    if density < 0.0 {
+                        return Err(Box::new(ParseError::new("Fog density can't be negative")));
+                    }
+                    self.fog_config().density = density;
+                }
+                "fog_albedo" => {
+                    let [r, g, b] = parse_vec(value)?;
+                    self.fog_config().albedo = Vec4::vec(r, g, b);
+                }
+                "fog_g" => {
+                    let g = value.parse::<f64>()?;
+                    self.fog_config().g = g;
+                }
+                "fog_falloff" => {
+                    // Fog sampling relies on density never increasing with height
+                    let falloff = value.parse::<f64>()?;
+                    if falloff < 0.0 {
+                        return Err(Box::new(ParseError::new("Fog falloff can't be negative")));
+                    }
+                    self.fog_config().height_falloff = falloff;
+                }
+                "fog_height" => {
+                    let height = value.parse::<f64>()?;
+                    self.fog_config().base_height = height;
+                }
+                "projection" => {
+                    let projection = Projection::from_name(value)
+                        .ok_or(ParseError::new(&format!("Unknown projection {value}")))?;
+                    self.scene_config.projection = Some(projection);
+                }
+                "aperture_blades" => {
+                    let blades = value.parse::<usize>()?;
+                    if blades < 3 {
+                        return Err(Box::new(ParseError::new(
+                            "Aperture must have at least 3 blades",
+                        )));
+                    }
+                    self.lens_config().aperture = Aperture::Polygon(blades);
+                }
+                "aperture_rotation" => {
+                    let deg = value.parse::<f64>()?;
+                    self.lens_config().aperture_rotation = deg_to_rad(deg);
+                }
+                "aperture_mask" => {
+                    let path = self.asset_path.to_owned() + value;
+                    let mask = ImageTexture::from_file(&path)?;
+                    self.lens_config().aperture = Aperture::from_mask(&mask);
+                }
+                "focus_tilt" => {
+                    let (x, y) = parse_pair(value)?;
+                    self.lens_config().focus_tilt = (deg_to_rad(x), deg_to_rad(y));
+                }
+                "lens_shift" => {
+                    self.lens_config().shift = parse_pair(value)?;
+                }
+                "anamorphic_squeeze" => {
+                    let squeeze = value.parse::<f64>()?;
+                    if squeeze <= 0.0 {
+                        return Err(Box::new(ParseError::new(
+                            "Anamorphic squeeze must be positive",
+                        )));
+                    }
+                    self.lens_config().anamorphic_squeeze = squeeze;
+                }
+                "chromatic_aberration" => {
+                    let amount = value.parse::<f64>()?;
+                    self.lens_config().chromatic_aberration = amount;
+                }
+                "shutter_open" => {
+                    let time = value.parse::<f64>()?;
+                    self.scene_config.shutter_open = Some(time);
+                }
+                "shutter_close" => {
+                    let time = value.parse::<f64>()?;
+                    self.scene_config.shutter_close = Some(time);
+                }
                 _ => (),
             };
 
@@ -211,6 +396,56 @@ impl<'a> SceneLoader<'a> {
         }
     }
 
+    /// Parse a camera keyframe, made of a frame number followed by
+    /// `key=value` pairs with the same keys as `@config`.
+    fn parse_keyframe_directive(&mut self, content: &str) -> Result<(), Box<dyn Error>> {
+        let mut parts = content.split_whitespace();
+        let frame = match parts.next() {
+            Some(frame) => frame.parse::<f64>()?,
+            None => return Err(Box::new(ParseError::new("Keyframe missing frame number"))),
+        };
+        let mut keyframe = CameraKeyframe::new(frame);
+
+        for part in parts {
+            let Some((key, value)) = part.split_once('=') else {
+                let err_str = format!("Expected key=value, got {part}");
+                return Err(Box::new(ParseError::new(&err_str)));
+            };
+
+            match key {
+                "camera_pos" => {
+                    let [x, y, z] = parse_vec(value)?;
+                    keyframe.position = Some(Vec4::point(x, y, z));
+                }
+                "camera_target" => {
+                    let [x, y, z] = parse_vec(value)?;
+                    keyframe.target = Some(Vec4::point(x, y, z));
+                }
+                "camera_roll" => keyframe.roll = Some(deg_to_rad(value.parse::<f64>()?)),
+                "focal_length" => keyframe.focal_length = Some(value.parse::<f64>()?),
+                "focus_distance" => keyframe.focus_distance = Some(value.parse::<f64>()?),
+                "f_number" => keyframe.f_number = Some(value.parse::<f64>()?),
+                _ => {
+                    let err_str = format!("Unknown keyframe parameter {key}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+        }
+
+        self.camera_keyframes.push(keyframe);
+        Ok(())
+    }
+
+    fn fog_config(&mut self) -> &mut FogConfig {
+        self.scene_config.fog.get_or_insert(FogConfig::new(0.0))
+    }
+
+    fn lens_config(&mut self) -> &mut LensConfig {
+        self.scene_config
+            .lens
+            .get_or_insert_with(LensConfig::default)
+    }
+
     fn parse_params(&self, decl: &str) -> Vec<String> {
         let mut params = Vec::new();
         let mut current = String::new();
@@ -259,6 +494,8 @@ impl<'a> SceneLoader<'a> {
                 "image" => self.create_image_tex(&mut params),
                 "channel" => self.create_channel_tex(&mut params),
                 "uv_debug" => Ok(Entity::TextureColor(Arc::new(UvDebugTexture))),
+                "vertex_color" => self.create_vertex_color_tex(&mut params),
+                "voxel_grid" => self.create_voxel_grid_tex(&mut params),
                 // Materials
                 "lambertian" => self.create_lambertian(&mut params),
                 "metal" => self.create_metal(&mut params),
@@ -266,18 +503,30 @@ impl<'a> SceneLoader<'a> {
                 "glossy" => self.create_glossy(&mut params),
                 "emissive" => self.create_emissive(&mut params),
                 "isotropic" => self.create_isotropic(&mut params),
+                "henyey_greenstein" => self.create_henyey_greenstein(&mut params),
                 "normal_debug" => self.create_normal_debug(&mut params),
                 // Objects
                 "sphere" => self.create_sphere(&mut params),
+                "disk" => self.create_disk(&mut params),
+                "cylinder" => self.create_cylinder(&mut params),
+                "cone" => self.create_cone(&mut params),
+                "torus" => self.create_torus(&mut params),
+                "triangle" => self.create_polygon(&mut params, 3),
+                "quad" => self.create_polygon(&mut params, 4),
+                "polymesh" => self.create_polymesh(&mut params),
                 "plane" => self.create_plane(&mut params),
                 "box" => self.create_box(&mut params),
                 "mesh" => self.create_mesh(&mut params),
                 "transform" => self.create_transform(&mut params),
+                "motion" => self.create_motion(&mut params),
+                "instance" => self.create_instance(&mut params),
+                "scatter" => self.create_scatter(&mut params),
                 "list" => self.create_list(&mut params),
                 "bvh" => self.create_bvh(&mut params),
                 "sky" => self.create_sky(&mut params),
                 "sun" => self.create_sun(&mut params),
                 "volume" => self.create_volume(&mut params),
+                "hetero_volume" => self.create_hetero_volume(&mut params),
                 // Noise
                 "perlin" => Ok(Entity::Noise(Arc::new(PerlinNoise3D::new(&mut self.rng)))),
                 _ => Err(Box::new(ParseError::new("Unknown object type"))),
@@ -478,6 +727,20 @@ impl<'a> SceneLoader<'a> {
         }
     }
 
+    fn create_vertex_color_tex(&self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        let fallback = match params.next() {
+            Some(value) => {
+                let [x, y, z] = parse_vec(&value)?;
+                Vec4::vec(x, y, z)
+            }
+            None => Vec4::vec(1.0, 1.0, 1.0),
+        };
+
+        Ok(Entity::TextureColor(Arc::new(VertexColorTexture::new(
+            fallback,
+        ))))
+    }
+
     fn create_checker_tex(
         &mut self,
         params: &mut dyn Iterator<Item = String>,
@@ -583,6 +846,26 @@ impl<'a> SceneLoader<'a> {
         }
     }
 
+    fn create_voxel_grid_tex(&self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(file_path), Some(min), Some(max)) =
+            (params.next(), params.next(), params.next())
+        {
+            let [x, y, z] = parse_vec(&min)?;
+            let min = Vec4::point(x, y, z);
+            let [x, y, z] = parse_vec(&max)?;
+            let max = Vec4::point(x, y, z);
+
+            let path = &(self.asset_path.to_owned() + &file_path);
+            let texture = VoxelGridTexture::from_file(path, [min, max])?;
+
+            Ok(Entity::TextureFloat(Arc::new(texture)))
+        } else {
+            Err(Box::new(ParseError::new(
+                "Voxel grid texture missing parameters",
+            )))
+        }
+    }
+
     // =========================================================================
     // Materials
     // =========================================================================
@@ -613,8 +896,25 @@ impl<'a> SceneLoader<'a> {
     }
 
     fn create_dielectric(&self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
-        let ior = params.next().map_or(1.5, |ior| ior.parse::<f64>().unwrap());
-        let material = Dielectric::new(ior);
+        let mut ior = 1.5;
+        let mut priority = None;
+
+        for param in params {
+            match param.split_once('=') {
+                Some(("priority", value)) => priority = Some(value.parse::<u32>()?),
+                Some(_) => {
+                    let err_str = format!("Unknown glass parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+                None => ior = param.parse::<f64>()?,
+            }
+        }
+
+        let mut material = Dielectric::new(ior);
+        if let Some(priority) = priority {
+            material.priority = priority;
+        }
+
         Ok(Entity::Material(Arc::new(material)))
     }
 
@@ -641,7 +941,19 @@ impl<'a> SceneLoader<'a> {
     fn create_emissive(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
         if let Some(expr) = params.next() {
             let texture = self.get_color_texture(&expr)?;
-            let material = Emissive::new(texture);
+            let mut material = Emissive::new(texture);
+
+            for param in params {
+                match param.split_once('=') {
+                    Some(("spread", value)) => material.set_spread(value.parse::<f64>()?),
+                    None if param == "two_sided" => material.two_sided = true,
+                    _ => {
+                        let err_str = format!("Unknown emissive parameter {param}");
+                        return Err(Box::new(ParseError::new(&err_str)));
+                    }
+                }
+            }
+
             Ok(Entity::Material(Arc::new(material)))
         } else {
             Err(Box::new(ParseError::new(
@@ -662,6 +974,34 @@ impl<'a> SceneLoader<'a> {
         }
     }
 
+    fn create_henyey_greenstein(
+        &mut self,
+        params: &mut dyn Iterator<Item = String>,
+    ) -> ParseResult {
+        if let (Some(albedo_expr), Some(g)) = (params.next(), params.next()) {
+            let albedo = self.get_color_texture(&albedo_expr)?;
+            let g = g.parse::<f64>()?;
+
+            let material = match (params.next(), params.next()) {
+                (Some(g2), Some(blend)) => {
+                    HenyeyGreenstein::double(albedo, g, g2.parse::<f64>()?, blend.parse::<f64>()?)
+                }
+                (None, None) => HenyeyGreenstein::new(albedo, g),
+                _ => {
+                    return Err(Box::new(ParseError::new(
+                        "Second Henyey-Greenstein lobe needs both g and blend",
+                    )))
+                }
+            };
+
+            Ok(Entity::Material(Arc::new(material)))
+        } else {
+            Err(Box::new(ParseError::new(
+                "Henyey-Greenstein material missing parameters",
+            )))
+        }
+    }
+
     fn create_normal_debug(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
         let mut material = NormalDebug::new();
 
@@ -677,6 +1017,33 @@ impl<'a> SceneLoader<'a> {
     // Objects
     // =========================================================================
 
+    /// Apply a `displace`, `displace_scale` or `displace_level` parameter.
+    /// Returns `false` for any other parameter.
+    fn apply_displacement_param(
+        &mut self,
+        displacement: &mut Displacement,
+        param: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        match param.split_once('=') {
+            Some(("displace", value)) => {
+                displacement.heights = Some(self.get_float_texture(value)?);
+            }
+            Some(("displace_scale", value)) => displacement.scale = value.parse::<f64>()?,
+            Some(("displace_level", value)) => {
+                let level = value.parse::<u32>()?;
+                if level > MAX_DISPLACE_LEVEL {
+                    let err_str =
+                        format!("Displacement level must be at most {MAX_DISPLACE_LEVEL}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+                displacement.level = Some(level);
+            }
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
     fn create_sphere(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
         if let (Some(origin), Some(radius), Some(mat_expr)) =
             (params.next(), params.next(), params.next())
@@ -687,13 +1054,265 @@ impl<'a> SceneLoader<'a> {
 
             let material = self.get_material(&mat_expr)?;
 
-            let sphere = Sphere::new(origin, radius, material);
-            Ok(Entity::Object(Arc::new(sphere)))
+            let mut sphere = Sphere::new(origin, radius, Arc::clone(&material));
+            let mut displacement = Displacement::new();
+            let mut moving = false;
+
+            for param in params {
+                if let Some(resolution) = parse_importance_param(&param)? {
+                    sphere.importance_sample_emission(resolution);
+                } else if let Some(("move", value)) = param.split_once('=') {
+                    let [x, y, z] = parse_vec(value)?;
+                    sphere.set_motion(Vec4::point(x, y, z));
+                    moving = true;
+                } else if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown sphere parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            if moving && displacement.heights.is_some() {
+                return Err(Box::new(ParseError::new(
+                    "Moving spheres can't be displaced, use a motion instead",
+                )));
+            }
+
+            let object = displacement.primitive_object(sphere, material);
+            Ok(Entity::Object(object))
         } else {
             Err(Box::new(ParseError::new("Sphere missing parameters")))
         }
     }
 
+    fn create_disk(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(center), Some(normal), Some(radius), Some(mat_expr)) =
+            (params.next(), params.next(), params.next(), params.next())
+        {
+            let [x, y, z] = parse_vec(&center)?;
+            let center = Vec4::point(x, y, z);
+            let normal = parse_axis(&normal)?;
+            let radius = parse_size(&radius, "Disk radius")?;
+
+            let material = self.get_material(&mat_expr)?;
+            let disk = Disk::new(center, normal, radius, Arc::clone(&material));
+
+            let mut displacement = Displacement::new();
+            for param in params {
+                if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown disk parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            let object = displacement.primitive_object(disk, material);
+            Ok(Entity::Object(object))
+        } else {
+            Err(Box::new(ParseError::new("Disk missing parameters")))
+        }
+    }
+
+    fn create_cylinder(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(base), Some(axis), Some(radius), Some(mat_expr)) =
+            (params.next(), params.next(), params.next(), params.next())
+        {
+            let [x, y, z] = parse_vec(&base)?;
+            let base = Vec4::point(x, y, z);
+            let axis = parse_axis(&axis)?;
+            let radius = parse_size(&radius, "Cylinder radius")?;
+
+            let material = self.get_material(&mat_expr)?;
+            let mut capped = true;
+            let mut displacement = Displacement::new();
+            for param in params {
+                if param == "open" {
+                    capped = false;
+                } else if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown cylinder parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            let cylinder = Cylinder::new(base, axis, radius, capped, Arc::clone(&material));
+            let object = displacement.primitive_object(cylinder, material);
+            Ok(Entity::Object(object))
+        } else {
+            Err(Box::new(ParseError::new("Cylinder missing parameters")))
+        }
+    }
+
+    fn create_cone(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(base), Some(axis), Some(radius), Some(mat_expr)) =
+            (params.next(), params.next(), params.next(), params.next())
+        {
+            let [x, y, z] = parse_vec(&base)?;
+            let base = Vec4::point(x, y, z);
+            let axis = parse_axis(&axis)?;
+            let radius = parse_size(&radius, "Cone radius")?;
+
+            let material = self.get_material(&mat_expr)?;
+            let mut capped = true;
+            let mut displacement = Displacement::new();
+            for param in params {
+                if param == "open" {
+                    capped = false;
+                } else if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown cone parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            let cone = Cone::new(base, axis, radius, capped, Arc::clone(&material));
+            let object = displacement.primitive_object(cone, material);
+            Ok(Entity::Object(object))
+        } else {
+            Err(Box::new(ParseError::new("Cone missing parameters")))
+        }
+    }
+
+    fn create_torus(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(center), Some(axis), Some(major_radius), Some(minor_radius), Some(mat_expr)) = (
+            params.next(),
+            params.next(),
+            params.next(),
+            params.next(),
+            params.next(),
+        ) {
+            let [x, y, z] = parse_vec(&center)?;
+            let center = Vec4::point(x, y, z);
+            let axis = parse_axis(&axis)?;
+            let major_radius = parse_size(&major_radius, "Torus major radius")?;
+            let minor_radius = parse_size(&minor_radius, "Torus minor radius")?;
+            if minor_radius >= major_radius {
+                return Err(Box::new(ParseError::new(
+                    "Torus minor radius must be smaller than its major radius",
+                )));
+            }
+
+            let material = self.get_material(&mat_expr)?;
+            let torus = Torus::new(
+                center,
+                axis,
+                major_radius,
+                minor_radius,
+                Arc::clone(&material),
+            );
+
+            let mut displacement = Displacement::new();
+            for param in params {
+                if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown torus parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            let object = displacement.primitive_object(torus, material);
+            Ok(Entity::Object(object))
+        } else {
+            Err(Box::new(ParseError::new("Torus missing parameters")))
+        }
+    }
+
+    /// Create a triangle or a quad, from its corners in counterclockwise order
+    /// when seen from the front.
+    fn create_polygon(
+        &mut self,
+        params: &mut dyn Iterator<Item = String>,
+        corner_count: usize,
+    ) -> ParseResult {
+        let name = if corner_count == 3 {
+            "Triangle"
+        } else {
+            "Quad"
+        };
+        let missing_params = || {
+            let err_str = format!("{name} missing parameters");
+            Box::new(ParseError::new(&err_str))
+        };
+
+        let mut vertices = Vec::with_capacity(corner_count);
+        for _ in 0..corner_count {
+            // Too few corners, the material comes early
+            let corner = params
+                .next()
+                .filter(|p| !p.starts_with('$') && !p.starts_with('('))
+                .ok_or_else(missing_params)?;
+            let [x, y, z] = parse_vec(&corner)?;
+            vertices.push(Vec4::point(x, y, z));
+        }
+        let material = self.get_material(&params.next().ok_or_else(missing_params)?)?;
+
+        // By default the UVs cover the unit square
+        let mut polygon = PolygonMesh::polygon(vertices);
+        let uvs = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        let uvs = if corner_count == 3 {
+            vec![uvs[0], uvs[1], uvs[3]]
+        } else {
+            uvs.to_vec()
+        };
+        polygon.uvs = Some(uvs.into_iter().map(|(u, v)| Vec4::vec(u, v, 0.0)).collect());
+
+        let mut backface = false;
+        for param in params {
+            if param == "backface" {
+                backface = true;
+            } else if !apply_polymesh_param(&mut polygon, &param)? {
+                let err_str = format!("Unknown {} parameter {param}", name.to_lowercase());
+                return Err(Box::new(ParseError::new(&err_str)));
+            }
+        }
+
+        let mut mesh = polygon.into_mesh(material)?;
+        mesh.hit_back_faces = backface;
+
+        Ok(Entity::Object(Arc::new(mesh)))
+    }
+
+    fn create_polymesh(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let Some(mat_expr) = params.next() {
+            let material = self.get_material(&mat_expr)?;
+
+            let mut polymesh = PolygonMesh {
+                vertices: Vec::new(),
+                normals: None,
+                uvs: None,
+                faces: Vec::new(),
+            };
+            let mut backface = false;
+
+            for param in params {
+                match param.split_once('=') {
+                    Some(("vertices", value)) => {
+                        polymesh.vertices = parse_vec_list(value)?
+                            .into_iter()
+                            .map(|v| Vec4::point(v.x(), v.y(), v.z()))
+                            .collect();
+                    }
+                    Some(("faces", value)) => polymesh.faces = parse_face_list(value)?,
+                    None if param == "backface" => backface = true,
+                    _ => {
+                        if !apply_polymesh_param(&mut polymesh, &param)? {
+                            let err_str = format!("Unknown polymesh parameter {param}");
+                            return Err(Box::new(ParseError::new(&err_str)));
+                        }
+                    }
+                }
+            }
+
+            if polymesh.vertices.is_empty() || polymesh.faces.is_empty() {
+                return Err(Box::new(ParseError::new(
+                    "Polymesh must have vertices and faces",
+                )));
+            }
+
+            let mut mesh = polymesh.into_mesh(material)?;
+            mesh.hit_back_faces = backface;
+
+            Ok(Entity::Object(Arc::new(mesh)))
+        } else {
+            Err(Box::new(ParseError::new("Polymesh missing parameters")))
+        }
+    }
+
     fn create_plane(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
         if let (Some(origin), Some(u), Some(v), Some(mat_expr)) =
             (params.next(), params.next(), params.next(), params.next())
@@ -707,15 +1326,27 @@ impl<'a> SceneLoader<'a> {
 
             let material = self.get_material(&mat_expr)?;
 
-            let mut plane = Plane::new(origin, (u, v), material);
+            let mut plane = Plane::new(origin, (u, v), Arc::clone(&material));
+            let mut displacement = Displacement::new();
 
-            if let Some(p) = params.next() {
-                if p == "backface" {
+            for param in params {
+                if param == "backface" {
                     plane.render_backface = true;
+                } else if let Some(resolution) = parse_importance_param(&param)? {
+                    plane.importance_sample_emission(resolution);
+                } else if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown plane parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
                 }
             }
 
-            Ok(Entity::Object(Arc::new(plane)))
+            match displacement.displace_primitive(&plane, material) {
+                Some(mut mesh) => {
+                    mesh.hit_back_faces = plane.render_backface;
+                    Ok(Entity::Object(Arc::new(mesh)))
+                }
+                None => Ok(Entity::Object(Arc::new(plane))),
+            }
         } else {
             Err(Box::new(ParseError::new("Plane missing parameters")))
         }
@@ -732,21 +1363,156 @@ impl<'a> SceneLoader<'a> {
 
             let material = self.get_material(&mat_expr)?;
 
-            let box_obj = obj_box::make_box(origin, size, material);
-            Ok(Entity::Object(Arc::new(box_obj)))
+            let mut displacement = Displacement::new();
+            for param in params {
+                if !self.apply_displacement_param(&mut displacement, &param)? {
+                    let err_str = format!("Unknown box parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            let sides = obj_box::box_sides(origin, size, Arc::clone(&material));
+            match displacement.displace_primitive(&sides, Arc::clone(&material)) {
+                Some(mesh) => Ok(Entity::Object(Arc::new(mesh))),
+                None => {
+                    let box_obj = obj_box::make_box(origin, size, material);
+                    Ok(Entity::Object(Arc::new(box_obj)))
+                }
+            }
         } else {
             Err(Box::new(ParseError::new("Box missing parameters")))
         }
     }
 
     fn create_mesh(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
-        if let (Some(file_path), Some(mat_expr)) = (params.next(), params.next()) {
-            let path = &(self.asset_path.to_owned() + &file_path);
-            let file = File::open(path)?;
-            let material = self.get_material(&mat_expr)?;
+        let mut params = params.peekable();
 
-            let mesh = load_mesh_from_file(&file, material)?;
-            Ok(Entity::Object(Arc::new(mesh)))
+        if let Some(file_path) = params.next() {
+            // Without a material, materials are loaded from the file's material libraries
+            let is_material_expr = |p: &String| p.starts_with('$') || p.starts_with('(');
+            let material = match params.next_if(is_material_expr) {
+                Some(mat_expr) => Some(self.get_material(&mat_expr)?),
+                None => None,
+            };
+
+            let mut key = MeshKey {
+                path: self.asset_path.to_owned() + &file_path,
+                backface: false,
+                octree: false,
+                file_materials: material.is_none(),
+                subdivide: 0,
+            };
+            let mut use_cache = false;
+            let mut displacement = Displacement::new();
+
+            for param in params {
+                match param.as_str() {
+                    "backface" => key.backface = true,
+                    "octree" => key.octree = true,
+                    "cache" => use_cache = true,
+                    _ if param.starts_with("subdivide=") => {
+                        let levels = param["subdivide=".len()..].parse::<u32>()?;
+                        if levels > MAX_SUBDIVIDE_LEVEL {
+                            let err_str =
+                                format!("Subdivision level must be at most {MAX_SUBDIVIDE_LEVEL}");
+                            return Err(Box::new(ParseError::new(&err_str)));
+                        }
+                        key.subdivide = levels;
+                    }
+                    _ => {
+                        if !self.apply_displacement_param(&mut displacement, &param)? {
+                            return Err(Box::new(ParseError::new(&format!(
+                                "Unknown mesh parameter {param}"
+                            ))));
+                        }
+                    }
+                }
+            }
+
+            // Meshes are only loaded once per file, other materials are applied through an instance.
+            // Displaced meshes are always loaded again, since they can't be compared.
+            let loaded = match displacement.heights {
+                Some(_) => None,
+                None => self.meshes.get(&key),
+            };
+            if let Some(loaded) = loaded {
+                let object = Arc::clone(&loaded.object);
+                match (material, &loaded.material) {
+                    (Some(material), Some(loaded_material))
+                        if !Arc::ptr_eq(&material, loaded_material) =>
+                    {
+                        let mut instance = Instance::new(Transform::new(object));
+                        instance.material = Some(material);
+                        return Ok(Entity::Object(Arc::new(instance)));
+                    }
+                    _ => return Ok(Entity::Object(object)),
+                }
+            }
+
+            let path = Path::new(&key.path);
+            let mesh_materials = match &material {
+                Some(material) => MeshMaterials::Single(Arc::clone(material)),
+                None => MeshMaterials::FromFile(Arc::new(LambertianDiffuse::new(Arc::new(
+                    ConstantTexture::from_values(0.5, 0.5, 0.5),
+                )))),
+            };
+
+            // OBJ polygons are subdivided directly, so quads can use Catmull-Clark. Other meshes
+            // only have triangles
+            let subdivide_obj =
+                key.subdivide > 0 && !rtmesh::is_rtmesh_path(path) && !ply::is_ply_path(path);
+
+            let mut mesh = if subdivide_obj {
+                let model = ObjModel::parse(BufReader::new(File::open(path)?))?;
+                println!("Loaded {} tris", model.triangle_count());
+                let materials = mesh_materials.resolve(model.material_slots(), path);
+                let control_mesh = model.into_control_mesh();
+                control_mesh.subdivide(key.subdivide).into_mesh(materials)
+            } else if rtmesh::is_rtmesh_path(path) {
+                rtmesh::load_rtmesh(path, &mesh_materials)?
+            } else if use_cache {
+                rtmesh::load_cached(path, &mesh_materials)?
+            } else if ply::is_ply_path(path) {
+                let model = PlyModel::load(path)?;
+                println!("Loaded {} tris", model.triangle_count());
+                model.into_mesh(&mesh_materials, path)
+            } else {
+                let model = ObjModel::parse(BufReader::new(File::open(path)?))?;
+                println!("Loaded {} tris", model.triangle_count());
+                model.into_mesh(&mesh_materials, path)
+            };
+
+            if key.subdivide > 0 {
+                if !subdivide_obj {
+                    mesh = mesh.subdivide(key.subdivide);
+                }
+                println!("Subdivided into {} tris", mesh.triangle_count());
+            }
+
+            if let Some(heights) = &displacement.heights {
+                let level = displacement.level.unwrap_or(DEFAULT_MESH_DISPLACE_LEVEL);
+                mesh = mesh.displace(heights.as_ref(), displacement.scale, level);
+                println!("Displaced into {} tris", mesh.triangle_count());
+            }
+
+            mesh.hit_back_faces = key.backface;
+            if key.octree {
+                mesh.use_octree();
+            }
+
+            let object: Arc<dyn Hit> = Arc::new(mesh);
+            if displacement.heights.is_some() {
+                return Ok(Entity::Object(object));
+            }
+            self.meshes.insert(
+                key,
+                LoadedMesh {
+                    object: Arc::clone(&object),
+                    material,
+                },
+            );
+
+            Ok(Entity::Object(object))
         } else {
             Err(Box::new(ParseError::new("Mesh missing parameters")))
         }
@@ -761,39 +1527,151 @@ impl<'a> SceneLoader<'a> {
 
             while let Some(param) = params.next() {
                 for (_, [key, value]) in param_regex.captures_iter(&param).map(|c| c.extract()) {
-                    match key {
-                        "t" => {
-                            let vec = parse_vec(value)?;
-                            transform.translate(vec[0], vec[1], vec[2]);
+                    apply_transform_param(&mut transform, key, value)?;
+                }
+            }
+
+            Ok(Entity::Object(Arc::new(transform)))
+        } else {
+            Err(Box::new(ParseError::new("Transform missing parameters")))
+        }
+    }
+
+    fn create_motion(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let Some(obj_expr) = params.next() {
+            let object = self.get_object(&obj_expr)?;
+            let mut keyframes: Vec<Keyframe> = Vec::new();
+
+            for param in params {
+                if let Some(("time", value)) = param.split_once('=') {
+                    let time = value.parse::<f64>()?;
+                    let keyframe = match keyframes.last() {
+                        Some(last) if time <= last.time => {
+                            return Err(Box::new(ParseError::new("Keyframe times must increase")));
                         }
-                        "s" => {
-                            if let Ok(vec) = parse_vec(value) {
-                                transform.scale(vec[0], vec[1], vec[2]);
-                            } else {
+                        // Keyframes start from the previous pose
+                        Some(last) => Keyframe { time, ..*last },
+                        None => Keyframe::new(time),
+                    };
+                    keyframes.push(keyframe);
+                    continue;
+                }
+
+                let applied = match (keyframes.last_mut(), param.split_once('=')) {
+                    (Some(keyframe), Some((key, value))) => {
+                        apply_keyframe_param(keyframe, key, value)?
+                    }
+                    (None, _) => {
+                        return Err(Box::new(ParseError::new(
+                            "Motion parameters must follow a time= keyframe",
+                        )));
+                    }
+                    _ => false,
+                };
+                if !applied {
+                    let err_str = format!("Unknown motion parameter {param}");
+                    return Err(Box::new(ParseError::new(&err_str)));
+                }
+            }
+
+            match Motion::new(object, keyframes) {
+                Some(motion) => Ok(Entity::Object(Arc::new(motion))),
+                None => Err(Box::new(ParseError::new(
+                    "Motion needs at least one keyframe",
+                ))),
+            }
+        } else {
+            Err(Box::new(ParseError::new("Motion missing parameters")))
+        }
+    }
+
+    fn create_instance(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let Some(obj_expr) = params.next() {
+            let object = self.get_object(&obj_expr)?;
+            let mut transform = Transform::new(object);
+            let mut material = None;
+
+            for param in params {
+                match param.split_once('=') {
+                    Some(("material", value)) => material = Some(self.get_material(value)?),
+                    Some((key, value)) if apply_transform_param(&mut transform, key, value)? => (),
+                    _ => {
+                        let err_str = format!("Unknown instance parameter {param}");
+                        return Err(Box::new(ParseError::new(&err_str)));
+                    }
+                }
+            }
+
+            let mut instance = Instance::new(transform);
+            instance.material = material;
+
+            Ok(Entity::Object(Arc::new(instance)))
+        } else {
+            Err(Box::new(ParseError::new("Instance missing parameters")))
+        }
+    }
+
+    fn create_scatter(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(obj_expr), Some(count), Some(min), Some(max)) =
+            (params.next(), params.next(), params.next(), params.next())
+        {
+            let object = self.get_object(&obj_expr)?;
+            let count = count.parse::<usize>()?;
+            let [x0, y0, z0] = parse_vec(&min)?;
+            let [x1, y1, z1] = parse_vec(&max)?;
+
+            let mut material = None;
+            let mut scale = (1.0, 1.0);
+            let mut rotate = false;
+            let mut seed = None;
+
+            for param in params {
+                match param.split_once('=') {
+                    Some(("material", value)) => material = Some(self.get_material(value)?),
+                    Some(("scale", value)) => {
+                        scale = match value.split_once(',') {
+                            Some((s0, s1)) => (s0.parse::<f64>()?, s1.parse::<f64>()?),
+                            None => {
                                 let s = value.parse::<f64>()?;
-                                transform.scale_uniform(s);
+                                (s, s)
                             }
-                        }
-                        "rx" => {
-                            let deg = value.parse::<f64>()?;
-                            transform.rotate_x(deg_to_rad(deg));
-                        }
-                        "ry" => {
-                            let deg = value.parse::<f64>()?;
-                            transform.rotate_y(deg_to_rad(deg));
-                        }
-                        "rz" => {
-                            let deg = value.parse::<f64>()?;
-                            transform.rotate_z(deg_to_rad(deg));
-                        }
-                        _ => (),
+                        };
+                    }
+                    Some(("seed", value)) => seed = Some(value.parse::<u64>()?),
+                    None if param == "rotate" => rotate = true,
+                    _ => {
+                        let err_str = format!("Unknown scatter parameter {param}");
+                        return Err(Box::new(ParseError::new(&err_str)));
                     }
                 }
             }
 
-            Ok(Entity::Object(Arc::new(transform)))
+            let mut rng = match seed {
+                Some(seed) => Pcg64Mcg::seed_from_u64(seed),
+                None => Pcg64Mcg::from_rng(&mut self.rng)?,
+            };
+
+            // Random value in [a; b], which may be an empty range
+            let mut lerp = |a: f64, b: f64| a + (b - a) * rng.gen_range(0.0..1.0);
+
+            let mut instances: Vec<Arc<dyn Hit>> = Vec::with_capacity(count);
+            for _ in 0..count {
+                let mut transform = Transform::new(Arc::clone(&object));
+                transform.scale_uniform(lerp(scale.0, scale.1));
+                if rotate {
+                    transform.rotate_y(lerp(0.0, 2.0 * PI));
+                }
+                transform.translate(lerp(x0, x1), lerp(y0, y1), lerp(z0, z1));
+
+                let mut instance = Instance::new(transform);
+                instance.material = material.clone();
+                instances.push(Arc::new(instance));
+            }
+
+            let bvh = BoundingVolumeHierarchy::from(instances);
+            Ok(Entity::Object(Arc::new(bvh)))
         } else {
-            Err(Box::new(ParseError::new("Transform missing parameters")))
+            Err(Box::new(ParseError::new("Scatter missing parameters")))
         }
     }
 
@@ -809,21 +1687,25 @@ impl<'a> SceneLoader<'a> {
     }
 
     fn create_bvh(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
-        if let Some(axes) = params.next() {
-            let axes = [axes.contains('x'), axes.contains('y'), axes.contains('z')];
-            let mut objs = Vec::new();
+        let mut params = params.peekable();
 
-            while let Some(obj_expr) = params.next() {
-                let obj = self.get_object(&obj_expr)?;
-                objs.push(obj);
-            }
+        // Split axes used to be required, now they're picked automatically
+        if params.next_if(|p| is_axes_param(p)).is_some() {
+            println!("Warning: bvh split axes are deprecated and ignored");
+        }
 
-            let bvh = BoundingVolumeHierarchyNode::from(objs, axes, self.rng);
+        let mut objs = Vec::new();
+        for obj_expr in params {
+            let obj = self.get_object(&obj_expr)?;
+            objs.push(obj);
+        }
 
-            Ok(Entity::Object(Arc::new(bvh)))
-        } else {
-            Err(Box::new(ParseError::new("BVH missing parameters")))
+        if objs.is_empty() {
+            return Err(Box::new(ParseError::new("BVH missing parameters")));
         }
+
+        let bvh = BoundingVolumeHierarchy::from(objs);
+        Ok(Entity::Object(Arc::new(bvh)))
     }
 
     fn create_sky(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
@@ -866,4 +1748,223 @@ impl<'a> SceneLoader<'a> {
             Err(Box::new(ParseError::new("Volume missing parameters")))
         }
     }
+
+    fn create_hetero_volume(&mut self, params: &mut dyn Iterator<Item = String>) -> ParseResult {
+        if let (Some(bound_expr), Some(mat_expr), Some(density_expr), Some(max_density)) =
+            (params.next(), params.next(), params.next(), params.next())
+        {
+            let boundary = self.get_object(&bound_expr)?;
+            let material = self.get_material(&mat_expr)?;
+            let density = self.get_float_texture(&density_expr)?;
+            let max_density = max_density.parse::<f64>()?;
+
+            let mut volume = HeterogeneousVolume::new(boundary, material, density, max_density);
+
+            for param in params {
+                match param.split_once('=') {
+                    Some(("absorption", value)) => volume.set_absorption(value.parse::<f64>()?),
+                    Some(("scattering", value)) => volume.set_scattering(value.parse::<f64>()?),
+                    Some(("emission", value)) => {
+                        let emission = self.get_color_texture(value)?;
+                        volume.set_emission(emission);
+                    }
+                    _ => {
+                        let err_str = format!("Unknown volume parameter {param}");
+                        return Err(Box::new(ParseError::new(&err_str)));
+                    }
+                }
+            }
+
+            Ok(Entity::Object(Arc::new(volume)))
+        } else {
+            Err(Box::new(ParseError::new(
+                "Heterogeneous volume missing parameters",
+            )))
+        }
+    }
+}
+
+/// Parse a pair of numbers in `x,y` format.
+fn parse_pair(expr: &str) -> Result<(f64, f64), Box<dyn Error>> {
+    match expr.split_once(',') {
+        Some((x, y)) => Ok((x.trim().parse::<f64>()?, y.trim().parse::<f64>()?)),
+        None => Err(Box::new(ParseError::new("Expected a pair of numbers"))),
+    }
+}
+
+fn is_axes_param(param: &str) -> bool {
+    !param.is_empty() && param.chars().all(|c| matches!(c, 'x' | 'y' | 'z'))
+}
+
+/// Apply a `t`, `s`, `rx`, `ry` or `rz` parameter to a transform. Returns
+/// `false` for any other parameter.
+fn apply_transform_param(
+    transform: &mut Transform,
+    key: &str,
+    value: &str,
+) -> Result<bool, Box<dyn Error>> {
+    match key {
+        "t" => {
+            let vec = parse_vec(value)?;
+            transform.translate(vec[0], vec[1], vec[2]);
+        }
+        "s" => {
+            if let Ok(vec) = parse_vec(value) {
+                transform.scale(vec[0], vec[1], vec[2]);
+            } else {
+                let s = value.parse::<f64>()?;
+                transform.scale_uniform(s);
+            }
+        }
+        "rx" => {
+            let deg = value.parse::<f64>()?;
+            transform.rotate_x(deg_to_rad(deg));
+        }
+        "ry" => {
+            let deg = value.parse::<f64>()?;
+            transform.rotate_y(deg_to_rad(deg));
+        }
+        "rz" => {
+            let deg = value.parse::<f64>()?;
+            transform.rotate_z(deg_to_rad(deg));
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Apply a `t`, `s`, `rx`, `ry` or `rz` parameter to a motion keyframe,
+/// replacing that part of its pose. Returns `false` for any other parameter.
+fn apply_keyframe_param(
+    keyframe: &mut Keyframe,
+    key: &str,
+    value: &str,
+) -> Result<bool, Box<dyn Error>> {
+    match key {
+        "t" => {
+            let [x, y, z] = parse_vec(value)?;
+            keyframe.translation = Vec4::vec(x, y, z);
+        }
+        "s" => {
+            keyframe.scale = if let Ok([x, y, z]) = parse_vec(value) {
+                Vec4::vec(x, y, z)
+            } else {
+                let s = value.parse::<f64>()?;
+                Vec4::vec(s, s, s)
+            };
+        }
+        "rx" | "ry" | "rz" => {
+            let deg = value.parse::<f64>()?;
+            let axis = match key {
+                "rx" => 0,
+                "ry" => 1,
+                _ => 2,
+            };
+            keyframe.rotation[axis] = deg_to_rad(deg);
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+const DEFAULT_IMPORTANCE_RESOLUTION: usize = 64;
+/// Largest emission importance grid resolution, keeping grids to a few
+/// million cells.
+const MAX_IMPORTANCE_RESOLUTION: usize = 1024;
+
+/// Default number of times mesh triangles are split for displacement.
+const DEFAULT_MESH_DISPLACE_LEVEL: u32 = 3;
+/// Default number of times primitives are split for displacement, starting
+/// from a single quad per surface.
+const DEFAULT_PRIMITIVE_DISPLACE_LEVEL: u32 = 6;
+const MAX_DISPLACE_LEVEL: u32 = 10;
+const MAX_SUBDIVIDE_LEVEL: u32 = 6;
+
+/// Parse an `importance[=<resolution>]` flag for emission importance sampling.
+/// Returns `None` for any other parameter.
+fn parse_importance_param(param: &str) -> Result<Option<usize>, Box<dyn Error>> {
+    match param.split_once('=') {
+        Some(("importance", value)) => {
+            let resolution = value.parse::<usize>()?;
+            if resolution == 0 || resolution > MAX_IMPORTANCE_RESOLUTION {
+                let err_str = format!(
+                    "Importance resolution must be between 1 and {MAX_IMPORTANCE_RESOLUTION}"
+                );
+                return Err(Box::new(ParseError::new(&err_str)));
+            }
+            Ok(Some(resolution))
+        }
+        None if param == "importance" => Ok(Some(DEFAULT_IMPORTANCE_RESOLUTION)),
+        _ => Ok(None),
+    }
+}
+
+/// Parse the axis of a primitive, which can't be zero.
+fn parse_axis(expr: &str) -> Result<Vec4, Box<dyn Error>> {
+    let [x, y, z] = parse_vec(expr)?;
+    let axis = Vec4::vec(x, y, z);
+
+    if axis.length_squared() == 0.0 {
+        Err(Box::new(ParseError::new("Axis must not be zero")))
+    } else {
+        Ok(axis)
+    }
+}
+
+/// Parse the size of a primitive, which must be positive.
+fn parse_size(expr: &str, name: &str) -> Result<f64, Box<dyn Error>> {
+    let size = expr.parse::<f64>()?;
+
+    if size > 0.0 {
+        Ok(size)
+    } else {
+        Err(Box::new(ParseError::new(&format!(
+            "{name} must be positive"
+        ))))
+    }
+}
+
+/// Apply a `normals` or `uvs` parameter to a polygon mesh, each a list of one
+/// item per vertex separated by `;`. Returns `false` for any other parameter.
+fn apply_polymesh_param(mesh: &mut PolygonMesh, param: &str) -> Result<bool, Box<dyn Error>> {
+    match param.split_once('=') {
+        Some(("normals", value)) => mesh.normals = Some(parse_vec_list(value)?),
+        Some(("uvs", value)) => {
+            let uvs = value
+                .split(';')
+                .map(|uv| match uv.split(',').collect::<Vec<_>>()[..] {
+                    [u, v] => Ok(Vec4::vec(u.parse::<f64>()?, v.parse::<f64>()?, 0.0)),
+                    _ => Err(Box::new(ParseError::new("UV must have two components")).into()),
+                })
+                .collect::<Result<_, Box<dyn Error>>>()?;
+            mesh.uvs = Some(uvs);
+        }
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Parse a list of vectors separated by `;`.
+fn parse_vec_list(expr: &str) -> Result<Vec<Vec4>, Box<dyn Error>> {
+    expr.split(';')
+        .map(|item| {
+            let [x, y, z] = parse_vec(item)?;
+            Ok(Vec4::vec(x, y, z))
+        })
+        .collect()
+}
+
+/// Parse a list of faces separated by `;`, each a list of vertex indices
+/// separated by `,`.
+fn parse_face_list(expr: &str) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+    expr.split(';')
+        .map(|face| {
+            face.split(',')
+                .map(|idx| Ok(idx.parse::<usize>()?))
+                .collect()
+        })
+        .collect()
 }
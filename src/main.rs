@@ -1,6 +1,7 @@
 use std::env;
 use std::error::Error;
 use std::fs::File;
+use std::sync::Arc;
 use std::time::Instant;
 
 use rand::SeedableRng;
@@ -8,6 +9,7 @@ use rand_pcg::Pcg64Mcg;
 
 use rust_raytracer::config::Config;
 use rust_raytracer::loaders::assimp::AssimpLoader;
+use rust_raytracer::loaders::gltf::{self, GltfLoader};
 use rust_raytracer::loaders::scene::SceneLoader;
 use rust_raytracer::output::Writer;
 use rust_raytracer::scene::CornellBoxScene;
@@ -21,13 +23,15 @@ use rust_raytracer::scene::TonemapTestScene;
 use rust_raytracer::tonemapping;
 
 const OUT_FILENAME: &str = "out.png";
+const OUT_FILENAME_PREFIX: &str = "out";
 
 fn main() -> Result<(), Box<dyn Error>> {
     let config = Config::from_args(env::args());
+    let frames = config.frames;
 
     let time = Instant::now();
     let scene = &config.scene_name[..];
-    let (camera, world, lights) = match scene {
+    let (mut camera, world, lights) = match scene {
         "golden_monkey" | "" => GoldenMonkeyScene::init(config)?,
         "earth" => EarthScene::init(config)?,
         "perlin" => PerlinScene::init(config)?,
@@ -35,6 +39,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         "cornell" => CornellBoxScene::init(config)?,
         "cornell_smoke" => CornellSmokeScene::init(config)?,
         "tonemap_test" => TonemapTestScene::init(config)?,
+        file_path if gltf::is_gltf_path(file_path) => {
+            let loader = GltfLoader::new(file_path)?;
+            loader.load(config)?
+        }
         file_path if file_path.starts_with("model:") => {
             let loader = AssimpLoader::new(file_path.strip_prefix("model:").unwrap())?;
             loader.load(config)?
@@ -71,15 +79,27 @@ fn main() -> Result<(), Box<dyn Error>> {
     );
 
     // Output
-    let mut buf = camera.create_buffer();
-    camera.render(world, lights, &mut buf);
+    let (first_frame, last_frame) = frames.unwrap_or((0, 0));
+    for frame in first_frame..=last_frame {
+        let frame_time = Instant::now();
+        camera.set_frame(frame);
 
-    let elapsed = time.elapsed();
-    println!("Done: {:.2?}. Writing output to file...", elapsed);
+        let mut buf = camera.create_buffer();
+        camera.render(Arc::clone(&world), Arc::clone(&lights), &mut buf);
+
+        // Sequences get numbered files, stills keep the default name
+        let filename = match frames {
+            Some(_) => format!("{OUT_FILENAME_PREFIX}_{frame:04}.png"),
+            None => OUT_FILENAME.to_owned(),
+        };
+
+        let elapsed = frame_time.elapsed();
+        println!("Done: {:.2?}. Writing output to {filename}...", elapsed);
 
-    let mut writer = Writer::new(buf);
-    writer.tonemap = tonemapping::tonemap_aces;
-    writer.save(OUT_FILENAME)?;
+        let mut writer = Writer::new(buf);
+        writer.tonemap = tonemapping::tonemap_aces;
+        writer.save(&filename)?;
+    }
 
     let elapsed = time.elapsed();
     println!("Done! Took {:.2?}. Goodbye :)", elapsed);
@@ -5,11 +5,13 @@ use rand_pcg::Pcg64Mcg;
 use crate::object::HitRecord;
 use crate::pdf::PDF;
 use crate::ray::Ray;
+use crate::texture::TexturePointer;
 use crate::vec4::{Color, Vec4};
 
 pub mod dielectric;
 pub mod emissive;
 pub mod glossy;
+pub mod henyey_greenstein;
 pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
@@ -18,6 +20,7 @@ pub mod normal_debug;
 pub use dielectric::Dielectric;
 pub use emissive::Emissive;
 pub use glossy::Glossy;
+pub use henyey_greenstein::HenyeyGreenstein;
 pub use isotropic::Isotropic;
 pub use lambertian::LambertianDiffuse;
 pub use metal::Metal;
@@ -39,9 +42,14 @@ pub trait Material: Send + Sync + Debug {
     /// Scatter a ray according to material properties.
     fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Pcg64Mcg) -> ScatterResult;
 
-    fn emit(&self, _: &HitRecord) -> Color {
+    fn emit(&self, _: &Ray, _: &HitRecord) -> Color {
         Vec4::vec(0.0, 0.0, 0.0)
     }
 
+    /// Texture for emitted radiance, used to importance sample emitters.
+    fn emission_map(&self) -> Option<&TexturePointer<Color>> {
+        None
+    }
+
     fn scattering_pdf(&self, ray_in: &Ray, scattered: &Ray, hit: &HitRecord) -> f64;
 }
@@ -1,6 +1,7 @@
 use rand::Rng;
 use rand_pcg::Pcg64Mcg;
 
+use crate::medium::Medium;
 use crate::object::HitRecord;
 use crate::ray::Ray;
 use crate::utils::reflectance;
@@ -11,28 +12,61 @@ use super::{Material, ScatterResult};
 #[derive(Debug)]
 pub struct Dielectric {
     ior: f64,
+
+    /// Priority for nested dielectrics. Where two dielectrics overlap, the one
+    /// with higher priority fills the overlapping space.
+    pub priority: u32,
 }
 
 impl Dielectric {
     pub fn new(index_of_refraction: f64) -> Self {
         Dielectric {
             ior: index_of_refraction,
+            priority: 1,
         }
     }
 
     pub fn index_of_refraction(&self) -> f64 {
         self.ior
     }
+
+    fn medium(&self) -> Medium {
+        Medium {
+            ior: self.ior,
+            priority: self.priority,
+        }
+    }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Pcg64Mcg) -> ScatterResult {
-        let ior_ratio = if hit.front_face() {
-            1.0 / self.ior
+        let medium = self.medium();
+        let mut media = *ray.media();
+
+        // Media the ray is moving from and into if it crosses the surface
+        let (from, to) = if hit.front_face() {
+            (media.current(), medium)
         } else {
-            self.ior
+            (medium, media.outside_of(medium))
         };
 
+        if hit.front_face() {
+            media.push(medium);
+        } else {
+            media.remove(medium);
+        }
+
+        // Surfaces inside a higher priority medium don't exist as far as the
+        // ray is concerned, so let it through unchanged
+        if !ray.media().is_interface(medium) {
+            return ScatterResult::ScatteredWithRay {
+                attenuation: Vec4::vec(1.0, 1.0, 1.0),
+                scattered: ray.spawn_with_media(hit.pos(), ray.dir(), media),
+            };
+        }
+
+        let ior_ratio = from.ior / to.ior;
+
         let unit_dir = ray.dir().to_unit();
         let cos_theta = f64::min(1.0, (-unit_dir).dot(&hit.normal()));
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
@@ -40,13 +74,13 @@ impl Material for Dielectric {
         let tir = ior_ratio * sin_theta > 1.0; // Total Internal Reflection
         let reflected = tir || reflectance(cos_theta, ior_ratio) > rng.gen_range(0.0..1.0);
 
-        let scatter_dir = if reflected {
-            unit_dir.reflect(hit.normal())
+        let scattered = if reflected {
+            ray.spawn(hit.pos(), unit_dir.reflect(hit.normal()))
         } else {
-            unit_dir.refract(hit.normal(), ior_ratio)
+            let refracted = unit_dir.refract(hit.normal(), ior_ratio);
+            ray.spawn_with_media(hit.pos(), refracted, media)
         };
 
-        let scattered = Ray::new(hit.pos(), scatter_dir);
         ScatterResult::ScatteredWithRay {
             attenuation: Vec4::vec(1.0, 1.0, 1.0),
             scattered,
@@ -4,7 +4,8 @@ use rand_pcg::Pcg64Mcg;
 
 use crate::object::HitRecord;
 use crate::ray::Ray;
-use crate::texture::Sampler;
+use crate::texture::{Sampler, TexturePointer};
+use crate::utils::deg_to_rad;
 use crate::vec4::{Color, Vec4};
 
 use super::{Material, ScatterResult};
@@ -12,11 +13,28 @@ use super::{Material, ScatterResult};
 #[derive(Debug)]
 pub struct Emissive {
     emission_map: Arc<dyn Sampler<Output = Color>>,
+
+    /// Emit light from both sides of a surface, instead of only the front.
+    pub two_sided: bool,
+
+    /// Cosine of the max angle from the surface normal light is emitted at.
+    cos_spread: f64,
 }
 
 impl Emissive {
     pub fn new(emission_map: Arc<dyn Sampler<Output = Color>>) -> Self {
-        Emissive { emission_map }
+        Emissive {
+            emission_map,
+            two_sided: false,
+            cos_spread: -1.0,
+        }
+    }
+
+    /// Limit emission to a cone around the surface normal, as with a softbox
+    /// or honeycomb grid. `spread` is the full cone angle in degrees, from 0
+    /// (emits only along the normal) to 180 (no limit, the default).
+    pub fn set_spread(&mut self, spread: f64) {
+        self.cos_spread = f64::cos(deg_to_rad(spread.clamp(0.0, 180.0)) / 2.0);
     }
 }
 
@@ -25,12 +43,22 @@ impl Material for Emissive {
         ScatterResult::Emissive
     }
 
-    fn emit(&self, hit: &HitRecord) -> Color {
-        if hit.front_face() {
-            self.emission_map.sample(hit.uv(), &hit.pos())
-        } else {
-            Vec4::vec(0.0, 0.0, 0.0)
+    fn emit(&self, ray: &Ray, hit: &HitRecord) -> Color {
+        if !hit.front_face() && !self.two_sided {
+            return Vec4::vec(0.0, 0.0, 0.0);
         }
+
+        // Normal always faces the incoming ray
+        let cos_theta = Vec4::dot(&-ray.dir().to_unit(), &hit.normal());
+        if cos_theta < self.cos_spread {
+            return Vec4::vec(0.0, 0.0, 0.0);
+        }
+
+        self.emission_map.sample_hit(hit)
+    }
+
+    fn emission_map(&self) -> Option<&TexturePointer<Color>> {
+        Some(&self.emission_map)
     }
 
     fn scattering_pdf(&self, _: &Ray, _: &Ray, _: &HitRecord) -> f64 {
@@ -35,7 +35,7 @@ impl Glossy {
     fn get_normal(&self, hit: &HitRecord) -> Vec4 {
         if let Some(normal_map) = &self.normal_map {
             // Calculate surface-space normal
-            let sampled = normal_map.sample(hit.uv(), &hit.pos());
+            let sampled = normal_map.sample_hit(hit);
             let basis = Mat4::from_columns(
                 hit.tangent(),
                 hit.bitangent(),
@@ -59,12 +59,12 @@ impl Material for Glossy {
 
         let specular = reflectance(cos_theta, self.inv_ior) > rng.gen_range(0.0..1.0);
         if specular {
-            let roughness = self.roughness.sample(hit.uv(), &hit.pos());
+            let roughness = self.roughness.sample_hit(hit);
             let reflected = ray.dir().reflect(normal);
             let scatter_dir = reflected + Vec4::random_unit(rng) * roughness * reflected.length();
 
             if scatter_dir.dot(&normal) > 0.0 {
-                let scattered = Ray::new(hit.pos(), scatter_dir);
+                let scattered = ray.spawn(hit.pos(), scatter_dir);
                 ScatterResult::ScatteredWithRay {
                     attenuation: Vec4::vec(1.0, 1.0, 1.0),
                     scattered,
@@ -77,7 +77,7 @@ impl Material for Glossy {
             let pdf = Box::new(pdf);
 
             ScatterResult::ScatteredWithPDF {
-                attenuation: self.albedo.sample(hit.uv(), &hit.pos()),
+                attenuation: self.albedo.sample_hit(hit),
                 pdf,
             }
         }
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use rand_pcg::Pcg64Mcg;
+
+use crate::object::HitRecord;
+use crate::pdf::{henyey_greenstein, HenyeyGreensteinPDF};
+use crate::ray::Ray;
+use crate::texture::Sampler;
+use crate::vec4::{Color, Vec4};
+
+use super::{Material, ScatterResult};
+
+/// Max magnitude for asymmetry parameters, as the phase function degenerates
+/// into a delta at 1 or -1.
+const MAX_G: f64 = 0.99;
+
+/// A volume material with anisotropic scattering, following a
+/// Henyey-Greenstein phase function with one or two lobes.
+///
+/// Meant for volumes, like `Isotropic`. Positive `g` values scatter light
+/// forward (fog, clouds), negative values scatter it back towards where it
+/// came from. A second lobe, mixed in by `blend`, allows combining a strong
+/// forward peak with some back-scattering.
+#[derive(Debug)]
+pub struct HenyeyGreenstein {
+    albedo: Arc<dyn Sampler<Output = Color>>,
+    g: (f64, f64),
+    blend: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Arc<dyn Sampler<Output = Color>>, g: f64) -> Self {
+        Self::double(albedo, g, 0.0, 0.0)
+    }
+
+    /// Double-lobe Henyey-Greenstein, with `blend` as the weight of the second
+    /// lobe.
+    pub fn double(albedo: Arc<dyn Sampler<Output = Color>>, g1: f64, g2: f64, blend: f64) -> Self {
+        HenyeyGreenstein {
+            albedo,
+            g: (g1.clamp(-MAX_G, MAX_G), g2.clamp(-MAX_G, MAX_G)),
+            blend: blend.clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord, _: &mut Pcg64Mcg) -> ScatterResult {
+        let pdf = HenyeyGreensteinPDF::double(ray.dir().to_unit(), self.g.0, self.g.1, self.blend);
+        let pdf = Box::new(pdf);
+
+        ScatterResult::ScatteredWithPDF {
+            attenuation: self.albedo.sample_hit(hit),
+            pdf,
+        }
+    }
+
+    fn scattering_pdf(&self, ray_in: &Ray, scattered: &Ray, _: &HitRecord) -> f64 {
+        let cos_theta = Vec4::dot(&ray_in.dir().to_unit(), &scattered.dir().to_unit());
+
+        henyey_greenstein(cos_theta, self.g.0) * (1.0 - self.blend)
+            + henyey_greenstein(cos_theta, self.g.1) * self.blend
+    }
+}
@@ -27,7 +27,7 @@ impl Material for LambertianDiffuse {
         let pdf = Box::new(pdf);
 
         ScatterResult::ScatteredWithPDF {
-            attenuation: self.albedo.sample(hit.uv(), &hit.pos()),
+            attenuation: self.albedo.sample_hit(hit),
             pdf,
         }
     }
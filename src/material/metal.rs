@@ -28,14 +28,12 @@ impl Material for Metal {
     fn scatter(&self, ray: &Ray, hit: &HitRecord, rng: &mut Pcg64Mcg) -> ScatterResult {
         let reflected = ray.dir().reflect(hit.normal());
         let scatter_dir = reflected
-            + Vec4::random_unit(rng)
-                * self.roughness.sample(hit.uv(), &hit.pos())
-                * reflected.length();
+            + Vec4::random_unit(rng) * self.roughness.sample_hit(hit) * reflected.length();
 
         if scatter_dir.dot(&hit.normal()) > 0.0 {
-            let scattered = Ray::new(hit.pos(), scatter_dir);
+            let scattered = ray.spawn(hit.pos(), scatter_dir);
             ScatterResult::ScatteredWithRay {
-                attenuation: self.albedo.sample(hit.uv(), &hit.pos()),
+                attenuation: self.albedo.sample_hit(hit),
                 scattered,
             }
         } else {
@@ -23,7 +23,7 @@ impl NormalDebug {
     fn get_normal(&self, hit: &HitRecord) -> Vec4 {
         if let Some(normal_map) = &self.normal_map {
             // Calculate surface-space normal
-            let sampled = normal_map.sample(hit.uv(), &hit.pos());
+            let sampled = normal_map.sample_hit(hit);
             let basis = Mat4::from_columns(
                 hit.tangent(),
                 hit.bitangent(),
@@ -43,7 +43,7 @@ impl Material for NormalDebug {
         ScatterResult::Emissive
     }
 
-    fn emit(&self, hit: &HitRecord) -> Color {
+    fn emit(&self, _: &Ray, hit: &HitRecord) -> Color {
         self.get_normal(hit) * 0.5 + Vec4::vec(0.5, 0.5, 0.5)
     }
 
@@ -0,0 +1,91 @@
+pub mod fog;
+pub use fog::Fog;
+
+/// Max nesting depth tracked. Kept small, as the stack is copied with every ray.
+const MAX_MEDIA: usize = 4;
+
+/// A dielectric medium a ray can travel through.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Medium {
+    pub ior: f64,
+    pub priority: u32,
+}
+
+/// Tracks the nested media a ray is currently inside of.
+///
+/// Media are ranked by priority: where media overlap (such as liquid inside a
+/// glass, modeled with slightly overlapping surfaces), the one with highest
+/// priority is considered to fill the space. Surfaces belonging to lower
+/// priority media are ignored while inside a higher priority one. This follows
+/// "Simple Nested Dielectrics in Ray Traced Images" by Schmidt and Budge.
+#[derive(Clone, Copy, Debug)]
+pub struct MediumStack {
+    media: [Medium; MAX_MEDIA],
+    len: usize,
+}
+
+impl MediumStack {
+    pub const AIR: Medium = Medium {
+        ior: 1.0,
+        priority: 0,
+    };
+
+    pub fn new() -> Self {
+        MediumStack {
+            media: [Self::AIR; MAX_MEDIA],
+            len: 0,
+        }
+    }
+
+    /// The medium with the highest priority, which is the one the ray is
+    /// actually travelling through. Air if the ray isn't inside any medium.
+    pub fn current(&self) -> Medium {
+        // Ties go to the innermost medium
+        let mut current: Option<Medium> = None;
+        for &medium in self.media[..self.len].iter().rev() {
+            if current.is_none_or(|c| medium.priority > c.priority) {
+                current = Some(medium);
+            }
+        }
+
+        current.unwrap_or(Self::AIR)
+    }
+
+    /// Check whether a surface of a given medium is a real interface, or lies
+    /// inside a medium with higher priority and should be ignored.
+    pub fn is_interface(&self, medium: Medium) -> bool {
+        medium.priority >= self.current().priority
+    }
+
+    /// The medium the ray would be in after leaving `medium`.
+    pub fn outside_of(&self, medium: Medium) -> Medium {
+        let mut stack = *self;
+        stack.remove(medium);
+        stack.current()
+    }
+
+    pub fn push(&mut self, medium: Medium) {
+        // Past the max nesting depth, drop the outermost medium
+        if self.len == MAX_MEDIA {
+            self.media.copy_within(1.., 0);
+            self.len -= 1;
+        }
+
+        self.media[self.len] = medium;
+        self.len += 1;
+    }
+
+    /// Remove the innermost entry of a medium from the stack, if present.
+    pub fn remove(&mut self, medium: Medium) {
+        if let Some(idx) = self.media[..self.len].iter().rposition(|&m| m == medium) {
+            self.media.copy_within((idx + 1)..self.len, idx);
+            self.len -= 1;
+        }
+    }
+}
+
+impl Default for MediumStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_distr::Standard;
+use rand_pcg::Pcg64Mcg;
+
+use crate::config::FogConfig;
+use crate::material::HenyeyGreenstein;
+use crate::object::HitRecord;
+use crate::ray::Ray;
+use crate::texture::ConstantTexture;
+use crate::vec4::Vec4;
+
+/// A participating medium filling the whole scene, for haze and aerial
+/// perspective.
+///
+/// Unlike a `Volume`, fog has no boundary, so objects at infinity (`Sky`,
+/// `Sun`) are attenuated correctly. Density may fall off exponentially with
+/// height above `base_height`, and stays constant below it.
+#[derive(Debug)]
+pub struct Fog {
+    density: f64,
+    height_falloff: f64,
+    base_height: f64,
+    phase: HenyeyGreenstein,
+}
+
+impl Fog {
+    pub fn new(config: &FogConfig) -> Self {
+        let albedo = Arc::new(ConstantTexture::new(config.albedo));
+
+        Fog {
+            density: config.density,
+            height_falloff: config.height_falloff,
+            base_height: config.base_height,
+            phase: HenyeyGreenstein::new(albedo, config.g),
+        }
+    }
+
+    fn density_at(&self, height: f64) -> f64 {
+        let height = f64::max(height - self.base_height, 0.0);
+        self.density * f64::exp(-self.height_falloff * height)
+    }
+
+    /// Sample a scattering event along a ray, up to `t_max` (usually the
+    /// closest surface hit).
+    ///
+    /// Uses delta tracking. Density only depends on height, so the density at
+    /// the lowest point of the remaining ray segment is a valid majorant, and
+    /// is updated as the ray moves up.
+    pub fn test(&self, ray: &Ray, t_max: f64, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
+        let ray_len = ray.dir().length();
+        let rising = ray.dir().y() >= 0.0;
+
+        let mut t = 0.0;
+        loop {
+            let majorant = if rising {
+                self.density_at(ray.at(t).y())
+            } else if t_max.is_finite() {
+                self.density_at(ray.at(t_max).y())
+            } else {
+                self.density
+            };
+            if majorant <= 0.0 {
+                return None;
+            }
+
+            let r: f64 = rng.sample(Standard);
+            t -= f64::ln(1.0 - r) / (majorant * ray_len);
+            if t >= t_max {
+                return None;
+            }
+
+            let hit_pos = ray.at(t);
+            let r: f64 = rng.sample(Standard);
+            if r < self.density_at(hit_pos.y()) / majorant {
+                return Some(HitRecord::new(
+                    ray,
+                    hit_pos,
+                    t,
+                    (0.0, 0.0), // Arbitrary, unused
+                    -ray.dir().to_unit(),
+                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+                    &self.phase,
+                ));
+            }
+        }
+    }
+}
@@ -6,26 +6,43 @@ use crate::aabb::AxisAlignedBoundingBox;
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec4::{Point4, Vec4};
+use crate::vec4::{Color, Point4, Vec4};
 
 pub mod bvh;
+pub mod cone;
+pub mod cylinder;
+pub mod disk;
+pub mod heterogeneous_volume;
+pub mod instance;
 pub mod list;
 pub mod mesh;
+pub mod motion;
 pub mod obj_box;
 pub mod plane;
+mod shape;
 pub mod sky;
 pub mod sphere;
 pub mod sun;
+pub mod tessellate;
+pub mod torus;
 pub mod transform;
 pub mod volume;
 
-pub use bvh::BoundingVolumeHierarchyNode;
+pub use bvh::BoundingVolumeHierarchy;
+pub use cone::Cone;
+pub use cylinder::Cylinder;
+pub use disk::Disk;
+pub use heterogeneous_volume::HeterogeneousVolume;
+pub use instance::Instance;
 pub use list::ObjectList;
+pub use motion::{Keyframe, Motion};
 pub use obj_box::make_box;
 pub use plane::Plane;
 pub use sky::Sky;
 pub use sphere::Sphere;
 pub use sun::Sun;
+pub use tessellate::Tessellate;
+pub use torus::Torus;
 pub use transform::Transform;
 pub use volume::Volume;
 
@@ -38,6 +55,8 @@ pub struct HitRecord<'a> {
     uv: (f64, f64),
     front_face: bool,
     material: &'a dyn Material,
+    /// Color interpolated from the vertex colors of a mesh, if it has any.
+    vertex_color: Option<Color>,
 }
 
 impl<'a> HitRecord<'a> {
@@ -68,6 +87,7 @@ impl<'a> HitRecord<'a> {
             uv,
             front_face,
             material,
+            vertex_color: None,
         }
     }
 
@@ -102,6 +122,10 @@ impl<'a> HitRecord<'a> {
     pub fn material(&self) -> &'a dyn Material {
         self.material
     }
+
+    pub fn vertex_color(&self) -> Option<Color> {
+        self.vertex_color
+    }
 }
 
 pub trait Hit: Send + Sync + Debug {
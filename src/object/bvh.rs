@@ -1,6 +1,5 @@
 use std::sync::Arc;
 
-use rand::Rng;
 use rand_pcg::Pcg64Mcg;
 
 use crate::aabb::{self, AxisAlignedBoundingBox};
@@ -8,96 +7,56 @@ use crate::interval::Interval;
 use crate::ray::Ray;
 use crate::vec4::{Point4, Vec4};
 
-use self::null_obj::NullObject;
+use self::flat::FlatBvh;
+use self::sah::BuildItem;
 
-use super::{Hit, HitRecord};
+use super::{Hit, HitRecord, ObjectList};
 
-mod null_obj;
-
-pub const AXES_X: [bool; 3] = [true, false, false];
-pub const AXES_Y: [bool; 3] = [false, true, false];
-pub const AXES_Z: [bool; 3] = [false, false, true];
-pub const AXES_XY: [bool; 3] = [true, true, false];
-pub const AXES_XZ: [bool; 3] = [true, false, true];
-pub const AXES_YZ: [bool; 3] = [false, true, true];
-pub const AXES_ALL: [bool; 3] = [true, true, true];
+pub mod flat;
+pub mod sah;
 
+/// A bounding volume hierarchy over a set of objects, built with the surface
+/// area heuristic.
+///
+/// The tree is stored as a flat array of nodes in depth-first order, with
+/// single-precision bounds to keep nodes small. Traversal uses an explicit
+/// stack, and visits the child nearest to the ray origin first so farther
+/// nodes can be skipped once something is hit.
 #[derive(Debug)]
-pub struct BoundingVolumeHierarchyNode {
-    children: (Arc<dyn Hit>, Arc<dyn Hit>),
+pub struct BoundingVolumeHierarchy {
+    /// Objects in leaf order, each leaf holds a single object.
+    objects: Vec<Arc<dyn Hit>>,
+    tree: FlatBvh,
     bounds: AxisAlignedBoundingBox,
 }
 
-impl BoundingVolumeHierarchyNode {
-    pub fn from(mut objects: Vec<Arc<dyn Hit>>, axes: [bool; 3], rng: &mut Pcg64Mcg) -> Self {
-        let mut axis_idx = rng.gen_range(0..3);
-        while !axes[axis_idx] {
-            axis_idx = rng.gen_range(0..3);
+impl BoundingVolumeHierarchy {
+    pub fn from(objects: Vec<Arc<dyn Hit>>) -> Self {
+        if objects.is_empty() {
+            return Self::from(vec![Arc::new(ObjectList::new())]);
         }
 
-        let comparator = |a: &Arc<dyn Hit>, b: &Arc<dyn Hit>| {
-            let min_a = a.get_bounding_box()[0][axis_idx];
-            let min_b = b.get_bounding_box()[0][axis_idx];
-
-            min_a.total_cmp(&min_b)
-        };
-
-        let object_count = objects.len();
-        let children: (Arc<dyn Hit>, Arc<dyn Hit>);
-        let bounds: AxisAlignedBoundingBox;
-
-        if object_count == 1 {
-            children = (objects.pop().unwrap(), Arc::new(NullObject()));
-            bounds = children.0.get_bounding_box();
-
-            BoundingVolumeHierarchyNode { children, bounds }
-        } else if object_count == 2 {
-            children = (objects.pop().unwrap(), objects.pop().unwrap());
-            bounds = aabb::combine_bounds(&[
-                children.0.get_bounding_box(),
-                children.1.get_bounding_box(),
-            ]);
-
-            BoundingVolumeHierarchyNode { children, bounds }
-        } else {
-            objects.sort_unstable_by(comparator);
-
-            let midpoint = object_count / 2;
-            let second_half = objects.split_off(midpoint);
-            let first_half = objects;
+        let mut items: Vec<_> = objects
+            .into_iter()
+            .map(|obj| BuildItem::new(obj.get_bounding_box(), obj))
+            .collect();
 
-            children = (
-                Arc::new(Self::from(first_half, axes, rng)),
-                Arc::new(Self::from(second_half, axes, rng)),
-            );
-            bounds = aabb::combine_bounds(&[
-                children.0.get_bounding_box(),
-                children.1.get_bounding_box(),
-            ]);
+        let tree = FlatBvh::build(&mut items, 1);
+        let bounds = aabb::combine_bounds(&[sah::item_bounds(&items)]);
 
-            BoundingVolumeHierarchyNode { children, bounds }
+        BoundingVolumeHierarchy {
+            objects: items.into_iter().map(|item| item.value).collect(),
+            tree,
+            bounds,
         }
     }
 }
 
-impl Hit for BoundingVolumeHierarchyNode {
+impl Hit for BoundingVolumeHierarchy {
     fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
-        if !aabb::test_bounding_box(&self.bounds, ray, &t) {
-            return None;
-        }
-
-        let mut closest_hit: Option<HitRecord> = None;
-        let mut closest_t = t.max();
-
-        if let Some(hit) = self.children.0.test(ray, Interval(t.min(), closest_t), rng) {
-            closest_t = hit.t;
-            closest_hit = Some(hit);
-        }
-        if let Some(hit) = self.children.1.test(ray, Interval(t.min(), closest_t), rng) {
-            closest_hit = Some(hit);
-        }
-
-        closest_hit
+        self.tree.test(ray, t, |range, interval| {
+            self.objects[range.start].test(ray, interval, rng)
+        })
     }
 
     fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
@@ -0,0 +1,341 @@
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::thread;
+
+use crate::aabb::{self, AxisAlignedBoundingBox};
+use crate::binary::{self, ReadBinary, WriteBinary};
+use crate::interval::Interval;
+use crate::object::HitRecord;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::sah::{self, BuildItem};
+
+/// Min number of items for a subtree to be built on a separate thread.
+const PARALLEL_BUILD_THRESHOLD: usize = 4096;
+
+/// Max tree depth supported by traversal.
+const MAX_DEPTH: usize = 128;
+
+/// Depth past which nodes are split at the median instead of using SAH, so
+/// pathological inputs can't go past `MAX_DEPTH`.
+const MAX_SAH_DEPTH: usize = 96;
+
+/// Bounding box stored with single precision, rounded outwards so it always
+/// contains the original box.
+#[derive(Clone, Copy, Debug)]
+struct CompactBounds([[f32; 3]; 2]);
+
+impl CompactBounds {
+    fn from(bounds: &AxisAlignedBoundingBox) -> Self {
+        let round_down = |x: f64| {
+            let v = x as f32;
+            if v as f64 > x {
+                v.next_down()
+            } else {
+                v
+            }
+        };
+        let round_up = |x: f64| {
+            let v = x as f32;
+            if (v as f64) < x {
+                v.next_up()
+            } else {
+                v
+            }
+        };
+
+        let [min, max] = bounds;
+        CompactBounds([
+            [round_down(min[0]), round_down(min[1]), round_down(min[2])],
+            [round_up(max[0]), round_up(max[1]), round_up(max[2])],
+        ])
+    }
+
+    /// Ray-box slab test, returning the distance the ray enters the box at.
+    #[inline(always)]
+    fn intersect(
+        &self,
+        origin: &Point4,
+        inv_dir: &Vec4,
+        sign: &[u8; 3],
+        t: &Interval,
+    ) -> Option<f64> {
+        let mut t_min = t.min();
+        let mut t_max = t.max();
+
+        for axis in 0..3 {
+            let near = self.0[sign[axis] as usize][axis] as f64;
+            let far = self.0[1 - sign[axis] as usize][axis] as f64;
+
+            let t_near = (near - origin[axis]) * inv_dir[axis];
+            let t_far = (far - origin[axis]) * inv_dir[axis];
+
+            // Written so NaNs (ray parallel to a slab, origin on its plane) don't discard the box
+            if t_near > t_min {
+                t_min = t_near;
+            }
+            if t_far < t_max {
+                t_max = t_far;
+            }
+        }
+
+        if t_min <= t_max {
+            Some(t_min)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+struct FlatNode {
+    bounds: CompactBounds,
+    /// Leaf nodes: index of the first item. Branch nodes: index of the
+    /// second child (the first child is always the next node).
+    offset: u32,
+    /// Number of items in a leaf, 0 for branch nodes.
+    count: u16,
+    /// Split axis, decides which child is visited first.
+    axis: u8,
+}
+
+/// Temporary pointer-based tree, flattened once built.
+enum BuildNode {
+    Leaf(Range<usize>, AxisAlignedBoundingBox),
+    Branch(Box<(BuildNode, BuildNode)>, AxisAlignedBoundingBox, usize),
+}
+
+impl BuildNode {
+    fn bounds(&self) -> AxisAlignedBoundingBox {
+        match self {
+            Self::Leaf(_, bounds) | Self::Branch(_, bounds, _) => *bounds,
+        }
+    }
+}
+
+/// A bounding volume hierarchy stored as a flat array of nodes.
+///
+/// The tree only stores item ranges: leaves refer to items by their index in
+/// the list the tree was built from, which is reordered during the build.
+/// Nodes are laid out in depth-first order with single-precision bounds, to
+/// keep them small. Traversal uses an explicit stack, and visits the child
+/// nearest to the ray origin first so farther nodes can be skipped once
+/// something is hit.
+#[derive(Debug)]
+pub struct FlatBvh {
+    nodes: Vec<FlatNode>,
+}
+
+impl FlatBvh {
+    /// Build a tree over a non-empty list of items, using the surface area
+    /// heuristic. Items are reordered so each leaf covers a contiguous range.
+    pub fn build<T: Send>(items: &mut [BuildItem<T>], max_leaf_size: usize) -> Self {
+        assert!(!items.is_empty(), "Can't build a BVH with no items");
+
+        let root = Self::build_node(items, 0, max_leaf_size.min(u16::MAX as usize), 0);
+
+        let mut nodes = Vec::with_capacity(2 * items.len() - 1);
+        Self::flatten(root, &mut nodes);
+
+        FlatBvh { nodes }
+    }
+
+    fn build_node<T: Send>(
+        items: &mut [BuildItem<T>],
+        first: usize,
+        max_leaf_size: usize,
+        depth: usize,
+    ) -> BuildNode {
+        let split = if depth < MAX_SAH_DEPTH {
+            sah::split(items, max_leaf_size)
+        } else if items.len() > max_leaf_size {
+            Some(sah::split_median(items))
+        } else {
+            None
+        };
+
+        let Some(split) = split else {
+            let bounds = aabb::combine_bounds(&[sah::item_bounds(items)]);
+            return BuildNode::Leaf(first..(first + items.len()), bounds);
+        };
+
+        let count = items.len();
+        let (left, right) = items.split_at_mut(split.mid);
+        let right_first = first + split.mid;
+        let children = if count >= PARALLEL_BUILD_THRESHOLD {
+            thread::scope(|s| {
+                let left = s.spawn(|| Self::build_node(left, first, max_leaf_size, depth + 1));
+                let right = Self::build_node(right, right_first, max_leaf_size, depth + 1);
+
+                (left.join().expect("BVH build thread failed"), right)
+            })
+        } else {
+            (
+                Self::build_node(left, first, max_leaf_size, depth + 1),
+                Self::build_node(right, right_first, max_leaf_size, depth + 1),
+            )
+        };
+
+        let bounds = aabb::combine_bounds(&[children.0.bounds(), children.1.bounds()]);
+        BuildNode::Branch(Box::new(children), bounds, split.axis)
+    }
+
+    fn flatten(node: BuildNode, nodes: &mut Vec<FlatNode>) {
+        match node {
+            BuildNode::Leaf(range, bounds) => nodes.push(FlatNode {
+                bounds: CompactBounds::from(&bounds),
+                offset: range.start as u32,
+                count: range.len() as u16,
+                axis: 0,
+            }),
+            BuildNode::Branch(children, bounds, axis) => {
+                let node_idx = nodes.len();
+                nodes.push(FlatNode {
+                    bounds: CompactBounds::from(&bounds),
+                    offset: 0,
+                    count: 0,
+                    axis: axis as u8,
+                });
+
+                let (first, second) = *children;
+                Self::flatten(first, nodes);
+                nodes[node_idx].offset = nodes.len() as u32;
+                Self::flatten(second, nodes);
+            }
+        }
+    }
+
+    /// Write the tree in a binary format, to be read back by `read_from`.
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_u32(self.nodes.len() as u32)?;
+        for node in &self.nodes {
+            for value in node.bounds.0.as_flattened() {
+                w.write_f32(*value)?;
+            }
+            w.write_u32(node.offset)?;
+            w.write_u32(node.count as u32 | (node.axis as u32) << 16)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a tree written by `write_to`, built over `item_count` items.
+    /// Fails if the tree doesn't match the item count or is malformed.
+    pub fn read_from(r: &mut impl Read, item_count: usize) -> io::Result<Self> {
+        let node_count = r.read_index(2 * item_count)?;
+        if node_count == 0 {
+            return Err(binary::invalid_data("BVH has no nodes"));
+        }
+
+        let mut nodes = Vec::with_capacity(node_count);
+        for _ in 0..node_count {
+            let mut bounds = [[0.0; 3]; 2];
+            for value in bounds.as_flattened_mut() {
+                *value = r.read_f32()?;
+            }
+            let offset = r.read_u32()?;
+            let packed = r.read_u32()?;
+
+            nodes.push(FlatNode {
+                bounds: CompactBounds(bounds),
+                offset,
+                count: packed as u16,
+                axis: (packed >> 16) as u8,
+            });
+        }
+
+        // Nodes are in depth-first order, so parents always come before their children
+        let mut depths = vec![None; node_count];
+        depths[0] = Some(0);
+        for (idx, node) in nodes.iter().enumerate() {
+            let depth = depths[idx].ok_or(binary::invalid_data("Unreachable BVH node"))?;
+            if node.axis > 2 || depth >= MAX_DEPTH {
+                return Err(binary::invalid_data("Invalid BVH node"));
+            }
+
+            if node.count > 0 {
+                if node.offset as usize + node.count as usize > item_count {
+                    return Err(binary::invalid_data("BVH leaf out of range"));
+                }
+            } else {
+                let second = node.offset as usize;
+                if second <= idx + 1 || second >= node_count {
+                    return Err(binary::invalid_data("BVH child out of range"));
+                }
+                for child in [idx + 1, second] {
+                    depths[child] = depths[child].max(Some(depth + 1));
+                }
+            }
+        }
+
+        Ok(FlatBvh { nodes })
+    }
+
+    /// Find the closest hit along a ray.
+    ///
+    /// `test_leaf` is called with the range of items in each leaf the ray
+    /// reaches, and the interval to test them in.
+    #[inline(always)]
+    pub fn test<'a>(
+        &self,
+        ray: &Ray,
+        t: Interval,
+        mut test_leaf: impl FnMut(Range<usize>, Interval) -> Option<HitRecord<'a>>,
+    ) -> Option<HitRecord<'a>> {
+        let origin = ray.origin();
+        let inv_dir = ray.inv_dir();
+        let sign = ray.sign();
+
+        let root_t = self.nodes[0]
+            .bounds
+            .intersect(&origin, &inv_dir, &sign, &t)?;
+
+        let mut closest_hit: Option<HitRecord> = None;
+        let mut closest_t = t.max();
+
+        // Nodes left to visit, along with the distance the ray enters them at
+        let mut stack = [(0usize, 0.0); MAX_DEPTH];
+        stack[0] = (0, root_t);
+        let mut stack_len = 1;
+
+        while stack_len > 0 {
+            stack_len -= 1;
+            let (node_idx, entry_t) = stack[stack_len];
+            if entry_t > closest_t {
+                continue; // Something closer was hit after this node was pushed
+            }
+
+            let node = &self.nodes[node_idx];
+            let interval = Interval(t.min(), closest_t);
+
+            if node.count > 0 {
+                let start = node.offset as usize;
+                if let Some(hit) = test_leaf(start..(start + node.count as usize), interval) {
+                    closest_t = hit.t();
+                    closest_hit = Some(hit);
+                }
+                continue;
+            }
+
+            let first = node_idx + 1;
+            let second = node.offset as usize;
+            let (near, far) = if sign[node.axis as usize] == 0 {
+                (first, second)
+            } else {
+                (second, first)
+            };
+
+            // Push the far child first, so the near one is visited first
+            for child in [far, near] {
+                let bounds = &self.nodes[child].bounds;
+                if let Some(child_t) = bounds.intersect(&origin, &inv_dir, &sign, &interval) {
+                    stack[stack_len] = (child, child_t);
+                    stack_len += 1;
+                }
+            }
+        }
+
+        closest_hit
+    }
+}
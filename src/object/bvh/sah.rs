@@ -0,0 +1,171 @@
+use crate::aabb::{self, AxisAlignedBoundingBox};
+use crate::constants::INFINITY;
+use crate::vec4::Point4;
+
+const BIN_COUNT: usize = 16;
+
+/// Cost of traversing a BVH node, relative to the cost of testing one item.
+const TRAVERSAL_COST: f64 = 0.5;
+
+/// An item to be placed in a BVH, with its bounds precomputed.
+#[derive(Debug)]
+pub struct BuildItem<T> {
+    pub bounds: AxisAlignedBoundingBox,
+    pub centroid: Point4,
+    pub value: T,
+}
+
+impl<T> BuildItem<T> {
+    pub fn new(bounds: AxisAlignedBoundingBox, value: T) -> Self {
+        BuildItem {
+            bounds,
+            centroid: aabb::centroid(&bounds),
+            value,
+        }
+    }
+}
+
+fn union(a: &AxisAlignedBoundingBox, b: &AxisAlignedBoundingBox) -> AxisAlignedBoundingBox {
+    let mut bounds = *a;
+    for i in 0..3 {
+        bounds[0][i] = f64::min(a[0][i], b[0][i]);
+        bounds[1][i] = f64::max(a[1][i], b[1][i]);
+    }
+
+    bounds
+}
+
+/// Combined bounds of a set of items, without padding.
+pub fn item_bounds<T>(items: &[BuildItem<T>]) -> AxisAlignedBoundingBox {
+    items
+        .iter()
+        .fold([INFINITY, -INFINITY], |acc, item| union(&acc, &item.bounds))
+}
+
+/// A split of a set of items: items before `mid` go in the left child.
+#[derive(Clone, Copy, Debug)]
+pub struct Split {
+    pub mid: usize,
+    pub axis: usize,
+}
+
+/// Split a set of items in two using the surface area heuristic (SAH).
+///
+/// Items are sorted into bins along each axis by centroid, and the split
+/// between bins with the lowest estimated cost is picked. Items are reordered
+/// in place to match the returned split.
+///
+/// Returns `None` if keeping all items in a single leaf is cheaper than any
+/// split, which is only allowed for up to `max_leaf_size` items.
+pub fn split<T>(items: &mut [BuildItem<T>], max_leaf_size: usize) -> Option<Split> {
+    let count = items.len();
+    if count <= 1 {
+        return None;
+    }
+
+    let bounds = item_bounds(items);
+    let centroid_bounds = items.iter().fold([INFINITY, -INFINITY], |acc, item| {
+        union(&acc, &[item.centroid, item.centroid])
+    });
+
+    let centroid_extent = centroid_bounds[1] - centroid_bounds[0];
+
+    let parent_area = aabb::surface_area(&bounds);
+    let bin_index = |centroid: &Point4, axis: usize| {
+        let rel = (centroid[axis] - centroid_bounds[0][axis]) / centroid_extent[axis];
+        usize::min((rel * BIN_COUNT as f64) as usize, BIN_COUNT - 1)
+    };
+
+    // Best split found, as (cost, axis, first bin of the right side)
+    let mut best: Option<(f64, usize, usize)> = None;
+
+    for axis in 0..3 {
+        if centroid_extent[axis] <= f64::EPSILON {
+            continue;
+        }
+
+        let mut bin_counts = [0usize; BIN_COUNT];
+        let mut bin_bounds = [[INFINITY, -INFINITY]; BIN_COUNT];
+        for item in items.iter() {
+            let bin = bin_index(&item.centroid, axis);
+            bin_counts[bin] += 1;
+            bin_bounds[bin] = union(&bin_bounds[bin], &item.bounds);
+        }
+
+        // Sweep from the right to get the cost of every right side
+        let mut right_costs = [0.0; BIN_COUNT];
+        let mut right_bounds = [INFINITY, -INFINITY];
+        let mut right_count = 0;
+        for bin in (1..BIN_COUNT).rev() {
+            right_bounds = union(&right_bounds, &bin_bounds[bin]);
+            right_count += bin_counts[bin];
+            right_costs[bin] = aabb::surface_area(&right_bounds) * right_count as f64;
+        }
+
+        // Sweep from the left, combining with the right side cost
+        let mut left_bounds = [INFINITY, -INFINITY];
+        let mut left_count = 0;
+        for bin in 1..BIN_COUNT {
+            left_bounds = union(&left_bounds, &bin_bounds[bin - 1]);
+            left_count += bin_counts[bin - 1];
+            if left_count == 0 || left_count == count {
+                continue;
+            }
+
+            let left_cost = aabb::surface_area(&left_bounds) * left_count as f64;
+            let cost = TRAVERSAL_COST + (left_cost + right_costs[bin]) / parent_area;
+            if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                best = Some((cost, axis, bin));
+            }
+        }
+    }
+
+    match best {
+        Some((cost, axis, bin)) => {
+            if count <= max_leaf_size && cost >= count as f64 {
+                return None;
+            }
+
+            // Partition in place, items in bins before the split go first
+            let mut mid = 0;
+            for i in 0..count {
+                if bin_index(&items[i].centroid, axis) < bin {
+                    items.swap(i, mid);
+                    mid += 1;
+                }
+            }
+
+            Some(Split { mid, axis })
+        }
+        None => {
+            // All centroids overlap, no split is better than another
+            if count <= max_leaf_size {
+                None
+            } else {
+                Some(Split {
+                    mid: count / 2,
+                    axis: 0,
+                })
+            }
+        }
+    }
+}
+
+/// Split a set of items in two halves at the median centroid along their
+/// largest axis. Guarantees a balanced split, regardless of cost.
+pub fn split_median<T>(items: &mut [BuildItem<T>]) -> Split {
+    let bounds = item_bounds(items);
+    let d = bounds[1] - bounds[0];
+    let axis = if d.x() > d.y() && d.x() > d.z() {
+        0
+    } else if d.y() > d.z() {
+        1
+    } else {
+        2
+    };
+
+    let mid = items.len() / 2;
+    items.select_nth_unstable_by(mid, |a, b| a.centroid[axis].total_cmp(&b.centroid[axis]));
+
+    Split { mid, axis }
+}
@@ -0,0 +1,204 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::shape::{self, azimuth, azimuth_tangent, Frame, LocalShape, SurfacePoint};
+use super::tessellate::{MeshBuilder, Tessellate};
+use super::{Hit, HitRecord};
+
+#[derive(Clone, Copy, Debug)]
+pub(super) enum ConePart {
+    Side,
+    Base,
+}
+
+/// A cone going from its base center to its apex along its axis, closed by a
+/// cap at its base unless it's open. UVs go around the cone in U, and along
+/// the axis in V on the side, or from the center to the edge on the cap.
+#[derive(Debug)]
+pub struct Cone {
+    pub material: Arc<dyn Material>,
+
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point4,
+        axis: Vec4,
+        radius: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let frame = Frame::new(base, axis);
+        let height = axis.length();
+        let bounds = frame.bounds(
+            Vec4::vec(-radius, -radius, 0.0),
+            Vec4::vec(radius, radius, height),
+        );
+
+        Cone {
+            material,
+            frame,
+            radius,
+            height,
+            capped,
+            bounds,
+        }
+    }
+
+    fn side_area(&self) -> f64 {
+        PI * self.radius * f64::sqrt(self.radius * self.radius + self.height * self.height)
+    }
+}
+
+impl LocalShape for Cone {
+    type Part = ConePart;
+
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn material(&self) -> &dyn Material {
+        Arc::as_ref(&self.material)
+    }
+
+    fn intersect(&self, origin: Vec4, dir: Vec4, found: &mut dyn FnMut(f64, ConePart)) {
+        // Side, where x² + y² = (k * (h - z))², cut between the base and the apex
+        let k = self.radius / self.height;
+        let k_squared = k * k;
+        let dz = self.height - origin.z();
+
+        let a = dir.x() * dir.x() + dir.y() * dir.y() - k_squared * dir.z() * dir.z();
+        let half_b = origin.x() * dir.x() + origin.y() * dir.y() + k_squared * dz * dir.z();
+        let c = origin.x() * origin.x() + origin.y() * origin.y() - k_squared * dz * dz;
+
+        let mut side_hit = |root: f64| {
+            let z = origin.z() + dir.z() * root;
+            if (0.0..=self.height).contains(&z) {
+                found(root, ConePart::Side);
+            }
+        };
+        if a.abs() < 1e-12 {
+            // Rays parallel to the side only hit it once
+            if half_b != 0.0 {
+                side_hit(-c / (2.0 * half_b));
+            }
+        } else {
+            let discriminant = half_b * half_b - a * c;
+            if discriminant >= 0.0 {
+                let d_sqrt = discriminant.sqrt();
+                side_hit((-half_b - d_sqrt) / a);
+                side_hit((-half_b + d_sqrt) / a);
+            }
+        }
+
+        if self.capped && dir.z() != 0.0 {
+            let root = -origin.z() / dir.z();
+            let p = origin + dir * root;
+            if p.x() * p.x() + p.y() * p.y() <= self.radius * self.radius {
+                found(root, ConePart::Base);
+            }
+        }
+    }
+
+    fn surface_at(&self, p: Vec4, part: ConePart) -> SurfacePoint {
+        let phi = azimuth(p);
+        let u = phi / (2.0 * PI);
+        let r = f64::sqrt(p.x() * p.x() + p.y() * p.y());
+
+        let (normal, v) = match part {
+            ConePart::Side => {
+                // The side leans outwards by the angle of the cone
+                let normal = Vec4::vec(
+                    self.height * phi.cos(),
+                    self.height * phi.sin(),
+                    self.radius,
+                );
+                (normal.to_unit(), p.z() / self.height)
+            }
+            ConePart::Base => (Vec4::vec(0.0, 0.0, -1.0), r / self.radius),
+        };
+
+        SurfacePoint {
+            normal,
+            uv: (u, v),
+            tangent: azimuth_tangent(phi),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        if self.capped {
+            self.side_area() + PI * self.radius * self.radius
+        } else {
+            self.side_area()
+        }
+    }
+
+    fn sample_point(&self, rng: &mut Pcg64Mcg) -> Vec4 {
+        let phi = rng.gen_range(0.0..(2.0 * PI));
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        // Pick the side or the base proportionally to their areas. Both have
+        // an area density growing linearly with the distance to the axis
+        let s = f64::sqrt(rng.gen_range(0.0..1.0));
+        let r = self.radius * s;
+        let pick = rng.gen_range(0.0..self.area());
+        let z = if pick < self.side_area() {
+            self.height * (1.0 - s)
+        } else {
+            0.0
+        };
+
+        Vec4::vec(r * cos_phi, r * sin_phi, z)
+    }
+}
+
+impl Tessellate for Cone {
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize) {
+        let size = (2 * resolution, resolution);
+        let circle = |u: f64, r: f64, z: f64| {
+            let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+            Vec4::vec(r * cos_phi, r * sin_phi, z)
+        };
+
+        shape::add_patch(self, builder, size, ConePart::Side, |u, v| {
+            circle(u, (1.0 - v) * self.radius, v * self.height)
+        });
+        if self.capped {
+            shape::add_patch(self, builder, size, ConePart::Base, |u, v| {
+                circle(u, v * self.radius, 0.0)
+            });
+        }
+    }
+}
+
+impl Hit for Cone {
+    fn test(&self, ray: &Ray, t: Interval, _: &mut Pcg64Mcg) -> Option<HitRecord<'_>> {
+        shape::test(self, ray, t)
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.bounds
+    }
+
+    fn pdf_value(&self, origin: Point4, dir: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        shape::pdf_value(self, origin, dir)
+    }
+
+    fn random(&self, origin: Point4, rng: &mut Pcg64Mcg) -> Vec4 {
+        shape::random(self, origin, rng)
+    }
+}
@@ -0,0 +1,193 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::shape::{self, azimuth, azimuth_tangent, Frame, LocalShape, SurfacePoint};
+use super::tessellate::{MeshBuilder, Tessellate};
+use super::{Hit, HitRecord};
+
+#[derive(Clone, Copy, Debug)]
+pub(super) enum CylinderPart {
+    Side,
+    Bottom,
+    Top,
+}
+
+/// A cylinder going from its base center along its axis, closed by two caps
+/// unless it's open. UVs go around the cylinder in U, and along the axis in V
+/// on the side, or from the center to the edge on the caps.
+#[derive(Debug)]
+pub struct Cylinder {
+    pub material: Arc<dyn Material>,
+
+    frame: Frame,
+    radius: f64,
+    height: f64,
+    capped: bool,
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point4,
+        axis: Vec4,
+        radius: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let frame = Frame::new(base, axis);
+        let height = axis.length();
+        let bounds = frame.bounds(
+            Vec4::vec(-radius, -radius, 0.0),
+            Vec4::vec(radius, radius, height),
+        );
+
+        Cylinder {
+            material,
+            frame,
+            radius,
+            height,
+            capped,
+            bounds,
+        }
+    }
+
+    fn side_area(&self) -> f64 {
+        2.0 * PI * self.radius * self.height
+    }
+}
+
+impl LocalShape for Cylinder {
+    type Part = CylinderPart;
+
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn material(&self) -> &dyn Material {
+        Arc::as_ref(&self.material)
+    }
+
+    fn intersect(&self, origin: Vec4, dir: Vec4, found: &mut dyn FnMut(f64, CylinderPart)) {
+        let radius_squared = self.radius * self.radius;
+
+        // Side, an infinite cylinder cut between the caps
+        let a = dir.x() * dir.x() + dir.y() * dir.y();
+        let half_b = origin.x() * dir.x() + origin.y() * dir.y();
+        let c = origin.x() * origin.x() + origin.y() * origin.y() - radius_squared;
+        let discriminant = half_b * half_b - a * c;
+        if a > 0.0 && discriminant >= 0.0 {
+            let d_sqrt = discriminant.sqrt();
+            for root in [(-half_b - d_sqrt) / a, (-half_b + d_sqrt) / a] {
+                let z = origin.z() + dir.z() * root;
+                if (0.0..=self.height).contains(&z) {
+                    found(root, CylinderPart::Side);
+                }
+            }
+        }
+
+        if self.capped && dir.z() != 0.0 {
+            for (z, part) in [
+                (0.0, CylinderPart::Bottom),
+                (self.height, CylinderPart::Top),
+            ] {
+                let root = (z - origin.z()) / dir.z();
+                let p = origin + dir * root;
+                if p.x() * p.x() + p.y() * p.y() <= radius_squared {
+                    found(root, part);
+                }
+            }
+        }
+    }
+
+    fn surface_at(&self, p: Vec4, part: CylinderPart) -> SurfacePoint {
+        let phi = azimuth(p);
+        let u = phi / (2.0 * PI);
+        let r = f64::sqrt(p.x() * p.x() + p.y() * p.y());
+
+        let (normal, v) = match part {
+            CylinderPart::Side => (Vec4::vec(phi.cos(), phi.sin(), 0.0), p.z() / self.height),
+            CylinderPart::Bottom => (Vec4::vec(0.0, 0.0, -1.0), r / self.radius),
+            CylinderPart::Top => (Vec4::vec(0.0, 0.0, 1.0), r / self.radius),
+        };
+
+        SurfacePoint {
+            normal,
+            uv: (u, v),
+            tangent: azimuth_tangent(phi),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        let cap_area = PI * self.radius * self.radius;
+        if self.capped {
+            self.side_area() + 2.0 * cap_area
+        } else {
+            self.side_area()
+        }
+    }
+
+    fn sample_point(&self, rng: &mut Pcg64Mcg) -> Vec4 {
+        let phi = rng.gen_range(0.0..(2.0 * PI));
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        // Pick the side or a cap proportionally to their areas
+        let pick = rng.gen_range(0.0..self.area());
+        if pick < self.side_area() {
+            let z = rng.gen_range(0.0..self.height);
+            Vec4::vec(self.radius * cos_phi, self.radius * sin_phi, z)
+        } else {
+            let r = self.radius * f64::sqrt(rng.gen_range(0.0..1.0));
+            let z = if rng.gen_bool(0.5) { 0.0 } else { self.height };
+            Vec4::vec(r * cos_phi, r * sin_phi, z)
+        }
+    }
+}
+
+impl Tessellate for Cylinder {
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize) {
+        let size = (2 * resolution, resolution);
+        let circle = |u: f64, r: f64, z: f64| {
+            let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+            Vec4::vec(r * cos_phi, r * sin_phi, z)
+        };
+
+        shape::add_patch(self, builder, size, CylinderPart::Side, |u, v| {
+            circle(u, self.radius, v * self.height)
+        });
+        if self.capped {
+            shape::add_patch(self, builder, size, CylinderPart::Bottom, |u, v| {
+                circle(u, v * self.radius, 0.0)
+            });
+            shape::add_patch(self, builder, size, CylinderPart::Top, |u, v| {
+                circle(u, v * self.radius, self.height)
+            });
+        }
+    }
+}
+
+impl Hit for Cylinder {
+    fn test(&self, ray: &Ray, t: Interval, _: &mut Pcg64Mcg) -> Option<HitRecord<'_>> {
+        shape::test(self, ray, t)
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.bounds
+    }
+
+    fn pdf_value(&self, origin: Point4, dir: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        shape::pdf_value(self, origin, dir)
+    }
+
+    fn random(&self, origin: Point4, rng: &mut Pcg64Mcg) -> Vec4 {
+        shape::random(self, origin, rng)
+    }
+}
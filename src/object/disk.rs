@@ -0,0 +1,116 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::shape::{self, azimuth, azimuth_tangent, Frame, LocalShape, SurfacePoint};
+use super::tessellate::{MeshBuilder, Tessellate};
+use super::{Hit, HitRecord};
+
+/// A flat disk facing along its normal. UVs go around the disk in U, and from
+/// the center to the edge in V.
+#[derive(Debug)]
+pub struct Disk {
+    pub material: Arc<dyn Material>,
+
+    frame: Frame,
+    radius: f64,
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Disk {
+    pub fn new(center: Point4, normal: Vec4, radius: f64, material: Arc<dyn Material>) -> Self {
+        let frame = Frame::new(center, normal);
+        let bounds = frame.bounds(
+            Vec4::vec(-radius, -radius, 0.0),
+            Vec4::vec(radius, radius, 0.0),
+        );
+
+        Disk {
+            material,
+            frame,
+            radius,
+            bounds,
+        }
+    }
+}
+
+impl LocalShape for Disk {
+    type Part = ();
+
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn material(&self) -> &dyn Material {
+        Arc::as_ref(&self.material)
+    }
+
+    fn intersect(&self, origin: Vec4, dir: Vec4, found: &mut dyn FnMut(f64, ())) {
+        if dir.z() == 0.0 {
+            return;
+        }
+
+        let t = -origin.z() / dir.z();
+        let p = origin + dir * t;
+        if p.x() * p.x() + p.y() * p.y() <= self.radius * self.radius {
+            found(t, ());
+        }
+    }
+
+    fn surface_at(&self, p: Vec4, _: ()) -> SurfacePoint {
+        let phi = azimuth(p);
+        let r = f64::sqrt(p.x() * p.x() + p.y() * p.y());
+
+        SurfacePoint {
+            normal: Vec4::vec(0.0, 0.0, 1.0),
+            uv: (phi / (2.0 * PI), r / self.radius),
+            tangent: azimuth_tangent(phi),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+
+    fn sample_point(&self, rng: &mut Pcg64Mcg) -> Vec4 {
+        let r = self.radius * f64::sqrt(rng.gen_range(0.0..1.0));
+        let phi = rng.gen_range(0.0..(2.0 * PI));
+
+        Vec4::vec(r * phi.cos(), r * phi.sin(), 0.0)
+    }
+}
+
+impl Tessellate for Disk {
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize) {
+        shape::add_patch(self, builder, (2 * resolution, resolution), (), |u, v| {
+            let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+            Vec4::vec(v * self.radius * cos_phi, v * self.radius * sin_phi, 0.0)
+        });
+    }
+}
+
+impl Hit for Disk {
+    fn test(&self, ray: &Ray, t: Interval, _: &mut Pcg64Mcg) -> Option<HitRecord<'_>> {
+        shape::test(self, ray, t)
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.bounds
+    }
+
+    fn pdf_value(&self, origin: Point4, dir: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        shape::pdf_value(self, origin, dir)
+    }
+
+    fn random(&self, origin: Point4, rng: &mut Pcg64Mcg) -> Vec4 {
+        shape::random(self, origin, rng)
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_distr::Standard;
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::interval::Interval;
+use crate::material::{Emissive, Material};
+use crate::ray::Ray;
+use crate::texture::{ConstantTexture, TexturePointer};
+use crate::vec4::{Color, Point4, Vec4};
+
+use super::volume::boundary_segments;
+use super::{Hit, HitRecord};
+
+/// A participating medium with spatially varying density, sampled using
+/// delta tracking.
+///
+/// Density is read from a scalar texture sampled in world space. The sampled
+/// value is clamped to [0; 1] and scaled by `max_density`, which gives the
+/// majorant needed for delta tracking.
+#[derive(Debug)]
+pub struct HeterogeneousVolume {
+    boundary: Arc<dyn Hit>,
+    phase: Arc<dyn Material>,
+    density: TexturePointer<f64>,
+    max_density: f64,
+
+    absorption: f64,
+    scattering: f64,
+    emission: Emissive,
+}
+
+impl HeterogeneousVolume {
+    pub fn new(
+        boundary: Arc<dyn Hit>,
+        phase: Arc<dyn Material>,
+        density: TexturePointer<f64>,
+        max_density: f64,
+    ) -> Self {
+        HeterogeneousVolume {
+            boundary,
+            phase,
+            density,
+            max_density,
+            absorption: 0.0,
+            scattering: 1.0,
+            emission: Emissive::new(Arc::new(ConstantTexture::from_values(0.0, 0.0, 0.0))),
+        }
+    }
+
+    /// Set the absorption coefficient, relative to density.
+    pub fn set_absorption(&mut self, absorption: f64) {
+        self.absorption = absorption;
+    }
+
+    /// Set the scattering coefficient, relative to density.
+    pub fn set_scattering(&mut self, scattering: f64) {
+        self.scattering = scattering;
+    }
+
+    /// Set the radiance emitted by the medium where light is absorbed.
+    pub fn set_emission(&mut self, emission: TexturePointer<Color>) {
+        self.emission = Emissive::new(emission);
+    }
+
+    fn density_at(&self, p: &Point4) -> f64 {
+        self.density.sample((0.0, 0.0), p).clamp(0.0, 1.0)
+    }
+}
+
+impl Hit for HeterogeneousVolume {
+    fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
+        let majorant = (self.absorption + self.scattering) * self.max_density;
+        if majorant <= 0.0 {
+            return None;
+        }
+
+        let ray_len = ray.dir().length();
+        for (t_min, t_max) in boundary_segments(&self.boundary, ray, &t, rng) {
+            // Delta tracking: sample tentative collisions against the majorant,
+            // and accept them as real collisions proportionally to the local density
+            let mut t = t_min;
+            loop {
+                let r: f64 = rng.sample(Standard);
+                t -= f64::ln(1.0 - r) / (majorant * ray_len);
+                if t >= t_max {
+                    break;
+                }
+
+                let hit_pos = ray.at(t);
+                let density = self.density_at(&hit_pos) * self.max_density;
+                let sigma_a = self.absorption * density;
+                let sigma_s = self.scattering * density;
+
+                let r: f64 = rng.sample(Standard);
+                let material: &dyn Material = if r < sigma_a / majorant {
+                    &self.emission // Absorption, path ends with emitted radiance
+                } else if r < (sigma_a + sigma_s) / majorant {
+                    self.phase.as_ref() // Real scattering event
+                } else {
+                    continue; // Null collision
+                };
+
+                // Normal faces the incoming ray, so emission always counts as front-facing
+                return Some(HitRecord::new(
+                    ray,
+                    hit_pos,
+                    t,
+                    (0.0, 0.0), // Arbitrary, unused
+                    -ray.dir().to_unit(),
+                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+                    material,
+                ));
+            }
+        }
+
+        None
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.boundary.get_bounding_box()
+    }
+
+    fn pdf_value(&self, _: Point4, _: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _: Point4, _: &mut Pcg64Mcg) -> Vec4 {
+        Vec4::vec(1.0, 0.0, 0.0)
+    }
+}
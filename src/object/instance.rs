@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::{Hit, HitRecord, Transform};
+
+/// A transformed reference to a shared object, optionally with its own
+/// material.
+///
+/// Instances of the same object share its geometry and acceleration
+/// structure, so placing many copies of a mesh only costs a transform each.
+/// Putting instances in a BVH gives a two-level hierarchy, where the top
+/// level only contains instance bounds.
+#[derive(Debug)]
+pub struct Instance {
+    /// Replaces the material of every surface hit on the instance, if set.
+    pub material: Option<Arc<dyn Material>>,
+
+    transform: Transform,
+}
+
+impl Instance {
+    pub fn new(transform: Transform) -> Self {
+        Instance {
+            material: None,
+            transform,
+        }
+    }
+}
+
+impl Hit for Instance {
+    fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
+        let mut hit = self.transform.test(ray, t, rng)?;
+        if let Some(material) = &self.material {
+            hit.material = material.as_ref();
+        }
+
+        Some(hit)
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.transform.get_bounding_box()
+    }
+
+    fn pdf_value(&self, _: Point4, _: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _: Point4, _: &mut Pcg64Mcg) -> Vec4 {
+        Vec4::vec(1.0, 0.0, 0.0)
+    }
+}
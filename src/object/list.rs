@@ -15,12 +15,6 @@ use super::{Hit, HitRecord};
 pub struct ObjectList {
     objects: Vec<Arc<dyn Hit>>,
     bounds: AxisAlignedBoundingBox,
-
-    /// Disables the bounding box check before hit test.
-    ///
-    /// Needed as a workaround for volumes. Don't use it otherwise as it has a
-    /// big impact on performance.
-    pub disable_bounds_check: bool,
 }
 
 impl ObjectList {
@@ -28,7 +22,6 @@ impl ObjectList {
         ObjectList {
             objects: Vec::new(),
             bounds: [INFINITY, -INFINITY],
-            disable_bounds_check: false,
         }
     }
 
@@ -36,11 +29,7 @@ impl ObjectList {
         let object_bounds: Vec<_> = objects.iter().map(|obj| obj.get_bounding_box()).collect();
         let bounds = aabb::combine_bounds(&object_bounds);
 
-        ObjectList {
-            objects,
-            bounds,
-            disable_bounds_check: false,
-        }
+        ObjectList { objects, bounds }
     }
 
     pub fn clear(&mut self) {
@@ -56,7 +45,7 @@ impl ObjectList {
 
 impl Hit for ObjectList {
     fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
-        if !self.disable_bounds_check && !aabb::test_bounding_box(&self.bounds, ray, &t) {
+        if !aabb::test_bounding_box(&self.bounds, ray, &t) {
             return None;
         }
 
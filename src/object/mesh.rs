@@ -4,60 +4,121 @@ use crate::aabb::{self, AxisAlignedBoundingBox};
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec4::{Point4, Vec4};
+use crate::vec4::{Color, Point4, Vec4};
 
+use super::bvh::flat::FlatBvh;
+use super::bvh::sah::BuildItem;
 use super::{Hit, HitRecord};
 
+mod binary;
+mod displace;
 mod octree;
+pub mod subdivide;
 use octree::{OctreeNode, OctreeNodeData};
 use rand_pcg::Pcg64Mcg;
 
+/// Max number of triangles in a leaf of a mesh BVH.
+const MAX_LEAF_TRIANGLES: usize = 8;
+
+/// Acceleration structure used to find the triangles hit by a ray.
+#[derive(Debug)]
+enum MeshAccel {
+    /// BVH over the triangles, which are stored in leaf order.
+    Bvh(FlatBvh),
+    Octree(OctreeNode),
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Triangle {
     pub vert_indices: [usize; 3],
     pub normal_indices: [usize; 3],
     pub uv_indices: Option<[usize; 3]>,
+    /// Index of the triangle's material in the mesh's material table.
+    pub material_index: usize,
 }
 
 #[derive(Debug)]
 pub struct TriangleMesh {
-    pub material: Arc<dyn Material>,
     pub flat_shading: bool,
     pub hit_back_faces: bool,
 
     vertices: Vec<Point4>,
     vertex_uvs: Vec<Point4>,
     vertex_normals: Vec<Vec4>,
+    /// Colors by vertex index, empty if the mesh has no vertex colors.
+    vertex_colors: Vec<Color>,
     triangles: Vec<Triangle>,
+    materials: Vec<Arc<dyn Material>>,
 
     bounds: AxisAlignedBoundingBox,
-    octree: OctreeNode,
+    accel: MeshAccel,
 }
 
 impl TriangleMesh {
+    /// Build a mesh with a table of materials, which triangles refer to by
+    /// their `material_index`.
     pub fn new(
         vertices: Vec<Point4>,
         vertex_uvs: Vec<Point4>,
         vertex_normals: Vec<Vec4>,
         triangles: Vec<Triangle>,
-        material: Arc<dyn Material>,
+        materials: Vec<Arc<dyn Material>>,
     ) -> Self {
+        assert!(
+            triangles.iter().all(|t| t.material_index < materials.len()),
+            "Triangle material index out of range"
+        );
+
         let bounds = aabb::get_bounding_box(&vertices);
-        let octree = OctreeNode::new(&vertices, &triangles, None, bounds);
+
+        let mut items: Vec<_> = triangles
+            .into_iter()
+            .map(|triangle| {
+                let tri_verts = triangle.vert_indices.map(|idx| vertices[idx]);
+                BuildItem::new(aabb::get_bounding_box(&tri_verts), triangle)
+            })
+            .collect();
+
+        let accel = if items.is_empty() {
+            MeshAccel::Octree(OctreeNode::new(&vertices, &Vec::new(), None, bounds))
+        } else {
+            MeshAccel::Bvh(FlatBvh::build(&mut items, MAX_LEAF_TRIANGLES))
+        };
 
         TriangleMesh {
             vertices,
             vertex_uvs,
             vertex_normals,
-            triangles,
-            material,
+            vertex_colors: Vec::new(),
+            triangles: items.into_iter().map(|item| item.value).collect(),
+            materials,
             flat_shading: false,
             hit_back_faces: false,
             bounds,
-            octree,
+            accel,
         }
     }
 
+    /// Set a color for each vertex, interpolated across triangles and exposed
+    /// to textures through hits.
+    pub fn set_vertex_colors(&mut self, colors: Vec<Color>) {
+        assert!(
+            colors.is_empty() || colors.len() == self.vertices.len(),
+            "Expected one color per vertex"
+        );
+        self.vertex_colors = colors;
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Use an octree instead of a BVH to find the triangles hit by a ray.
+    pub fn use_octree(&mut self) {
+        let octree = OctreeNode::new(&self.vertices, &self.triangles, None, self.bounds);
+        self.accel = MeshAccel::Octree(octree);
+    }
+
     // Möller–Trumbore intersection
     fn test_tri(&self, triangle: &Triangle, ray: &Ray, t_int: Interval) -> Option<HitRecord> {
         let [v0, v1, v2] = [
@@ -149,7 +210,7 @@ impl TriangleMesh {
                 None => Vec4::vec(0.0, 0.0, 0.0),
             };
 
-            Some(HitRecord::new(
+            let mut hit = HitRecord::new(
                 ray,
                 hit_pos,
                 t,
@@ -157,8 +218,14 @@ impl TriangleMesh {
                 normal,
                 tangent,
                 bitangent,
-                Arc::as_ref(&self.material),
-            ))
+                Arc::as_ref(&self.materials[triangle.material_index]),
+            );
+            if !self.vertex_colors.is_empty() {
+                let [c0, c1, c2] = triangle.vert_indices.map(|idx| self.vertex_colors[idx]);
+                hit.vertex_color = Some(c0 * w + c1 * u + c2 * v);
+            }
+
+            Some(hit)
         }
     }
 
@@ -199,7 +266,24 @@ impl TriangleMesh {
 
 impl Hit for TriangleMesh {
     fn test(&self, ray: &Ray, t: Interval, _: &mut Pcg64Mcg) -> Option<HitRecord> {
-        self.test_octree_node(&self.octree, ray, t)
+        match &self.accel {
+            MeshAccel::Bvh(tree) => tree.test(ray, t, |range, interval| {
+                let mut closest_hit: Option<HitRecord> = None;
+                let mut closest_t = interval.max();
+
+                for triangle in &self.triangles[range] {
+                    if let Some(hit) =
+                        self.test_tri(triangle, ray, Interval(interval.min(), closest_t))
+                    {
+                        closest_t = hit.t;
+                        closest_hit = Some(hit);
+                    }
+                }
+
+                closest_hit
+            }),
+            MeshAccel::Octree(octree) => self.test_octree_node(octree, ray, t),
+        }
     }
 
     fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
@@ -214,3 +298,30 @@ impl Hit for TriangleMesh {
         Vec4::vec(1.0, 0.0, 0.0)
     }
 }
+
+/// Smooth normals for each vertex, averaging the normals of the triangles
+/// around it weighted by their area. Vertices outside the triangles get an
+/// arbitrary normal.
+pub(crate) fn vertex_normals<'a>(
+    vertices: &[Point4],
+    triangles: impl IntoIterator<Item = &'a Triangle>,
+) -> Vec<Vec4> {
+    let mut normals = vec![Vec4::vec(0.0, 0.0, 0.0); vertices.len()];
+    for triangle in triangles {
+        let [v0, v1, v2] = triangle.vert_indices.map(|idx| vertices[idx]);
+        let face_normal = Vec4::cross(&(v1 - v0), &(v2 - v0));
+        for idx in triangle.vert_indices {
+            normals[idx] += face_normal;
+        }
+    }
+
+    normals.into_iter().map(unit_or_up).collect()
+}
+
+fn unit_or_up(n: Vec4) -> Vec4 {
+    if n.length_squared() > 0.0 {
+        n.to_unit()
+    } else {
+        Vec4::vec(0.0, 1.0, 0.0)
+    }
+}
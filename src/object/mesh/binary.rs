@@ -0,0 +1,129 @@
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+
+use crate::aabb;
+use crate::binary::{self, ReadBinary, WriteBinary};
+use crate::material::Material;
+use crate::object::bvh::flat::FlatBvh;
+use crate::vec4::Vec4;
+
+use super::{MeshAccel, Triangle, TriangleMesh};
+
+/// Marks a triangle without UVs.
+const NO_UV: u32 = u32::MAX;
+
+fn write_vecs(w: &mut impl Write, vecs: &[Vec4]) -> io::Result<()> {
+    w.write_u32(vecs.len() as u32)?;
+    for v in vecs {
+        w.write_f64(v.x())?;
+        w.write_f64(v.y())?;
+        w.write_f64(v.z())?;
+    }
+
+    Ok(())
+}
+
+fn read_vecs(r: &mut impl Read, make: fn(f64, f64, f64) -> Vec4) -> io::Result<Vec<Vec4>> {
+    let count = r.read_u32()? as usize;
+
+    // Avoid trusting the count for the allocation size, the file may be truncated
+    let mut vecs = Vec::with_capacity(count.min(1 << 20));
+    for _ in 0..count {
+        vecs.push(make(r.read_f64()?, r.read_f64()?, r.read_f64()?));
+    }
+
+    Ok(vecs)
+}
+
+impl TriangleMesh {
+    /// Write the mesh geometry and its BVH in a binary format, to be read
+    /// back by `read_from`. Materials and flags aren't included.
+    pub fn write_to(&self, w: &mut impl Write) -> io::Result<()> {
+        let MeshAccel::Bvh(tree) = &self.accel else {
+            return Err(io::Error::other("Only meshes using a BVH can be written"));
+        };
+
+        write_vecs(w, &self.vertices)?;
+        write_vecs(w, &self.vertex_uvs)?;
+        write_vecs(w, &self.vertex_normals)?;
+        write_vecs(w, &self.vertex_colors)?;
+
+        w.write_u32(self.triangles.len() as u32)?;
+        for triangle in &self.triangles {
+            for idx in triangle.vert_indices.iter().chain(&triangle.normal_indices) {
+                w.write_u32(*idx as u32)?;
+            }
+            for idx in triangle.uv_indices.unwrap_or([NO_UV as usize; 3]) {
+                w.write_u32(idx as u32)?;
+            }
+            w.write_u32(triangle.material_index as u32)?;
+        }
+
+        tree.write_to(w)
+    }
+
+    /// Read a mesh written by `write_to`, without rebuilding its BVH.
+    /// `materials` is the material table triangles refer to.
+    pub fn read_from(r: &mut impl Read, materials: Vec<Arc<dyn Material>>) -> io::Result<Self> {
+        if materials.is_empty() {
+            return Err(binary::invalid_data("Mesh has no materials"));
+        }
+
+        let vertices = read_vecs(r, Vec4::point)?;
+        let vertex_uvs = read_vecs(r, Vec4::vec)?;
+        let vertex_normals = read_vecs(r, Vec4::vec)?;
+        let vertex_colors = read_vecs(r, Vec4::vec)?;
+        if !vertex_colors.is_empty() && vertex_colors.len() != vertices.len() {
+            return Err(binary::invalid_data("Expected one color per vertex"));
+        }
+
+        let triangle_count = r.read_u32()? as usize;
+        let mut triangles = Vec::with_capacity(triangle_count.min(1 << 20));
+        for _ in 0..triangle_count {
+            let mut indices = [0; 9];
+            for idx in &mut indices {
+                *idx = r.read_u32()?;
+            }
+
+            let in_range =
+                |indices: &[u32], len: usize| indices.iter().all(|&i| (i as usize) < len);
+            let uv_indices = match indices[6..9] {
+                [NO_UV, NO_UV, NO_UV] => None,
+                [a, b, c] if in_range(&[a, b, c], vertex_uvs.len()) => {
+                    Some([a as usize, b as usize, c as usize])
+                }
+                _ => return Err(binary::invalid_data("UV index out of range")),
+            };
+            if !in_range(&indices[0..3], vertices.len())
+                || !in_range(&indices[3..6], vertex_normals.len())
+            {
+                return Err(binary::invalid_data("Vertex index out of range"));
+            }
+
+            triangles.push(Triangle {
+                vert_indices: [indices[0], indices[1], indices[2]].map(|i| i as usize),
+                normal_indices: [indices[3], indices[4], indices[5]].map(|i| i as usize),
+                uv_indices,
+                material_index: r.read_index(materials.len() - 1)?,
+            });
+        }
+
+        if triangles.is_empty() {
+            return Err(binary::invalid_data("Mesh has no triangles"));
+        }
+        let tree = FlatBvh::read_from(r, triangles.len())?;
+
+        Ok(TriangleMesh {
+            bounds: aabb::get_bounding_box(&vertices),
+            vertices,
+            vertex_uvs,
+            vertex_normals,
+            vertex_colors,
+            triangles,
+            materials,
+            flat_shading: false,
+            hit_back_faces: false,
+            accel: MeshAccel::Bvh(tree),
+        })
+    }
+}
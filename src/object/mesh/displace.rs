@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::texture::Sampler;
+use crate::vec4::{Point4, Vec4};
+
+use super::{unit_or_up, vertex_normals, Triangle, TriangleMesh};
+
+/// A vertex of the grid a triangle is split into. Vertices on the corners and
+/// edges of the original triangles are shared with their neighbors, so the
+/// displaced mesh has no cracks.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum GridVertex {
+    Corner(usize),
+    /// A vertex on the edge between two original vertices, by index, with its
+    /// number of steps from the first one.
+    Edge(usize, usize, usize),
+    /// A vertex inside a triangle, by triangle index and grid coordinates.
+    Interior(usize, usize, usize),
+}
+
+impl TriangleMesh {
+    /// Build a displaced copy of the mesh. Each triangle is split `level`
+    /// times into 4 triangles, and vertices are moved along the mesh's
+    /// smoothed normals by `scale` times the height texture, sampled at
+    /// their UV coordinates. Normals are then recomputed from the displaced
+    /// surface, which is smooth shaded.
+    pub fn displace(&self, heights: &dyn Sampler<Output = f64>, scale: f64, level: u32) -> Self {
+        let steps = 1 << level;
+        let base_normals = self.smooth_normals();
+
+        let mut vertices = Vec::new();
+        let mut colors = Vec::new();
+        let mut uvs = Vec::new();
+        let mut triangles = Vec::with_capacity(self.triangles.len() * steps * steps);
+        let mut vertex_indices: HashMap<GridVertex, usize> = HashMap::new();
+
+        for (tri_idx, triangle) in self.triangles.iter().enumerate() {
+            let [a, b, c] = triangle.vert_indices;
+            let tri_uvs = triangle
+                .uv_indices
+                .map(|uv_i| uv_i.map(|idx| self.vertex_uvs[idx]));
+
+            // Grid points, from barycentric coordinates in steps towards b and c
+            let mut grid = Vec::with_capacity((steps + 1) * (steps + 2) / 2);
+            for i in 0..=steps {
+                for j in 0..=(steps - i) {
+                    let weights = [steps - i - j, i, j].map(|w| w as f64 / steps as f64);
+                    let lerp = |values: [Vec4; 3]| {
+                        values[0] * weights[0] + values[1] * weights[1] + values[2] * weights[2]
+                    };
+
+                    let uv = match tri_uvs {
+                        Some(tri_uvs) => lerp(tri_uvs),
+                        None => Vec4::vec(0.0, 0.0, 0.0),
+                    };
+                    uvs.push(uv);
+
+                    let key = grid_vertex(tri_idx, [a, b, c], [steps - i - j, i, j]);
+                    let vert_idx = *vertex_indices.entry(key).or_insert_with(|| {
+                        let p: Point4 = lerp([a, b, c].map(|idx| self.vertices[idx]));
+                        let normal = lerp([a, b, c].map(|idx| base_normals[idx])).to_unit();
+                        let height = heights.sample((uv.x(), uv.y()), &p);
+                        vertices.push(p + normal * (height * scale));
+
+                        if !self.vertex_colors.is_empty() {
+                            colors.push(lerp([a, b, c].map(|idx| self.vertex_colors[idx])));
+                        }
+
+                        vertices.len() - 1
+                    });
+                    grid.push([vert_idx, uvs.len() - 1]);
+                }
+            }
+
+            // Index of the grid point i steps towards b and j steps towards c
+            let at = |i: usize, j: usize| grid[i * (2 * steps + 3 - i) / 2 + j];
+            let mut add = |corners: [[usize; 2]; 3]| {
+                triangles.push(Triangle {
+                    vert_indices: corners.map(|c| c[0]),
+                    normal_indices: corners.map(|c| c[0]),
+                    uv_indices: triangle.uv_indices.map(|_| corners.map(|c| c[1])),
+                    material_index: triangle.material_index,
+                });
+            };
+            for i in 0..steps {
+                for j in 0..(steps - i) {
+                    add([at(i, j), at(i + 1, j), at(i, j + 1)]);
+                    if i + j + 1 < steps {
+                        add([at(i + 1, j), at(i + 1, j + 1), at(i, j + 1)]);
+                    }
+                }
+            }
+        }
+
+        let normals = vertex_normals(&vertices, &triangles);
+        let mut mesh = TriangleMesh::new(
+            vertices,
+            uvs,
+            normals,
+            triangles,
+            self.materials.iter().map(Arc::clone).collect(),
+        );
+        mesh.set_vertex_colors(colors);
+        mesh.hit_back_faces = self.hit_back_faces;
+
+        mesh
+    }
+
+    /// A normal for each vertex, averaging the normals the triangles around
+    /// it use at that vertex. Vertices on hard edges get a direction halfway
+    /// between the faces.
+    fn smooth_normals(&self) -> Vec<Vec4> {
+        if self.flat_shading {
+            return vertex_normals(&self.vertices, &self.triangles);
+        }
+
+        let mut normals = vec![Vec4::vec(0.0, 0.0, 0.0); self.vertices.len()];
+        for triangle in &self.triangles {
+            for (vert_idx, normal_idx) in triangle.vert_indices.iter().zip(triangle.normal_indices)
+            {
+                normals[*vert_idx] += self.vertex_normals[normal_idx].to_unit();
+            }
+        }
+
+        normals.into_iter().map(unit_or_up).collect()
+    }
+}
+
+fn grid_vertex(tri_idx: usize, verts: [usize; 3], steps: [usize; 3]) -> GridVertex {
+    let nonzero: Vec<usize> = (0..3).filter(|&k| steps[k] > 0).collect();
+
+    match nonzero[..] {
+        [k] => GridVertex::Corner(verts[k]),
+        [k1, k2] => {
+            // Order the edge's vertices so both triangles sharing it agree
+            let (first, second) = if verts[k1] < verts[k2] {
+                (k1, k2)
+            } else {
+                (k2, k1)
+            };
+            GridVertex::Edge(verts[first], verts[second], steps[second])
+        }
+        _ => GridVertex::Interior(tri_idx, steps[1], steps[2]),
+    }
+}
@@ -0,0 +1,327 @@
+//! Subdivision surfaces, smoothing a mesh by repeatedly splitting its faces:
+//! Loop subdivision for triangle meshes, and Catmull-Clark subdivision for
+//! meshes with other polygons.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::vec4::{Color, Point4, Vec4};
+
+use super::{vertex_normals, Triangle, TriangleMesh};
+
+/// A polygon of a control mesh.
+#[derive(Clone, Debug)]
+pub struct Face {
+    pub vert_indices: Vec<usize>,
+    pub uv_indices: Option<Vec<usize>>,
+    pub material_index: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubdivisionScheme {
+    /// Splits each triangle into 4 triangles. Only works on triangle meshes.
+    Loop,
+    /// Splits each polygon into quads, one per corner.
+    CatmullClark,
+}
+
+/// A polygon mesh to subdivide. Positions are smoothed, while UVs and vertex
+/// colors are interpolated linearly.
+#[derive(Debug)]
+pub struct ControlMesh {
+    pub vertices: Vec<Point4>,
+    pub uvs: Vec<Vec4>,
+    /// Colors by vertex index, empty if the mesh has no vertex colors.
+    pub colors: Vec<Color>,
+    pub faces: Vec<Face>,
+}
+
+/// Edges by sorted vertex indices, in the order they're first found, with the
+/// faces on each side.
+struct Edges {
+    keys: Vec<(usize, usize)>,
+    faces: HashMap<(usize, usize), Vec<usize>>,
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+impl ControlMesh {
+    /// Loop subdivision for triangle meshes, Catmull-Clark otherwise.
+    pub fn default_scheme(&self) -> SubdivisionScheme {
+        if self.faces.iter().all(|face| face.vert_indices.len() == 3) {
+            SubdivisionScheme::Loop
+        } else {
+            SubdivisionScheme::CatmullClark
+        }
+    }
+
+    /// Subdivide the mesh `levels` times with its default scheme.
+    pub fn subdivide(mut self, levels: u32) -> Self {
+        // Catmull-Clark only outputs quads, so the scheme doesn't change
+        let scheme = self.default_scheme();
+        for _ in 0..levels {
+            self = self.subdivide_once(scheme);
+        }
+
+        self
+    }
+
+    fn subdivide_once(&self, scheme: SubdivisionScheme) -> Self {
+        let mut edges = Edges {
+            keys: Vec::new(),
+            faces: HashMap::new(),
+        };
+        for (face_idx, face) in self.faces.iter().enumerate() {
+            let verts = &face.vert_indices;
+            for i in 0..verts.len() {
+                let key = edge_key(verts[i], verts[(i + 1) % verts.len()]);
+                let faces = edges.faces.entry(key).or_insert_with(|| {
+                    edges.keys.push(key);
+                    Vec::new()
+                });
+                faces.push(face_idx);
+            }
+        }
+
+        let face_points: Vec<Point4> = self
+            .faces
+            .iter()
+            .map(|face| average(face.vert_indices.iter().map(|&idx| self.vertices[idx])))
+            .collect();
+
+        // Original vertices keep their indices, followed by edge points, then
+        // face points for Catmull-Clark
+        let mut vertices = self.vertex_points(&edges, &face_points, scheme);
+        let mut colors = self.colors.clone();
+
+        let mut edge_points = HashMap::with_capacity(edges.keys.len());
+        for &(a, b) in &edges.keys {
+            let faces = &edges.faces[&(a, b)];
+            let midpoint = (self.vertices[a] + self.vertices[b]) * 0.5;
+            let p = if faces.len() != 2 {
+                // Boundaries and non-manifold edges are kept straight
+                midpoint
+            } else if scheme == SubdivisionScheme::Loop {
+                let opposite = faces.iter().map(|&face_idx| {
+                    let face = &self.faces[face_idx].vert_indices;
+                    let idx = face.iter().find(|&&idx| idx != a && idx != b);
+                    self.vertices[*idx.unwrap_or(&a)]
+                });
+                midpoint * 0.75 + average(opposite) * 0.25
+            } else {
+                (midpoint + (face_points[faces[0]] + face_points[faces[1]]) * 0.5) * 0.5
+            };
+
+            edge_points.insert((a, b), vertices.len());
+            vertices.push(p);
+            if !self.colors.is_empty() {
+                colors.push((self.colors[a] + self.colors[b]) * 0.5);
+            }
+        }
+
+        let mut uvs = self.uvs.clone();
+        let mut faces = Vec::new();
+
+        for (face_idx, face) in self.faces.iter().enumerate() {
+            let verts = &face.vert_indices;
+            let n = verts.len();
+            let edge_verts: Vec<usize> = (0..n)
+                .map(|i| edge_points[&edge_key(verts[i], verts[(i + 1) % n])])
+                .collect();
+
+            // UVs are split along with the face, so faces keep their own UVs
+            // across seams
+            let face_uvs = face.uv_indices.as_ref().map(|uv_indices| {
+                let corners: Vec<Vec4> = uv_indices.iter().map(|&idx| self.uvs[idx]).collect();
+                let edge_start = uvs.len();
+                for i in 0..n {
+                    uvs.push((corners[i] + corners[(i + 1) % n]) * 0.5);
+                }
+                if scheme == SubdivisionScheme::CatmullClark {
+                    uvs.push(average(corners.iter().copied()));
+                }
+
+                (uv_indices, edge_start)
+            });
+            let uv_edge = |i: usize| face_uvs.map(|(_, start)| start + i % n);
+            let uv_corner = |i: usize| face_uvs.map(|(corners, _)| corners[i % n]);
+
+            let mut add_face = |corners: Vec<(usize, Option<usize>)>| {
+                faces.push(Face {
+                    vert_indices: corners.iter().map(|c| c.0).collect(),
+                    uv_indices: face_uvs
+                        .map(|_| corners.iter().map(|c| c.1.unwrap_or(0)).collect()),
+                    material_index: face.material_index,
+                });
+            };
+
+            match scheme {
+                SubdivisionScheme::Loop => {
+                    // A triangle at each corner, and one in the middle
+                    for i in 0..3 {
+                        add_face(vec![
+                            (verts[i], uv_corner(i)),
+                            (edge_verts[i], uv_edge(i)),
+                            (edge_verts[(i + 2) % 3], uv_edge(i + 2)),
+                        ]);
+                    }
+                    add_face((0..3).map(|i| (edge_verts[i], uv_edge(i))).collect());
+                }
+                SubdivisionScheme::CatmullClark => {
+                    let center = vertices.len();
+                    vertices.push(face_points[face_idx]);
+                    if !self.colors.is_empty() {
+                        colors.push(average(verts.iter().map(|&idx| self.colors[idx])));
+                    }
+                    let center_uv = face_uvs.map(|(_, start)| start + n);
+
+                    // A quad at each corner
+                    for i in 0..n {
+                        add_face(vec![
+                            (verts[i], uv_corner(i)),
+                            (edge_verts[i], uv_edge(i)),
+                            (center, center_uv),
+                            (edge_verts[(i + n - 1) % n], uv_edge(i + n - 1)),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        ControlMesh {
+            vertices,
+            uvs,
+            colors,
+            faces,
+        }
+    }
+
+    /// New positions of the original vertices.
+    fn vertex_points(
+        &self,
+        edges: &Edges,
+        face_points: &[Point4],
+        scheme: SubdivisionScheme,
+    ) -> Vec<Point4> {
+        let mut neighbors = vec![Vec::new(); self.vertices.len()];
+        let mut boundary_neighbors = vec![Vec::new(); self.vertices.len()];
+        for &(a, b) in &edges.keys {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+            if edges.faces[&(a, b)].len() != 2 {
+                boundary_neighbors[a].push(b);
+                boundary_neighbors[b].push(a);
+            }
+        }
+
+        let mut vertex_faces = vec![Vec::new(); self.vertices.len()];
+        if scheme == SubdivisionScheme::CatmullClark {
+            for (face_idx, face) in self.faces.iter().enumerate() {
+                for &idx in &face.vert_indices {
+                    vertex_faces[idx].push(face_idx);
+                }
+            }
+        }
+
+        (0..self.vertices.len())
+            .map(|idx| {
+                let p = self.vertices[idx];
+                let ring = &neighbors[idx];
+                let valence = ring.len() as f64;
+
+                match boundary_neighbors[idx][..] {
+                    // Interior vertex
+                    [] if ring.is_empty() => p,
+                    [] => match scheme {
+                        SubdivisionScheme::Loop => {
+                            let beta = if ring.len() == 3 {
+                                3.0 / 16.0
+                            } else {
+                                3.0 / (8.0 * valence)
+                            };
+                            let ring_sum = ring
+                                .iter()
+                                .fold(Vec4::vec(0.0, 0.0, 0.0), |sum, &n| sum + self.vertices[n]);
+
+                            p * (1.0 - valence * beta) + ring_sum * beta
+                        }
+                        SubdivisionScheme::CatmullClark => {
+                            let faces = vertex_faces[idx].iter().map(|&f| face_points[f]);
+                            let edge_mids = ring.iter().map(|&n| (p + self.vertices[n]) * 0.5);
+
+                            (average(faces) + average(edge_mids) * 2.0 + p * (valence - 3.0))
+                                / valence
+                        }
+                    },
+                    // Boundaries follow a cubic B-spline with both schemes
+                    [b0, b1] => p * 0.75 + (self.vertices[b0] + self.vertices[b1]) * 0.125,
+                    // Corners and non-manifold vertices stay in place
+                    _ => p,
+                }
+            })
+            .collect()
+    }
+
+    /// Build a triangle mesh with smooth normals from the control mesh.
+    /// Polygons are split into a fan of triangles.
+    pub fn into_mesh(self, materials: Vec<Arc<dyn Material>>) -> TriangleMesh {
+        let mut triangles = Vec::new();
+        for face in &self.faces {
+            let verts = &face.vert_indices;
+            for i in 2..verts.len() {
+                let corners = [0, i - 1, i];
+                triangles.push(Triangle {
+                    vert_indices: corners.map(|c| verts[c]),
+                    normal_indices: corners.map(|c| verts[c]),
+                    uv_indices: face.uv_indices.as_ref().map(|uvs| corners.map(|c| uvs[c])),
+                    material_index: face.material_index,
+                });
+            }
+        }
+
+        let normals = vertex_normals(&self.vertices, &triangles);
+        let mut mesh = TriangleMesh::new(self.vertices, self.uvs, normals, triangles, materials);
+        mesh.set_vertex_colors(self.colors);
+
+        mesh
+    }
+}
+
+impl TriangleMesh {
+    /// Build a subdivided copy of the mesh, with Loop subdivision applied
+    /// `levels` times. Normals are recomputed from the subdivided surface.
+    pub fn subdivide(&self, levels: u32) -> Self {
+        let control_mesh = ControlMesh {
+            vertices: self.vertices.clone(),
+            uvs: self.vertex_uvs.clone(),
+            colors: self.vertex_colors.clone(),
+            faces: self
+                .triangles
+                .iter()
+                .map(|triangle| Face {
+                    vert_indices: triangle.vert_indices.to_vec(),
+                    uv_indices: triangle.uv_indices.map(|uvs| uvs.to_vec()),
+                    material_index: triangle.material_index,
+                })
+                .collect(),
+        };
+
+        let mut mesh = control_mesh
+            .subdivide(levels)
+            .into_mesh(self.materials.iter().map(Arc::clone).collect());
+        mesh.hit_back_faces = self.hit_back_faces;
+
+        mesh
+    }
+}
+
+fn average(points: impl Iterator<Item = Vec4>) -> Vec4 {
+    let (sum, count) = points.fold((Vec4::vec(0.0, 0.0, 0.0), 0), |(sum, count), p| {
+        (sum + p, count + 1)
+    });
+
+    sum / count.max(1) as f64
+}
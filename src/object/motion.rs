@@ -0,0 +1,188 @@
+//! Objects moving over time, for motion blur.
+
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::{self, AxisAlignedBoundingBox};
+use crate::interval::Interval;
+use crate::mat4::Mat4;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::{Hit, HitRecord};
+
+/// Largest rotation between two poses sampled for the bounds of a motion.
+const MAX_BOUNDS_ROTATION_STEP: f64 = PI / 36.0;
+
+/// The pose of a moving object at a given time: scaled, then rotated around
+/// the X, Y and Z axes in that order, then translated.
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe {
+    pub time: f64,
+    pub translation: Vec4,
+    /// Rotation angles around the X, Y and Z axes, in radians.
+    pub rotation: Vec4,
+    pub scale: Vec4,
+}
+
+impl Keyframe {
+    /// A keyframe leaving the object in place.
+    pub fn new(time: f64) -> Self {
+        Keyframe {
+            time,
+            translation: Vec4::vec(0.0, 0.0, 0.0),
+            rotation: Vec4::vec(0.0, 0.0, 0.0),
+            scale: Vec4::vec(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Angles are interpolated separately, so a keyframe can turn the
+    /// object more than half a turn from the previous one.
+    fn lerp(&self, other: &Keyframe, s: f64) -> Keyframe {
+        Keyframe {
+            time: self.time + (other.time - self.time) * s,
+            translation: self.translation.lerp(other.translation, s),
+            rotation: self.rotation.lerp(other.rotation, s),
+            scale: self.scale.lerp(other.scale, s),
+        }
+    }
+
+    /// Object to world matrix, and its inverse.
+    fn matrices(&self) -> (Mat4, Mat4) {
+        let (tx, ty, tz) = self.translation.xyz();
+        let (rx, ry, rz) = self.rotation.xyz();
+        let (sx, sy, sz) = self.scale.xyz();
+
+        let transform = Mat4::translation(tx, ty, tz)
+            * Mat4::rotate_z(rz)
+            * Mat4::rotate_y(ry)
+            * Mat4::rotate_x(rx)
+            * Mat4::scale(sx, sy, sz);
+        let inv_transform = Mat4::scale(1.0 / sx, 1.0 / sy, 1.0 / sz)
+            * Mat4::rotate_x(-rx)
+            * Mat4::rotate_y(-ry)
+            * Mat4::rotate_z(-rz)
+            * Mat4::translation(-tx, -ty, -tz);
+
+        (transform, inv_transform)
+    }
+}
+
+/// An object moving along a list of keyframes. Its pose is interpolated
+/// linearly between keyframes at the time of each ray, and held before the
+/// first keyframe and after the last one.
+///
+/// Like `Transform`, assumes no negative scaling.
+#[derive(Debug)]
+pub struct Motion {
+    object: Arc<dyn Hit>,
+    keyframes: Vec<Keyframe>,
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Motion {
+    /// Returns `None` without keyframes.
+    pub fn new(object: Arc<dyn Hit>, mut keyframes: Vec<Keyframe>) -> Option<Self> {
+        if keyframes.is_empty() {
+            return None;
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        let mut motion = Motion {
+            object,
+            keyframes,
+            bounds: [Vec4::point(0.0, 0.0, 0.0); 2],
+        };
+        motion.update_bounds();
+
+        Some(motion)
+    }
+
+    fn pose_at(&self, time: f64) -> Keyframe {
+        let next = self.keyframes.partition_point(|key| key.time <= time);
+        if next == 0 {
+            return self.keyframes[0];
+        }
+        if next == self.keyframes.len() {
+            return self.keyframes[next - 1];
+        }
+
+        let (prev, next) = (&self.keyframes[next - 1], &self.keyframes[next]);
+        prev.lerp(next, (time - prev.time) / (next.time - prev.time))
+    }
+
+    /// Bound the object over its whole motion, from poses sampled between
+    /// keyframes. Without rotation, corners move in straight lines, so the
+    /// keyframes are enough. Rotating corners move along arcs, so the bounds
+    /// are padded by how far an arc can stray from the sampled points.
+    fn update_bounds(&mut self) {
+        let corners = box_corners(&self.object.get_bounding_box());
+        let radius = self
+            .keyframes
+            .iter()
+            .flat_map(|key| corners.iter().map(|&c| (c * key.scale).length()))
+            .fold(0.0, f64::max);
+
+        let mut poses = vec![self.keyframes[0]];
+        let mut padding: f64 = 0.0;
+        for pair in self.keyframes.windows(2) {
+            let delta = pair[1].rotation - pair[0].rotation;
+            let angle = delta.x().abs() + delta.y().abs() + delta.z().abs();
+            let steps = (angle / MAX_BOUNDS_ROTATION_STEP).ceil().max(1.0);
+
+            for step in 1..=(steps as usize) {
+                poses.push(pair[0].lerp(&pair[1], step as f64 / steps));
+            }
+            padding = padding.max(radius * (1.0 - f64::cos(angle / steps / 2.0)));
+        }
+
+        let mut points = Vec::with_capacity(poses.len() * corners.len());
+        for pose in poses {
+            let (transform, _) = pose.matrices();
+            points.extend(corners.iter().map(|&c| transform * c));
+        }
+
+        let [min, max] = aabb::get_bounding_box(&points);
+        let padding = Vec4::vec(padding, padding, padding);
+        self.bounds = [min - padding, max + padding];
+    }
+}
+
+fn box_corners([min, max]: &AxisAlignedBoundingBox) -> [Point4; 8] {
+    [0, 1, 2, 3, 4, 5, 6, 7].map(|i| {
+        Vec4::point(
+            if i & 1 == 0 { min.x() } else { max.x() },
+            if i & 2 == 0 { min.y() } else { max.y() },
+            if i & 4 == 0 { min.z() } else { max.z() },
+        )
+    })
+}
+
+impl Hit for Motion {
+    fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord<'_>> {
+        let (transform, inv_transform) = self.pose_at(ray.time()).matrices();
+
+        // Transform ray to object space at the ray's time
+        let ray_obj = ray.spawn(inv_transform * ray.origin(), inv_transform * ray.dir());
+
+        let mut hit = self.object.test(&ray_obj, t, rng)?;
+        hit.hit_pos = transform * hit.hit_pos;
+        hit.normal = (transform * hit.normal).to_unit();
+
+        Some(hit)
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.bounds
+    }
+
+    fn pdf_value(&self, _: Point4, _: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        0.0
+    }
+
+    fn random(&self, _: Point4, _: &mut Pcg64Mcg) -> Vec4 {
+        Vec4::vec(1.0, 0.0, 0.0)
+    }
+}
@@ -5,44 +5,35 @@ use crate::vec4::{Point4, Vec4};
 
 use super::{ObjectList, Plane};
 
+/// Build an axis-aligned box out of six planes.
+///
+/// Sides render their backfaces, so the box can be hit from the inside (as
+/// needed by volumes and dielectrics).
 pub fn make_box(center: Point4, size: Vec4, material: Arc<dyn Material>) -> ObjectList {
     let mut sides = ObjectList::new();
 
+    for mut side in box_sides(center, size, material) {
+        side.render_backface = true;
+        sides.add(Arc::new(side));
+    }
+
+    sides
+}
+
+/// The six sides of an axis-aligned box, facing outwards. As a whole they can
+/// be tessellated like any other primitive.
+pub fn box_sides(center: Point4, size: Vec4, material: Arc<dyn Material>) -> [Plane; 6] {
     let half_size = size / 2.0;
     let dx = Vec4::vec(half_size.x(), 0.0, 0.0);
     let dy = Vec4::vec(0.0, half_size.y(), 0.0);
     let dz = Vec4::vec(0.0, 0.0, half_size.z());
 
-    sides.add(Arc::new(Plane::new(
-        center + dy,
-        (dx, -dz),
-        Arc::clone(&material),
-    )));
-    sides.add(Arc::new(Plane::new(
-        center - dy,
-        (-dx, -dz),
-        Arc::clone(&material),
-    )));
-    sides.add(Arc::new(Plane::new(
-        center - dx,
-        (dz, dy),
-        Arc::clone(&material),
-    )));
-    sides.add(Arc::new(Plane::new(
-        center + dx,
-        (-dz, dy),
-        Arc::clone(&material),
-    )));
-    sides.add(Arc::new(Plane::new(
-        center - dz,
-        (-dx, dy),
-        Arc::clone(&material),
-    )));
-    sides.add(Arc::new(Plane::new(
-        center + dz,
-        (dx, dy),
-        Arc::clone(&material),
-    )));
-
-    sides
+    [
+        Plane::new(center + dy, (dx, -dz), Arc::clone(&material)),
+        Plane::new(center - dy, (-dx, -dz), Arc::clone(&material)),
+        Plane::new(center - dx, (dz, dy), Arc::clone(&material)),
+        Plane::new(center + dx, (-dz, dy), Arc::clone(&material)),
+        Plane::new(center - dz, (-dx, dy), Arc::clone(&material)),
+        Plane::new(center + dz, (dx, dy), material),
+    ]
 }
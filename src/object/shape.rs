@@ -0,0 +1,198 @@
+//! Shared code for analytic primitives defined in a local frame around the Z
+//! axis (disks, cylinders, cones and tori), which are intersected in local
+//! space and sampled uniformly over their area for light sampling.
+
+use std::f64::consts::PI;
+
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::{self, AxisAlignedBoundingBox};
+use crate::interval::Interval;
+use crate::mat4::Mat4;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::utils::onb_from_vec;
+use crate::vec4::{Point4, Vec4};
+
+use super::tessellate::MeshBuilder;
+use super::HitRecord;
+
+/// Min distance to a surface for light sampling, so a surface doesn't sample
+/// itself.
+const MIN_LIGHT_DIST: f64 = 0.001;
+
+/// An orthonormal frame placing a primitive in the world, with the
+/// primitive's axis as the local Z axis.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Frame {
+    origin: Point4,
+    to_world: Mat4,
+    to_local: Mat4,
+}
+
+impl Frame {
+    pub fn new(origin: Point4, axis: Vec4) -> Self {
+        let to_world = onb_from_vec(axis.to_unit());
+
+        Frame {
+            origin,
+            to_world,
+            // The basis is orthonormal, so its inverse is its transpose
+            to_local: to_world.transposed(),
+        }
+    }
+
+    /// Transform a ray into local space, as an origin and a direction. Both
+    /// are vectors from the frame's origin.
+    pub fn ray_to_local(&self, ray: &Ray) -> (Vec4, Vec4) {
+        let origin = self.to_local * (ray.origin() - self.origin);
+        (origin, self.to_local * ray.dir())
+    }
+
+    pub fn point_to_world(&self, p: Vec4) -> Point4 {
+        self.origin + self.to_world * p
+    }
+
+    pub fn vec_to_world(&self, v: Vec4) -> Vec4 {
+        self.to_world * v
+    }
+
+    /// World bounds of a local axis-aligned box.
+    pub fn bounds(&self, min: Vec4, max: Vec4) -> AxisAlignedBoundingBox {
+        let corners: Vec<Point4> = (0..8)
+            .map(|i| {
+                let corner = Vec4::vec(
+                    if i & 1 == 0 { min.x() } else { max.x() },
+                    if i & 2 == 0 { min.y() } else { max.y() },
+                    if i & 4 == 0 { min.z() } else { max.z() },
+                );
+                self.point_to_world(corner)
+            })
+            .collect();
+
+        aabb::get_bounding_box(&corners)
+    }
+}
+
+/// Local geometry of a surface point.
+pub(super) struct SurfacePoint {
+    /// Outward unit normal.
+    pub normal: Vec4,
+    pub uv: (f64, f64),
+    /// Unit tangent, along the direction of increasing U.
+    pub tangent: Vec4,
+}
+
+/// A primitive defined in a local frame.
+pub(super) trait LocalShape {
+    /// Part of the surface that was hit, such as the side or a cap.
+    type Part: Copy;
+
+    fn frame(&self) -> &Frame;
+
+    fn material(&self) -> &dyn Material;
+
+    /// Call `found` with the distance along the ray and the part of every
+    /// intersection of a local ray with the surface, in any order.
+    fn intersect(&self, origin: Vec4, dir: Vec4, found: &mut dyn FnMut(f64, Self::Part));
+
+    /// Geometry of a local point on a part of the surface.
+    fn surface_at(&self, p: Vec4, part: Self::Part) -> SurfacePoint;
+
+    fn area(&self) -> f64;
+
+    /// Pick a local point uniformly over the surface's area.
+    fn sample_point(&self, rng: &mut Pcg64Mcg) -> Vec4;
+}
+
+/// Find the closest hit of a ray with a shape.
+pub(super) fn test<'a, S: LocalShape>(
+    shape: &'a S,
+    ray: &Ray,
+    t: Interval,
+) -> Option<HitRecord<'a>> {
+    let (origin, dir) = shape.frame().ray_to_local(ray);
+
+    let mut closest: Option<(f64, S::Part)> = None;
+    shape.intersect(origin, dir, &mut |root, part| {
+        if t.min() < root && root < t.max() && closest.is_none_or(|(closest_t, _)| root < closest_t)
+        {
+            closest = Some((root, part));
+        }
+    });
+    let (root, part) = closest?;
+
+    let surface = shape.surface_at(origin + dir * root, part);
+    let frame = shape.frame();
+    let normal = frame.vec_to_world(surface.normal);
+    let tangent = frame.vec_to_world(surface.tangent);
+
+    Some(HitRecord::new(
+        ray,
+        ray.at(root),
+        root,
+        surface.uv,
+        normal,
+        tangent,
+        normal.cross(&tangent),
+        shape.material(),
+    ))
+}
+
+/// PDF (over solid angle) of sampling a direction towards a shape by picking
+/// points uniformly over its area. A direction can reach several points of
+/// the surface, which all add to the PDF.
+pub(super) fn pdf_value<S: LocalShape>(shape: &S, origin: Point4, dir: Vec4) -> f64 {
+    let (local_origin, local_dir) = shape.frame().ray_to_local(&Ray::new(origin, dir));
+    let dir_length_squared = dir.length_squared();
+    let area = shape.area();
+
+    let mut pdf = 0.0;
+    shape.intersect(local_origin, local_dir, &mut |root, part| {
+        if root <= MIN_LIGHT_DIST {
+            return;
+        }
+
+        let normal = shape
+            .surface_at(local_origin + local_dir * root, part)
+            .normal;
+        let cosine = local_dir.dot(&normal).abs() / dir_length_squared.sqrt();
+        if cosine > 0.0 {
+            pdf += root * root * dir_length_squared / (cosine * area);
+        }
+    });
+
+    pdf
+}
+
+/// Direction from `origin` towards a point picked uniformly over a shape.
+pub(super) fn random<S: LocalShape>(shape: &S, origin: Point4, rng: &mut Pcg64Mcg) -> Vec4 {
+    shape.frame().point_to_world(shape.sample_point(rng)) - origin
+}
+
+/// Add a patch of a shape to a mesh. `local_point` maps UV coordinates in
+/// [0; 1] to a local point on a part of the surface.
+pub(super) fn add_patch<S: LocalShape>(
+    shape: &S,
+    builder: &mut MeshBuilder,
+    size: (usize, usize),
+    part: S::Part,
+    local_point: impl Fn(f64, f64) -> Vec4,
+) {
+    let frame = shape.frame();
+    builder.add_patch(size, |u, v| {
+        let p = local_point(u, v);
+        let normal = shape.surface_at(p, part).normal;
+        (frame.point_to_world(p), frame.vec_to_world(normal))
+    });
+}
+
+/// Angle of a local point around the Z axis, in [0; 2π).
+pub(super) fn azimuth(p: Vec4) -> f64 {
+    f64::atan2(p.y(), p.x()).rem_euclid(2.0 * PI)
+}
+
+/// Unit tangent around the Z axis at an azimuth.
+pub(super) fn azimuth_tangent(phi: f64) -> Vec4 {
+    Vec4::vec(-phi.sin(), phi.cos(), 0.0)
+}
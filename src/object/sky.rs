@@ -36,8 +36,10 @@ impl Hit for Sky {
 
         let unit_dir = ray.dir().to_unit();
         let normal = -unit_dir;
-        let u = f64::atan2(unit_dir.x(), unit_dir.z()) / (2.0 * PI) + 0.5;
-        let v = unit_dir.dot(&Vec4::vec(0.0, 1.0, 0.0)) / 2.0 + 0.5;
+        // Equirectangular mapping, with the top of the image up and its center
+        // towards +Z, as rendered by a panorama camera looking towards +Z
+        let u = f64::atan2(-unit_dir.x(), unit_dir.z()) / (2.0 * PI) + 0.5;
+        let v = f64::acos(unit_dir.y().clamp(-1.0, 1.0)) / PI;
 
         Some(HitRecord::new(
             ray,
@@ -5,13 +5,15 @@ use rand::Rng;
 use rand_distr::Standard;
 use rand_pcg::Pcg64Mcg;
 
-use crate::aabb::AxisAlignedBoundingBox;
+use crate::aabb::{self, AxisAlignedBoundingBox};
 use crate::interval::Interval;
 use crate::material::Material;
+use crate::pdf::Distribution2D;
 use crate::ray::Ray;
 use crate::utils::onb_from_vec;
 use crate::vec4::{Point4, Vec4};
 
+use super::tessellate::{MeshBuilder, Tessellate};
 use super::{Hit, HitRecord};
 
 #[derive(Debug)]
@@ -19,8 +21,11 @@ pub struct Sphere {
     pub material: Arc<dyn Material>,
 
     center: Point4,
+    /// Offset of the center at time 1, for moving spheres.
+    motion: Vec4,
     radius: f64,
     bounds: AxisAlignedBoundingBox,
+    emission_distribution: Option<Distribution2D>,
 }
 
 impl Sphere {
@@ -30,15 +35,100 @@ impl Sphere {
 
         Sphere {
             center,
+            motion: Vec4::vec(0.0, 0.0, 0.0),
             radius,
             bounds,
             material,
+            emission_distribution: None,
         }
     }
 
+    /// Move the sphere over time, from its center at time 0 to `end_center`
+    /// at time 1. It stays in place before and after that.
+    ///
+    /// Light sampling and tessellation only use the sphere's position at
+    /// time 0.
+    pub fn set_motion(&mut self, end_center: Point4) {
+        self.motion = end_center - self.center;
+
+        let radius_vec = Vec4::vec(self.radius, self.radius, self.radius);
+        self.bounds = aabb::combine_bounds(&[
+            [self.center - radius_vec, self.center + radius_vec],
+            [end_center - radius_vec, end_center + radius_vec],
+        ]);
+    }
+
+    fn center_at(&self, time: f64) -> Point4 {
+        self.center + self.motion * time.clamp(0.0, 1.0)
+    }
+
+    /// Sample light towards the sphere proportionally to the brightness of
+    /// its emission texture, instead of uniformly over the visible cap.
+    /// Greatly reduces noise for lights with mostly dark emission textures.
+    ///
+    /// The texture is sampled on a `2 * resolution` by `resolution` grid in UV
+    /// space. Has no effect if the material isn't emissive.
+    pub fn importance_sample_emission(&mut self, resolution: usize) {
+        if let Some(emission_map) = self.material.emission_map() {
+            let distribution = Distribution2D::from_fn(2 * resolution, resolution, |uv| {
+                let normal = uv_to_normal(uv);
+                let p = self.center + normal * self.radius;
+
+                // Weighted by the area covered by each UV cell
+                let sin_theta = f64::sin(uv.1 * PI);
+                emission_map.sample(uv, &p).luminance() * sin_theta
+            });
+            self.emission_distribution = Some(distribution);
+        }
+    }
+
+    /// PDF (over solid angle) of sampling a direction towards the sphere with
+    /// the emission distribution.
+    ///
+    /// Points are sampled over the whole surface, so a direction may come from
+    /// a point on the near side or on the far side of the sphere: both
+    /// intersections add to the PDF.
+    fn emission_pdf_value(&self, distribution: &Distribution2D, origin: Point4, dir: Vec4) -> f64 {
+        let center_diff = origin - self.center;
+
+        let a = dir.length_squared();
+        let half_b = dir.dot(&center_diff);
+        let c = center_diff.length_squared() - self.radius * self.radius;
+
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return 0.0;
+        }
+        let d_sqrt = discriminant.sqrt();
+
+        let mut pdf = 0.0;
+        for root in [(-half_b - d_sqrt) / a, (-half_b + d_sqrt) / a] {
+            if root <= 0.001 {
+                continue;
+            }
+
+            let normal = (origin + dir * root - self.center) / self.radius;
+            let (uv, sin_theta) = normal_to_uv(normal);
+            if sin_theta <= 0.0 {
+                continue;
+            }
+
+            // Convert from PDF over UV space to PDF over area, then to solid angle
+            let area_pdf =
+                distribution.pdf(uv) / (2.0 * PI * PI * self.radius * self.radius * sin_theta);
+            let dist_squared = root * root * a;
+            let cosine = (dir.dot(&normal) / a.sqrt()).abs();
+
+            pdf += area_pdf * dist_squared / cosine;
+        }
+
+        pdf
+    }
+
     #[inline(always)]
     fn test_impl(&self, ray: &Ray, t: Interval, skip_uvs: bool) -> Option<HitRecord> {
-        let center_diff = ray.origin() - self.center;
+        let center = self.center_at(ray.time());
+        let center_diff = ray.origin() - center;
 
         // Test for ray-sphere intersection using quadratic formula
         let a = ray.dir().length_squared();
@@ -62,17 +152,14 @@ impl Sphere {
         }
 
         let hit_pos = ray.at(root);
-        let normal = (hit_pos - self.center) / self.radius;
+        let normal = (hit_pos - center) / self.radius;
 
         // Get UV coordinates and TB vectors
         let (uv, tangent, bitangent) = if !skip_uvs {
-            let theta = f64::acos(normal[1]);
-            let phi = f64::atan2(-normal[2], normal[0]) + PI;
-
             let tangent = Vec4::vec(-normal[2], 0.0, -normal[0]);
             let bitangent = normal.cross(&tangent);
 
-            ((phi / (2.0 * PI), theta / PI), tangent, bitangent)
+            (normal_to_uv(normal).0, tangent, bitangent)
         } else {
             (
                 (0.0, 0.0),
@@ -94,6 +181,15 @@ impl Sphere {
     }
 }
 
+impl Tessellate for Sphere {
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize) {
+        builder.add_patch((2 * resolution, resolution), |u, v| {
+            let normal = uv_to_normal((u, v));
+            (self.center + normal * self.radius, normal)
+        });
+    }
+}
+
 impl Hit for Sphere {
     fn test(&self, ray: &Ray, t: Interval, _: &mut Pcg64Mcg) -> Option<HitRecord> {
         self.test_impl(ray, t, false)
@@ -104,6 +200,10 @@ impl Hit for Sphere {
     }
 
     fn pdf_value(&self, origin: Point4, dir: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        if let Some(distribution) = &self.emission_distribution {
+            return self.emission_pdf_value(distribution, origin, dir);
+        }
+
         if self
             .test_impl(&Ray::new(origin, dir), Interval(0.001, f64::INFINITY), true)
             .is_some()
@@ -121,6 +221,13 @@ impl Hit for Sphere {
     }
 
     fn random(&self, origin: Point4, rng: &mut Pcg64Mcg) -> Vec4 {
+        if let Some(distribution) = &self.emission_distribution {
+            let (uv, _) = distribution.sample(rng.sample(Standard), rng.sample(Standard));
+            let p = self.center + uv_to_normal(uv) * self.radius;
+
+            return p - origin;
+        }
+
         let dir = self.center - origin;
         let basis = onb_from_vec(dir);
 
@@ -128,6 +235,26 @@ impl Hit for Sphere {
     }
 }
 
+/// Get UV coordinates for a point on the unit sphere, along with the sine of
+/// its polar angle.
+fn normal_to_uv(normal: Vec4) -> ((f64, f64), f64) {
+    let theta = f64::acos(normal[1].clamp(-1.0, 1.0));
+    let phi = f64::atan2(-normal[2], normal[0]) + PI;
+
+    ((phi / (2.0 * PI), theta / PI), theta.sin())
+}
+
+fn uv_to_normal((u, v): (f64, f64)) -> Vec4 {
+    let theta = v * PI;
+    let phi = u * 2.0 * PI;
+
+    Vec4::vec(
+        -phi.cos() * theta.sin(),
+        theta.cos(),
+        phi.sin() * theta.sin(),
+    )
+}
+
 fn random_to_sphere(radius: f64, distance_squared: f64, rng: &mut Pcg64Mcg) -> Vec4 {
     let radius_squared = radius * radius;
     let cos_theta_max = (1.0 - radius_squared / distance_squared).sqrt();
@@ -0,0 +1,140 @@
+//! Conversion of analytic primitives to triangle meshes, so they can be
+//! displaced like any other mesh.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::material::Material;
+use crate::vec4::{Point4, Vec4};
+
+use super::mesh::{Triangle, TriangleMesh};
+
+/// Scale of the grid positions are snapped to when merging vertices.
+const WELD_PRECISION: f64 = 1e7;
+
+/// A surface that can be approximated by a triangle mesh.
+pub trait Tessellate {
+    /// Add the surface to a mesh, split into about `resolution` quads along
+    /// each UV direction.
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize);
+
+    /// Build a mesh approximating the surface, using a single material. Like
+    /// the analytic primitives, the mesh can be hit from both sides.
+    fn tessellate(&self, resolution: usize, material: Arc<dyn Material>) -> TriangleMesh {
+        let mut builder = MeshBuilder::new();
+        self.add_to_mesh(&mut builder, resolution);
+
+        let mut mesh = builder.build(material);
+        mesh.hit_back_faces = true;
+        mesh
+    }
+}
+
+/// Surfaces made of several patches, such as the sides of a box.
+impl<T: Tessellate, const N: usize> Tessellate for [T; N] {
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize) {
+        for patch in self {
+            patch.add_to_mesh(builder, resolution);
+        }
+    }
+}
+
+/// Builds a mesh out of parametric patches. Vertices at the same position
+/// are merged, so patches and seams join without cracks once displaced,
+/// while keeping their own normals and UVs.
+#[derive(Debug, Default)]
+pub struct MeshBuilder {
+    vertices: Vec<Point4>,
+    normals: Vec<Vec4>,
+    uvs: Vec<Vec4>,
+    triangles: Vec<Triangle>,
+    vertex_indices: HashMap<[i64; 3], usize>,
+}
+
+impl MeshBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a grid of `nu` by `nv` quads over a patch. `surface` maps UV
+    /// coordinates in [0; 1] to a point of the patch and its outward normal.
+    pub fn add_patch(
+        &mut self,
+        (nu, nv): (usize, usize),
+        surface: impl Fn(f64, f64) -> (Point4, Vec4),
+    ) {
+        let mut grid = Vec::with_capacity((nu + 1) * (nv + 1));
+        for j in 0..=nv {
+            for i in 0..=nu {
+                let (u, v) = (i as f64 / nu as f64, j as f64 / nv as f64);
+                let (p, normal) = surface(u, v);
+
+                self.normals.push(normal.to_unit());
+                self.uvs.push(Vec4::vec(u, v, 0.0));
+                grid.push([self.add_vertex(p), self.normals.len() - 1]);
+            }
+        }
+
+        let at = |i: usize, j: usize| grid[j * (nu + 1) + i];
+        for j in 0..nv {
+            for i in 0..nu {
+                let quad = [at(i, j), at(i + 1, j), at(i + 1, j + 1), at(i, j + 1)];
+                self.add_triangle([quad[0], quad[1], quad[2]]);
+                self.add_triangle([quad[0], quad[2], quad[3]]);
+            }
+        }
+    }
+
+    fn add_vertex(&mut self, p: Point4) -> usize {
+        let key = [p.x(), p.y(), p.z()].map(|c| (c * WELD_PRECISION).round() as i64);
+
+        *self.vertex_indices.entry(key).or_insert_with(|| {
+            self.vertices.push(p);
+            self.vertices.len() - 1
+        })
+    }
+
+    /// Add a triangle from `[vertex, normal/UV]` index pairs, facing the same
+    /// way as its normals.
+    fn add_triangle(&mut self, corners: [[usize; 2]; 3]) {
+        let mut vert_indices = corners.map(|c| c[0]);
+        let mut attr_indices = corners.map(|c| c[1]);
+
+        // Corners merged at poles or apexes leave a degenerate triangle
+        if vert_indices[0] == vert_indices[1]
+            || vert_indices[1] == vert_indices[2]
+            || vert_indices[2] == vert_indices[0]
+        {
+            return;
+        }
+
+        let [v0, v1, v2] = vert_indices.map(|idx| self.vertices[idx]);
+        let face_normal = Vec4::cross(&(v1 - v0), &(v2 - v0));
+        let normal_sum = attr_indices
+            .iter()
+            .fold(Vec4::vec(0.0, 0.0, 0.0), |sum, &idx| {
+                sum + self.normals[idx]
+            });
+        if face_normal.dot(&normal_sum) < 0.0 {
+            vert_indices.swap(1, 2);
+            attr_indices.swap(1, 2);
+        }
+
+        self.triangles.push(Triangle {
+            vert_indices,
+            normal_indices: attr_indices,
+            uv_indices: Some(attr_indices),
+            material_index: 0,
+        });
+    }
+
+    pub fn build(self, material: Arc<dyn Material>) -> TriangleMesh {
+        TriangleMesh::new(
+            self.vertices,
+            self.uvs,
+            self.normals,
+            self.triangles,
+            vec![material],
+        )
+    }
+}
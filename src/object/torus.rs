@@ -0,0 +1,300 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand_pcg::Pcg64Mcg;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec4::{Point4, Vec4};
+
+use super::shape::{self, azimuth, azimuth_tangent, Frame, LocalShape, SurfacePoint};
+use super::tessellate::{MeshBuilder, Tessellate};
+use super::{Hit, HitRecord};
+
+/// Tolerance under which a coefficient counts as zero in the polynomial
+/// solvers.
+const EPSILON: f64 = 1e-9;
+
+/// A ring around its axis, made by sweeping a circle of the minor radius at
+/// the major radius from the center. UVs go around the axis in U, and around
+/// the tube in V.
+#[derive(Debug)]
+pub struct Torus {
+    pub material: Arc<dyn Material>,
+
+    frame: Frame,
+    major_radius: f64,
+    minor_radius: f64,
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point4,
+        axis: Vec4,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let frame = Frame::new(center, axis);
+        let extent = major_radius + minor_radius;
+        let bounds = frame.bounds(
+            Vec4::vec(-extent, -extent, -minor_radius),
+            Vec4::vec(extent, extent, minor_radius),
+        );
+
+        Torus {
+            material,
+            frame,
+            major_radius,
+            minor_radius,
+            bounds,
+        }
+    }
+}
+
+impl LocalShape for Torus {
+    type Part = ();
+
+    fn frame(&self) -> &Frame {
+        &self.frame
+    }
+
+    fn material(&self) -> &dyn Material {
+        Arc::as_ref(&self.material)
+    }
+
+    fn intersect(&self, origin: Vec4, dir: Vec4, found: &mut dyn FnMut(f64, ())) {
+        let dir_length = dir.length();
+        if dir_length == 0.0 {
+            return;
+        }
+        let d = dir / dir_length;
+
+        // Start from the point of the ray closest to the center, which keeps
+        // the quartic's coefficients small for faraway rays
+        let shift = -origin.dot(&d);
+        let o = origin + d * shift;
+        let extent = self.major_radius + self.minor_radius;
+        if o.length_squared() > extent * extent {
+            return;
+        }
+
+        // Points on the torus verify (|p|² + R² - r²)² = 4R²(x² + y²)
+        let major_squared = self.major_radius * self.major_radius;
+        let e = o.dot(&d);
+        let g = o.length_squared() + major_squared - self.minor_radius * self.minor_radius;
+        let coeffs = [
+            g * g - 4.0 * major_squared * (o.x() * o.x() + o.y() * o.y()),
+            4.0 * e * g - 8.0 * major_squared * (o.x() * d.x() + o.y() * d.y()),
+            4.0 * e * e + 2.0 * g - 4.0 * major_squared * (d.x() * d.x() + d.y() * d.y()),
+            4.0 * e,
+            1.0,
+        ];
+
+        for root in solve_quartic(coeffs) {
+            found((polish_root(coeffs, root) + shift) / dir_length, ());
+        }
+    }
+
+    fn surface_at(&self, p: Vec4, _: ()) -> SurfacePoint {
+        let phi = azimuth(p);
+        let ring_dist = f64::sqrt(p.x() * p.x() + p.y() * p.y()) - self.major_radius;
+        let theta = f64::atan2(p.z(), ring_dist).rem_euclid(2.0 * PI);
+
+        // The normal points away from the closest point of the central ring
+        let ring_point = Vec4::vec(phi.cos(), phi.sin(), 0.0) * self.major_radius;
+
+        SurfacePoint {
+            normal: (p - ring_point).to_unit(),
+            uv: (phi / (2.0 * PI), theta / (2.0 * PI)),
+            tangent: azimuth_tangent(phi),
+        }
+    }
+
+    fn area(&self) -> f64 {
+        4.0 * PI * PI * self.major_radius * self.minor_radius
+    }
+
+    fn sample_point(&self, rng: &mut Pcg64Mcg) -> Vec4 {
+        let phi = rng.gen_range(0.0..(2.0 * PI));
+
+        // The outside of the tube is larger than the inside, so pick the
+        // angle around the tube by rejection
+        let max_dist = self.major_radius + self.minor_radius;
+        let theta = loop {
+            let theta = rng.gen_range(0.0..(2.0 * PI));
+            let dist = self.major_radius + self.minor_radius * theta.cos();
+            if rng.gen_range(0.0..max_dist) <= dist {
+                break theta;
+            }
+        };
+
+        let dist = self.major_radius + self.minor_radius * theta.cos();
+        Vec4::vec(
+            dist * phi.cos(),
+            dist * phi.sin(),
+            self.minor_radius * theta.sin(),
+        )
+    }
+}
+
+impl Tessellate for Torus {
+    fn add_to_mesh(&self, builder: &mut MeshBuilder, resolution: usize) {
+        shape::add_patch(self, builder, (2 * resolution, resolution), (), |u, v| {
+            let (sin_phi, cos_phi) = (2.0 * PI * u).sin_cos();
+            let (sin_theta, cos_theta) = (2.0 * PI * v).sin_cos();
+            let dist = self.major_radius + self.minor_radius * cos_theta;
+
+            Vec4::vec(
+                dist * cos_phi,
+                dist * sin_phi,
+                self.minor_radius * sin_theta,
+            )
+        });
+    }
+}
+
+impl Hit for Torus {
+    fn test(&self, ray: &Ray, t: Interval, _: &mut Pcg64Mcg) -> Option<HitRecord<'_>> {
+        shape::test(self, ray, t)
+    }
+
+    fn get_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.bounds
+    }
+
+    fn pdf_value(&self, origin: Point4, dir: Vec4, _: &mut Pcg64Mcg) -> f64 {
+        shape::pdf_value(self, origin, dir)
+    }
+
+    fn random(&self, origin: Point4, rng: &mut Pcg64Mcg) -> Vec4 {
+        shape::random(self, origin, rng)
+    }
+}
+
+/// Refine a root of a polynomial with a few Newton steps, since the closed
+/// form solution of a quartic loses a lot of precision.
+fn polish_root(coeffs: [f64; 5], mut x: f64) -> f64 {
+    for _ in 0..2 {
+        let value = coeffs.iter().rev().fold(0.0, |acc, c| acc * x + c);
+        let derivative = (1..5)
+            .rev()
+            .fold(0.0, |acc, i| acc * x + i as f64 * coeffs[i]);
+        if derivative.abs() < EPSILON {
+            break;
+        }
+        x -= value / derivative;
+    }
+
+    x
+}
+
+// Polynomial solvers, from "Solving quartic and cubic equations" by Jochen
+// Schwarze, in Graphics Gems I. Coefficients go from the constant term to the
+// highest degree term.
+
+fn solve_quadric(coeffs: [f64; 3]) -> Vec<f64> {
+    let p = coeffs[1] / (2.0 * coeffs[2]);
+    let q = coeffs[0] / coeffs[2];
+    let discriminant = p * p - q;
+
+    if discriminant.abs() < EPSILON {
+        vec![-p]
+    } else if discriminant < 0.0 {
+        vec![]
+    } else {
+        let d_sqrt = discriminant.sqrt();
+        vec![d_sqrt - p, -d_sqrt - p]
+    }
+}
+
+fn solve_cubic(coeffs: [f64; 4]) -> Vec<f64> {
+    // Normal form x³ + Ax² + Bx + C, substituted with x = y - A/3 to remove
+    // the quadratic term: y³ + 3py + 2q
+    let a = coeffs[2] / coeffs[3];
+    let b = coeffs[1] / coeffs[3];
+    let c = coeffs[0] / coeffs[3];
+
+    let a_squared = a * a;
+    let p = (-a_squared / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * a_squared - a * b / 3.0 + c) / 2.0;
+
+    let p_cubed = p * p * p;
+    let discriminant = q * q + p_cubed;
+
+    let roots = if discriminant.abs() < EPSILON {
+        if q.abs() < EPSILON {
+            vec![0.0]
+        } else {
+            let u = f64::cbrt(-q);
+            vec![2.0 * u, -u]
+        }
+    } else if discriminant < 0.0 {
+        // Three real roots
+        let phi = f64::acos(-q / f64::sqrt(-p_cubed)) / 3.0;
+        let t = 2.0 * f64::sqrt(-p);
+        vec![
+            t * phi.cos(),
+            -t * (phi + PI / 3.0).cos(),
+            -t * (phi - PI / 3.0).cos(),
+        ]
+    } else {
+        let d_sqrt = discriminant.sqrt();
+        vec![f64::cbrt(d_sqrt - q) - f64::cbrt(d_sqrt + q)]
+    };
+
+    roots.into_iter().map(|y| y - a / 3.0).collect()
+}
+
+fn solve_quartic(coeffs: [f64; 5]) -> Vec<f64> {
+    // Normal form x⁴ + Ax³ + Bx² + Cx + D, substituted with x = y - A/4 to
+    // remove the cubic term: y⁴ + py² + qy + r
+    let a = coeffs[3] / coeffs[4];
+    let b = coeffs[2] / coeffs[4];
+    let c = coeffs[1] / coeffs[4];
+    let d = coeffs[0] / coeffs[4];
+
+    let a_squared = a * a;
+    let p = -3.0 / 8.0 * a_squared + b;
+    let q = a_squared * a / 8.0 - a * b / 2.0 + c;
+    let r = -3.0 / 256.0 * a_squared * a_squared + a_squared * b / 16.0 - a * c / 4.0 + d;
+
+    let roots = if r.abs() < EPSILON {
+        // No constant term: y(y³ + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // Split the quartic into two quadrics with a root of the resolvent
+        // cubic
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if u.abs() < EPSILON {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return vec![];
+        };
+        let v = if v.abs() < EPSILON {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return vec![];
+        };
+
+        let signed_v = if q < 0.0 { -v } else { v };
+        let mut roots = solve_quadric([z - u, signed_v, 1.0]);
+        roots.extend(solve_quadric([z + u, -signed_v, 1.0]));
+        roots
+    };
+
+    roots.into_iter().map(|y| y - a / 4.0).collect()
+}
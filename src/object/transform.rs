@@ -121,7 +121,7 @@ impl Transform {
 impl Hit for Transform {
     fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
         // Transform ray to object space
-        let ray_obj = Ray::new(
+        let ray_obj = ray.spawn(
             self.inv_transform * ray.origin(),
             self.inv_transform * ray.dir(),
         );
@@ -11,6 +11,9 @@ use crate::{interval::Interval, vec4::Vec4};
 
 use super::{Hit, HitRecord};
 
+/// Limit on boundary crossings tested per ray, in case of broken boundaries.
+const MAX_BOUNDARY_CROSSINGS: usize = 64;
+
 #[derive(Debug)]
 pub struct Volume {
     boundary: Arc<dyn Hit>,
@@ -31,43 +34,36 @@ impl Volume {
 
 impl Hit for Volume {
     fn test(&self, ray: &Ray, t: Interval, rng: &mut Pcg64Mcg) -> Option<HitRecord> {
-        if let Some(hit_enter) = self.boundary.test(ray, Interval::UNIVERSE, rng) {
-            let exit_t = Interval(hit_enter.t() + 0.0001, f64::INFINITY);
-
-            if let Some(hit_exit) = self.boundary.test(ray, exit_t, rng) {
-                let mut t_min = f64::max(hit_enter.t(), t.min());
-                let t_max = f64::min(hit_exit.t(), t.max());
-
-                if t_min >= t_max {
-                    return None;
-                }
-
-                t_min = f64::max(t_min, 0.0);
-                let ray_len = ray.dir().length();
-                let dist_inside_boundary = (t_max - t_min) * ray_len;
-                let hit_dist = self.neg_inv_density * f64::ln(rng.sample(Standard));
-
-                if hit_dist > dist_inside_boundary {
-                    return None;
-                }
-
-                let t = t_min + hit_dist / ray_len;
-                let hit_pos = ray.at(t);
-
-                return Some(HitRecord::new(
-                    ray,
-                    hit_pos,
-                    t,
-                    (0.0, 0.0),               // Arbitrary, unused
-                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
-                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
-                    Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
-                    self.material.as_ref(),
-                ));
+        let segments = boundary_segments(&self.boundary, ray, &t, rng);
+
+        // With constant density, the distance to the scattering point can be
+        // sampled once and then walked along the segments inside the boundary
+        let ray_len = ray.dir().length();
+        let mut hit_dist = self.neg_inv_density * f64::ln(rng.sample(Standard));
+        let mut scatter_t = None;
+
+        for (t_min, t_max) in segments {
+            let segment_dist = (t_max - t_min) * ray_len;
+            if hit_dist <= segment_dist {
+                scatter_t = Some(t_min + hit_dist / ray_len);
+                break;
             }
+            hit_dist -= segment_dist;
         }
 
-        None
+        let t = scatter_t?;
+        let hit_pos = ray.at(t);
+
+        Some(HitRecord::new(
+            ray,
+            hit_pos,
+            t,
+            (0.0, 0.0),               // Arbitrary, unused
+            Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+            Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+            Vec4::vec(1.0, 0.0, 0.0), // Arbitrary, unused
+            self.material.as_ref(),
+        ))
     }
 
     fn get_bounding_box(&self) -> crate::aabb::AxisAlignedBoundingBox {
@@ -82,3 +78,40 @@ impl Hit for Volume {
         Vec4::vec(1.0, 0.0, 0.0)
     }
 }
+
+/// Find the intervals along a ray that lie inside a volume boundary, clipped
+/// to the interval `t`.
+///
+/// Boundary crossings are found by walking the ray from hit to hit, so any
+/// closed boundary works, as long as it reports hits on both sides of its
+/// surface. A ray whose first crossing is an exit started inside the volume.
+pub(super) fn boundary_segments(
+    boundary: &Arc<dyn Hit>,
+    ray: &Ray,
+    t: &Interval,
+    rng: &mut Pcg64Mcg,
+) -> Vec<(f64, f64)> {
+    let mut segments = Vec::new();
+    let mut segment_start = t.min();
+    let mut t_next = t.min();
+
+    for _ in 0..MAX_BOUNDARY_CROSSINGS {
+        let hit = match boundary.test(ray, Interval(t_next, f64::INFINITY), rng) {
+            Some(hit) => hit,
+            None => break, // Rays leaving an open boundary are considered outside
+        };
+
+        if hit.front_face() {
+            segment_start = hit.t();
+        } else {
+            segments.push((f64::max(segment_start, t.min()), f64::min(hit.t(), t.max())));
+        }
+
+        if hit.t() >= t.max() {
+            break;
+        }
+        t_next = hit.t() + 0.0001;
+    }
+
+    segments
+}
@@ -4,6 +4,10 @@ use rand_pcg::Pcg64Mcg;
 
 mod cosine;
 pub use cosine::CosinePDF;
+mod distribution;
+pub use distribution::{Distribution1D, Distribution2D};
+mod henyey_greenstein;
+pub use henyey_greenstein::{henyey_greenstein, HenyeyGreensteinPDF};
 mod hittable;
 pub use hittable::HittablePDF;
 mod mix;
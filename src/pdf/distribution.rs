@@ -0,0 +1,138 @@
+/// A piecewise-constant 1D distribution over [0; 1], built from a list of
+/// non-negative function values.
+#[derive(Debug)]
+pub struct Distribution1D {
+    func: Vec<f64>,
+    cdf: Vec<f64>,
+    integral: f64,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f64>) -> Self {
+        let n = func.len();
+
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for i in 0..n {
+            cdf.push(cdf[i] + func[i].max(0.0) / n as f64);
+        }
+
+        let integral = cdf[n];
+        if integral > 0.0 {
+            for value in &mut cdf {
+                *value /= integral;
+            }
+        } else {
+            // Fall back to a uniform distribution if the function is zero everywhere
+            for (i, value) in cdf.iter_mut().enumerate() {
+                *value = i as f64 / n as f64;
+            }
+        }
+
+        Distribution1D {
+            func,
+            cdf,
+            integral,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.func.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.func.is_empty()
+    }
+
+    /// Integral of the function over [0; 1].
+    pub fn integral(&self) -> f64 {
+        self.integral
+    }
+
+    /// Sample the distribution from a uniform random value in [0; 1).
+    ///
+    /// Returns the sampled position in [0; 1), its PDF value and the index of
+    /// the segment it falls into.
+    pub fn sample(&self, u: f64) -> (f64, f64, usize) {
+        // Last segment with cdf <= u
+        let idx = self.cdf.partition_point(|&c| c <= u).clamp(1, self.len()) - 1;
+
+        let width = self.cdf[idx + 1] - self.cdf[idx];
+        let offset = if width > 0.0 {
+            (u - self.cdf[idx]) / width
+        } else {
+            0.0
+        };
+
+        let x = (idx as f64 + offset) / self.len() as f64;
+        (x, self.pdf(x), idx)
+    }
+
+    /// PDF value at a position in [0; 1].
+    pub fn pdf(&self, x: f64) -> f64 {
+        let idx = ((x * self.len() as f64) as usize).min(self.len() - 1);
+
+        if self.integral > 0.0 {
+            self.func[idx].max(0.0) / self.integral
+        } else {
+            1.0
+        }
+    }
+}
+
+/// A piecewise-constant 2D distribution over [0; 1]², built from a grid of
+/// non-negative function values in row-major order (`u` varying fastest).
+#[derive(Debug)]
+pub struct Distribution2D {
+    conditional: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f64], size_u: usize, size_v: usize) -> Self {
+        assert_eq!(func.len(), size_u * size_v, "Distribution size mismatch");
+
+        let conditional: Vec<_> = func
+            .chunks(size_u)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(conditional.iter().map(|d| d.integral()).collect());
+
+        Distribution2D {
+            conditional,
+            marginal,
+        }
+    }
+
+    /// Build a distribution by evaluating a function at the center of each
+    /// cell of a `size_u` by `size_v` grid.
+    pub fn from_fn(size_u: usize, size_v: usize, f: impl Fn((f64, f64)) -> f64) -> Self {
+        let mut func = Vec::with_capacity(size_u * size_v);
+        for j in 0..size_v {
+            for i in 0..size_u {
+                let u = (i as f64 + 0.5) / size_u as f64;
+                let v = (j as f64 + 0.5) / size_v as f64;
+                func.push(f((u, v)));
+            }
+        }
+
+        Self::new(&func, size_u, size_v)
+    }
+
+    /// Sample the distribution from two uniform random values in [0; 1).
+    ///
+    /// Returns the sampled `(u, v)` position and its PDF value.
+    pub fn sample(&self, r1: f64, r2: f64) -> ((f64, f64), f64) {
+        let (v, pdf_v, row) = self.marginal.sample(r2);
+        let (u, pdf_u, _) = self.conditional[row].sample(r1);
+
+        ((u, v), pdf_u * pdf_v)
+    }
+
+    /// PDF value at a `(u, v)` position in [0; 1]².
+    pub fn pdf(&self, (u, v): (f64, f64)) -> f64 {
+        let row = ((v * self.marginal.len() as f64) as usize).min(self.marginal.len() - 1);
+
+        self.marginal.pdf(v) * self.conditional[row].pdf(u)
+    }
+}
@@ -0,0 +1,85 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+use rand_distr::Standard;
+use rand_pcg::Pcg64Mcg;
+
+use crate::mat4::Mat4;
+use crate::utils::onb_from_vec;
+use crate::vec4::Vec4;
+
+use super::PDF;
+
+/// Henyey-Greenstein phase function.
+///
+/// `cos_theta` is the cosine of the angle between the incoming ray direction
+/// and the scattered direction. `g` is the asymmetry parameter in (-1; 1):
+/// positive values scatter forward, negative values scatter backward, and 0
+/// is isotropic.
+pub fn henyey_greenstein(cos_theta: f64, g: f64) -> f64 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+}
+
+/// Sample the cosine of the scattering angle from a Henyey-Greenstein
+/// distribution.
+fn sample_cos_theta(g: f64, r: f64) -> f64 {
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * r;
+    }
+
+    let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * r);
+    ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+}
+
+/// PDF for a mix of two Henyey-Greenstein lobes around the incoming ray
+/// direction. A single lobe is just a mix with `blend` set to 0.
+pub struct HenyeyGreensteinPDF {
+    basis: Mat4,
+    w: Vec4,
+    g: (f64, f64),
+    blend: f64,
+}
+
+impl HenyeyGreensteinPDF {
+    pub fn new(w: Vec4, g: f64) -> Self {
+        Self::double(w, g, 0.0, 0.0)
+    }
+
+    /// Double Henyey-Greenstein, with `blend` as the weight of the second
+    /// lobe.
+    pub fn double(w: Vec4, g1: f64, g2: f64, blend: f64) -> Self {
+        HenyeyGreensteinPDF {
+            basis: onb_from_vec(w),
+            w,
+            g: (g1, g2),
+            blend,
+        }
+    }
+}
+
+impl PDF for HenyeyGreensteinPDF {
+    fn value(&self, dir: &Vec4, _: &mut Pcg64Mcg) -> f64 {
+        let cos_theta = Vec4::dot(&dir.to_unit(), &self.w);
+
+        henyey_greenstein(cos_theta, self.g.0) * (1.0 - self.blend)
+            + henyey_greenstein(cos_theta, self.g.1) * self.blend
+    }
+
+    fn generate(&self, rng: &mut Pcg64Mcg) -> Vec4 {
+        let g = if rng.gen_range(0.0..1.0) < self.blend {
+            self.g.1
+        } else {
+            self.g.0
+        };
+
+        let r1: f64 = rng.sample(Standard);
+        let r2: f64 = rng.sample(Standard);
+
+        let cos_theta = sample_cos_theta(g, r1);
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = r2 * 2.0 * PI;
+
+        self.basis * Vec4::vec(phi.cos() * sin_theta, phi.sin() * sin_theta, cos_theta)
+    }
+}
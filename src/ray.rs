@@ -1,3 +1,4 @@
+use crate::medium::MediumStack;
 use crate::vec4::{Point4, Vec4};
 
 #[derive(Clone, Copy)]
@@ -6,6 +7,8 @@ pub struct Ray {
     dir: Vec4,
     inv_dir: Vec4,
     sign: [u8; 3],
+    media: MediumStack,
+    time: f64,
 }
 
 impl Ray {
@@ -29,6 +32,32 @@ impl Ray {
             dir,
             inv_dir,
             sign,
+            media: MediumStack::new(),
+            time: 0.0,
+        }
+    }
+
+    /// Same ray, at a different point in time. Rays spawned from it keep the
+    /// same time, so a whole path sees moving objects at the same position.
+    pub fn with_time(self, time: f64) -> Self {
+        Ray { time, ..self }
+    }
+
+    /// Create a new ray continuing the path of this one, keeping track of the
+    /// media it travels through.
+    pub fn spawn(&self, origin: Point4, dir: Vec4) -> Self {
+        Ray {
+            media: self.media,
+            time: self.time,
+            ..Ray::new(origin, dir)
+        }
+    }
+
+    /// Same as `spawn`, but with a different set of media.
+    pub fn spawn_with_media(&self, origin: Point4, dir: Vec4, media: MediumStack) -> Self {
+        Ray {
+            media,
+            ..self.spawn(origin, dir)
         }
     }
 
@@ -51,4 +80,12 @@ impl Ray {
     pub fn sign(&self) -> [u8; 3] {
         self.sign
     }
+
+    pub fn media(&self) -> &MediumStack {
+        &self.media
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
 }
@@ -23,7 +23,15 @@ impl SceneInit for CornellBoxScene {
             focus_distance: None,
             camera_pos: Some(Vec4::point(277.5, 277.5, -800.0)),
             camera_target: Some(Vec4::point(277.5, 277.5, 0.0)),
+            camera_up: None,
+            camera_roll: None,
+            camera_transform: None,
             background: None,
+            fog: None,
+            shutter_open: None,
+            shutter_close: None,
+            projection: None,
+            lens: None,
         };
 
         let scene_config = SceneConfig::merge(
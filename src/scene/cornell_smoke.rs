@@ -23,7 +23,15 @@ impl SceneInit for CornellSmokeScene {
             focus_distance: None,
             camera_pos: Some(Vec4::point(0.0, 0.0, 110.0)),
             camera_target: Some(Vec4::point(0.0, 0.0, 0.0)),
+            camera_up: None,
+            camera_roll: None,
+            camera_transform: None,
             background: None,
+            fog: None,
+            shutter_open: None,
+            shutter_close: None,
+            projection: None,
+            lens: None,
         };
 
         let scene_config = SceneConfig::merge(
@@ -94,23 +102,21 @@ impl SceneInit for CornellSmokeScene {
         );
         let light: Arc<dyn Hit> = Arc::new(light);
 
-        let mut box1 = make_box(
+        let box1 = make_box(
             Vec4::point(0.0, 0.0, 0.0),
             Vec4::vec(16.5, 16.5, 16.5),
             Arc::clone(&mat_white),
         );
-        box1.disable_bounds_check = true;
         let mut box1 = Transform::new(Arc::new(box1));
         box1.rotate_y(deg_to_rad(-15.0));
         box1.translate(27.5 - 21.25, 8.25 - 27.5, 27.5 - 14.75);
         let box1 = Volume::new(Arc::new(box1), mat_smoke, 0.15);
 
-        let mut box2 = make_box(
+        let box2 = make_box(
             Vec4::point(0.0, 0.0, 0.0),
             Vec4::vec(16.5, 33.0, 16.5),
             Arc::clone(&mat_white),
         );
-        box2.disable_bounds_check = true;
         let mut box2 = Transform::new(Arc::new(box2));
         box2.rotate_y(deg_to_rad(18.0));
         box2.translate(27.5 - 34.75, 16.5 - 27.5, 27.5 - 37.75);
@@ -10,7 +10,7 @@ use crate::config::{Config, DEFAULT_SCENE_CONFIG, SceneConfig};
 use crate::loaders::obj::load_mesh_from_file;
 use crate::material::{Dielectric, Glossy, LambertianDiffuse, Material, Metal};
 use crate::object::{Hit, ObjectList, Plane, Sky, Sphere, Sun};
-use crate::object::bvh::{self, BoundingVolumeHierarchyNode};
+use crate::object::bvh::BoundingVolumeHierarchy;
 use crate::object::transform::Transform;
 use crate::texture::{CheckerboardTexture, ConstantTexture};
 use crate::vec4::Vec4;
@@ -29,7 +29,15 @@ impl SceneInit for GoldenMonkeyScene {
             focus_distance: None,
             camera_pos: Some(Vec4::point(5.0, 2.0, 9.0)),
             camera_target: Some(Vec4::point(0.0, 0.5, 0.0)),
+            camera_up: None,
+            camera_roll: None,
+            camera_transform: None,
             background: None,
+            fog: None,
+            shutter_open: None,
+            shutter_close: None,
+            projection: None,
+            lens: None,
         };
 
         let scene_config = SceneConfig::merge(
@@ -117,7 +125,7 @@ impl SceneInit for GoldenMonkeyScene {
             }
         }
 
-        let spheres_bvh = BoundingVolumeHierarchyNode::from(random_spheres, bvh::AXES_XZ, &mut rng);
+        let spheres_bvh = BoundingVolumeHierarchy::from(random_spheres);
 
         let mut world = ObjectList::new();
         world.add(Arc::new(mesh));
@@ -25,7 +25,15 @@ impl SceneInit for LightTestScene {
             focus_distance: None,
             camera_pos: Some(Vec4::point(10.0, 1.0, 6.0)),
             camera_target: Some(Vec4::point(0.0, 0.0, 0.0)),
+            camera_up: None,
+            camera_roll: None,
+            camera_transform: None,
             background: None,
+            fog: None,
+            shutter_open: None,
+            shutter_close: None,
+            projection: None,
+            lens: None,
         };
 
         let scene_config = SceneConfig::merge(
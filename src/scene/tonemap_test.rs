@@ -22,7 +22,15 @@ impl SceneInit for TonemapTestScene {
             focus_distance: None,
             camera_pos: Some(Vec4::point(0.0, 30.0, 15.0)),
             camera_target: Some(Vec4::point(0.0, 0.0, -0.75)),
+            camera_up: None,
+            camera_roll: None,
+            camera_transform: None,
             background: None,
+            fog: None,
+            shutter_open: None,
+            shutter_close: None,
+            projection: None,
+            lens: None,
         };
 
         let scene_config = SceneConfig::merge(
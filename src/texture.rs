@@ -1,5 +1,6 @@
 use std::{fmt::Debug, sync::Arc};
 
+use crate::object::HitRecord;
 use crate::vec4::Point4;
 
 pub mod channel;
@@ -8,7 +9,10 @@ pub mod constant;
 pub mod image;
 pub mod interpolate;
 pub mod noise;
+pub mod scale;
 pub mod uv_debug;
+pub mod vertex_color;
+pub mod voxel_grid;
 
 pub use channel::Channel;
 pub use checkerboard::{CheckerboardSolidTexture, CheckerboardTexture};
@@ -16,12 +20,22 @@ pub use constant::ConstantTexture;
 pub use image::ImageTexture;
 pub use interpolate::Interpolate;
 pub use noise::NoiseSolidTexture;
+pub use scale::Scale;
 pub use uv_debug::UvDebugTexture;
+pub use vertex_color::{VertexColorTexture, VertexColorTint};
+pub use voxel_grid::VoxelGridTexture;
 
 pub trait Sampler: Send + Sync + Debug {
     type Output: Send + Sync + Copy;
 
     fn sample(&self, uv: (f64, f64), p: &Point4) -> Self::Output;
+
+    /// Sample the texture at a surface hit. Textures using other attributes
+    /// of the hit than its UV and position, such as vertex colors, override
+    /// this, and textures built from other textures forward it to them.
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        self.sample(hit.uv(), &hit.pos())
+    }
 }
 
 pub type TexturePointer<T> = Arc<dyn Sampler<Output = T>>;
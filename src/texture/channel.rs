@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 
+use crate::object::HitRecord;
 use crate::vec4::{Color, Point4};
 
 use super::{Sampler, TexturePointer};
@@ -23,4 +24,8 @@ impl Sampler for Channel {
         let color = self.color.sample(uv, p);
         color[self.channel]
     }
+
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        self.color.sample_hit(hit)[self.channel]
+    }
 }
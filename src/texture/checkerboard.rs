@@ -1,5 +1,6 @@
 use std::fmt::Debug;
 
+use crate::object::HitRecord;
 use crate::vec4::Point4;
 
 use super::{Sampler, TexturePointer};
@@ -23,6 +24,19 @@ impl<T> CheckerboardTexture<T> {
             scale,
         }
     }
+
+    /// Get the texture of the square a UV falls into.
+    fn square(&self, (u, v): (f64, f64)) -> &TexturePointer<T> {
+        let iu = (u * 2.0 / self.scale) as u32;
+        let iv = (v * 2.0 / self.scale) as u32;
+
+        let is_even = (iu + iv) % 2 == 0;
+        if is_even {
+            &self.even_squares
+        } else {
+            &self.odd_squares
+        }
+    }
 }
 
 impl<T> Sampler for CheckerboardTexture<T>
@@ -31,16 +45,12 @@ where
 {
     type Output = T;
 
-    fn sample(&self, (u, v): (f64, f64), p: &Point4) -> Self::Output {
-        let iu = (u * 2.0 / self.scale) as u32;
-        let iv = (v * 2.0 / self.scale) as u32;
+    fn sample(&self, uv: (f64, f64), p: &Point4) -> Self::Output {
+        self.square(uv).sample(uv, p)
+    }
 
-        let is_even = (iu + iv) % 2 == 0;
-        if is_even {
-            self.even_squares.sample((u, v), p)
-        } else {
-            self.odd_squares.sample((u, v), p)
-        }
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        self.square(hit.uv()).sample_hit(hit)
     }
 }
 
@@ -63,6 +73,20 @@ impl<T> CheckerboardSolidTexture<T> {
             scale,
         }
     }
+
+    /// Get the texture of the volume a point falls into.
+    fn volume(&self, p: &Point4) -> &TexturePointer<T> {
+        let ix = (p.x() / self.scale).floor() as i32;
+        let iy = (p.y() / self.scale).floor() as i32;
+        let iz = (p.z() / self.scale).floor() as i32;
+
+        let is_even = (ix + iy + iz) % 2 == 0;
+        if is_even {
+            &self.even_volumes
+        } else {
+            &self.odd_volumes
+        }
+    }
 }
 
 impl<T> Sampler for CheckerboardSolidTexture<T>
@@ -72,15 +96,10 @@ where
     type Output = T;
 
     fn sample(&self, uv: (f64, f64), p: &Point4) -> Self::Output {
-        let ix = (p.x() / self.scale).floor() as i32;
-        let iy = (p.y() / self.scale).floor() as i32;
-        let iz = (p.z() / self.scale).floor() as i32;
+        self.volume(p).sample(uv, p)
+    }
 
-        let is_even = (ix + iy + iz) % 2 == 0;
-        if is_even {
-            self.even_volumes.sample(uv, p)
-        } else {
-            self.odd_volumes.sample(uv, p)
-        }
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        self.volume(&hit.pos()).sample_hit(hit)
     }
 }
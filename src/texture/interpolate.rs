@@ -3,6 +3,7 @@ use std::{
     ops::{Add, Mul},
 };
 
+use crate::object::HitRecord;
 use crate::vec4::Point4;
 
 use super::{Sampler, TexturePointer};
@@ -20,6 +21,22 @@ impl<T> Interpolate<T> {
     }
 }
 
+impl<T> Interpolate<T>
+where
+    T: Send + Sync + Copy + Mul<f64, Output = T> + Add<T, Output = T> + Debug,
+{
+    /// Blend the start and end textures, sampled with `sample`.
+    fn blend(&self, t: f64, sample: impl Fn(&TexturePointer<T>) -> T) -> T {
+        if t == 0.0 {
+            sample(&self.start)
+        } else if t == 1.0 {
+            sample(&self.end)
+        } else {
+            sample(&self.start) * (1.0 - t) + sample(&self.end) * t
+        }
+    }
+}
+
 impl<T> Sampler for Interpolate<T>
 where
     T: Send + Sync + Copy + Mul<f64, Output = T> + Add<T, Output = T> + Debug,
@@ -27,14 +44,10 @@ where
     type Output = T;
 
     fn sample(&self, uv: (f64, f64), p: &Point4) -> Self::Output {
-        let t = self.t.sample(uv, p);
+        self.blend(self.t.sample(uv, p), |texture| texture.sample(uv, p))
+    }
 
-        if t == 0.0 {
-            self.start.sample(uv, p)
-        } else if t == 1.0 {
-            self.end.sample(uv, p)
-        } else {
-            self.start.sample(uv, p) * (1.0 - t) + self.end.sample(uv, p) * t
-        }
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        self.blend(self.t.sample_hit(hit), |texture| texture.sample_hit(hit))
     }
 }
@@ -0,0 +1,35 @@
+use std::{fmt::Debug, ops::Mul};
+
+use crate::object::HitRecord;
+use crate::vec4::Point4;
+
+use super::{Sampler, TexturePointer};
+
+/// A texture multiplied by a constant factor, such as a tint applied to an
+/// image texture.
+#[derive(Debug)]
+pub struct Scale<T> {
+    texture: TexturePointer<T>,
+    factor: T,
+}
+
+impl<T> Scale<T> {
+    pub fn new(texture: TexturePointer<T>, factor: T) -> Self {
+        Scale { texture, factor }
+    }
+}
+
+impl<T> Sampler for Scale<T>
+where
+    T: Send + Sync + Copy + Mul<T, Output = T> + Debug,
+{
+    type Output = T;
+
+    fn sample(&self, uv: (f64, f64), p: &Point4) -> Self::Output {
+        self.texture.sample(uv, p) * self.factor
+    }
+
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        self.texture.sample_hit(hit) * self.factor
+    }
+}
@@ -0,0 +1,59 @@
+use crate::object::HitRecord;
+use crate::vec4::{Color, Point4};
+
+use super::{Sampler, TexturePointer};
+
+/// The vertex colors of a mesh, interpolated across its triangles. Surfaces
+/// without vertex colors use a fallback color.
+#[derive(Debug)]
+pub struct VertexColorTexture {
+    fallback: Color,
+}
+
+impl VertexColorTexture {
+    pub fn new(fallback: Color) -> Self {
+        VertexColorTexture { fallback }
+    }
+}
+
+impl Sampler for VertexColorTexture {
+    type Output = Color;
+
+    fn sample(&self, _: (f64, f64), _: &Point4) -> Self::Output {
+        self.fallback
+    }
+
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        hit.vertex_color().unwrap_or(self.fallback)
+    }
+}
+
+/// A texture multiplied by the vertex colors of a mesh, as glTF applies
+/// `COLOR_0` to the base color. Surfaces without vertex colors use the
+/// texture as is.
+#[derive(Debug)]
+pub struct VertexColorTint {
+    texture: TexturePointer<Color>,
+}
+
+impl VertexColorTint {
+    pub fn new(texture: TexturePointer<Color>) -> Self {
+        VertexColorTint { texture }
+    }
+}
+
+impl Sampler for VertexColorTint {
+    type Output = Color;
+
+    fn sample(&self, uv: (f64, f64), p: &Point4) -> Self::Output {
+        self.texture.sample(uv, p)
+    }
+
+    fn sample_hit(&self, hit: &HitRecord) -> Self::Output {
+        let color = self.texture.sample_hit(hit);
+        match hit.vertex_color() {
+            Some(vertex_color) => color * vertex_color,
+            None => color,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::error::Error;
+use std::fs;
+
+use crate::aabb::AxisAlignedBoundingBox;
+use crate::utils::ParseError;
+use crate::vec4::Point4;
+
+use super::Sampler;
+
+/// A scalar 3D texture backed by a regular grid of values, stretched over an
+/// axis-aligned box. Samples are trilinearly interpolated, and points outside
+/// the box sample as zero.
+#[derive(Debug)]
+pub struct VoxelGridTexture {
+    size: [usize; 3],
+    data: Vec<f64>,
+    bounds: AxisAlignedBoundingBox,
+}
+
+impl VoxelGridTexture {
+    /// # Panics
+    /// Panics if a dimension is zero, or if `data` doesn't hold exactly one
+    /// value per voxel.
+    pub fn new(size: [usize; 3], data: Vec<f64>, bounds: AxisAlignedBoundingBox) -> Self {
+        let count = voxel_count(size).expect("Voxel grid size must be positive");
+        assert_eq!(data.len(), count, "Voxel grid data doesn't match grid size");
+
+        VoxelGridTexture { size, data, bounds }
+    }
+
+    /// Load a voxel grid from a text file.
+    ///
+    /// The file starts with the grid size as three integers `nx ny nz`,
+    /// followed by `nx * ny * nz` values separated by whitespace. Values are
+    /// ordered with x varying fastest, then y, then z.
+    pub fn from_file(
+        file_path: &str,
+        bounds: AxisAlignedBoundingBox,
+    ) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(file_path)?;
+        let mut values = contents
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .flat_map(|line| line.split_whitespace());
+
+        let mut size = [0; 3];
+        for dim in &mut size {
+            *dim = match values.next() {
+                Some(value) => value.parse::<usize>()?,
+                None => return Err(Box::new(ParseError::new("Voxel grid missing size"))),
+            };
+        }
+
+        let Some(count) = voxel_count(size) else {
+            return Err(Box::new(ParseError::new(
+                "Voxel grid size must be positive",
+            )));
+        };
+
+        let data = values
+            .map(|value| value.parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if data.len() != count {
+            return Err(Box::new(ParseError::new(
+                "Voxel grid data doesn't match grid size",
+            )));
+        }
+
+        Ok(VoxelGridTexture { size, data, bounds })
+    }
+
+    fn voxel(&self, x: usize, y: usize, z: usize) -> f64 {
+        self.data[(z * self.size[1] + y) * self.size[0] + x]
+    }
+}
+
+/// Number of voxels in a grid, or `None` if a dimension is zero or the count
+/// overflows.
+fn voxel_count(size: [usize; 3]) -> Option<usize> {
+    if size.contains(&0) {
+        return None;
+    }
+
+    size[0].checked_mul(size[1])?.checked_mul(size[2])
+}
+
+impl Sampler for VoxelGridTexture {
+    type Output = f64;
+
+    fn sample(&self, _: (f64, f64), p: &Point4) -> Self::Output {
+        let [b_min, b_max] = self.bounds;
+
+        // Position in grid space, with voxel centers at integer coordinates
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+        for i in 0..3 {
+            let rel = (p[i] - b_min[i]) / (b_max[i] - b_min[i]);
+            if !(0.0..=1.0).contains(&rel) {
+                return 0.0;
+            }
+
+            let max_idx = self.size[i] - 1;
+            let x = (rel * self.size[i] as f64 - 0.5).clamp(0.0, max_idx as f64);
+            base[i] = usize::min(x as usize, max_idx.saturating_sub(1));
+            frac[i] = if max_idx == 0 {
+                0.0
+            } else {
+                x - base[i] as f64
+            };
+        }
+
+        let next = |i: usize| usize::min(base[i] + 1, self.size[i] - 1);
+        let (x0, y0, z0) = (base[0], base[1], base[2]);
+        let (x1, y1, z1) = (next(0), next(1), next(2));
+        let (fx, fy, fz) = (frac[0], frac[1], frac[2]);
+
+        let lerp = |a: f64, b: f64, t: f64| a * (1.0 - t) + b * t;
+        let c00 = lerp(self.voxel(x0, y0, z0), self.voxel(x1, y0, z0), fx);
+        let c10 = lerp(self.voxel(x0, y1, z0), self.voxel(x1, y1, z0), fx);
+        let c01 = lerp(self.voxel(x0, y0, z1), self.voxel(x1, y0, z1), fx);
+        let c11 = lerp(self.voxel(x0, y1, z1), self.voxel(x1, y1, z1), fx);
+
+        lerp(lerp(c00, c10, fy), lerp(c01, c11, fy), fz)
+    }
+}
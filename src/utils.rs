@@ -48,6 +48,29 @@ pub fn parse_vec(str: &str) -> Result<[f64; 3], ParseError> {
     Ok([components[0], components[1], components[2]])
 }
 
+/// Parse an affine transform from its 16 comma-separated values, row by row.
+/// The last row must be `0,0,0,1`, and the matrix must be invertible.
+pub fn parse_transform(str: &str) -> Result<Mat4, ParseError> {
+    let values = str
+        .split(',')
+        .map(|x| x.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ParseError::new("Matrix values must be numbers"))?;
+    let Ok(values) = <[f64; 16]>::try_from(values) else {
+        return Err(ParseError::new("Matrix must have 16 values"));
+    };
+
+    if values[12..] != [0.0, 0.0, 0.0, 1.0] {
+        return Err(ParseError::new("Matrix last row must be 0,0,0,1"));
+    }
+    let matrix = Mat4::from_values(values);
+    if matrix.inverse().is_none() {
+        return Err(ParseError::new("Matrix must be invertible"));
+    }
+
+    Ok(matrix)
+}
+
 #[derive(Debug)]
 pub struct ParseError {
     message: String,
@@ -59,6 +82,11 @@ impl ParseError {
             message: message.to_owned(),
         }
     }
+
+    /// A new error, boxed for functions returning `Box<dyn Error>`.
+    pub fn boxed(message: &str) -> Box<dyn Error> {
+        Box::new(ParseError::new(message))
+    }
 }
 
 impl Display for ParseError {
@@ -96,6 +96,11 @@ impl Vec4 {
         self.0[2]
     }
 
+    /// Relative luminance of a linear RGB color (Rec. 709 weights).
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+    }
+
     // Utility functions
 
     pub fn length(&self) -> f64 {